@@ -1,17 +1,46 @@
 use shared::codec::dummy::PassthroughCodec;
+use shared::codec::h264::{parse_annex_b, salvage_access_unit};
+use shared::codec::record::{FileRecorder, RecordFormat};
 use shared::codec::types::EncodedFrame;
 use shared::codec::VideoDecoder;
-use shared::core::packet_codec::decode_packet;
-use shared::core::reassembler::{FrameReassembler, ReassemblyError};
+use shared::core::control::{ControlMessage, ReceiverReport, MAX_NACK_ENTRIES};
+use shared::core::decode_order::{DecodeOrder, DecodeOrderConfig};
+use shared::core::healthcheck::{HealthcheckKind, HealthcheckPacket};
+use shared::core::jitter_buffer::{JitterBuffer, JitterBufferConfig, PlayoutMode};
+use shared::core::keyframe::KeyframeRequestThrottle;
+use shared::core::nack::{NackConfig, NackTracker};
+use shared::core::packet_codec::VIDEO_PACKET_OVERHEAD;
+use shared::core::reassembler::{
+    FrameReassembler, PartialFrame, ReassembledFrame, ReassemblyError,
+};
+use shared::core::reception::ReceptionTracker;
+use shared::transport::demux::{Demultiplexer, DemuxError, Message};
+use shared::transport::pcap::{CaptureTransport, PcapReader, PcapWriter};
+use shared::transport::replay::{Replay, ReplayEvent, ReplayPacer, ReplaySpeed};
+use shared::transport::tcp::TcpTransport;
 use shared::transport::udp::UdpTransport;
-use shared::transport::PacketReceiver;
-use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use shared::transport::{PacketReceiver, PacketSender};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener};
+use std::path::PathBuf;
 use std::time::{Duration, Instant};
 
+#[cfg(target_os = "macos")]
+use shared::codec::h264::{AccessUnitInfo, SequenceParameterSet};
 #[cfg(target_os = "macos")]
 use shared::codec::macos::h264::VideoToolboxH264Decoder;
 #[cfg(target_os = "macos")]
 use shared::platform::macos::network::detect_preferred_interface;
+#[cfg(feature = "quic")]
+use shared::transport::quic::{format_fingerprint, QuicIdentity, QuicListener};
+
+const PING_INTERVAL: Duration = Duration::from_secs(1);
+const RECEIVE_POLL_INTERVAL: Duration = Duration::from_millis(2);
+const KEYFRAME_REQUEST_INTERVAL: Duration = Duration::from_millis(250);
+const RECEIVER_REPORT_INTERVAL: Duration = Duration::from_millis(250);
+const MAX_UDP_DATAGRAM_BYTES: usize = 65_507;
+const RECEIVE_BATCH_DATAGRAMS: usize = 32;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum CodecChoice {
@@ -19,12 +48,29 @@ enum CodecChoice {
     H264,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TransportChoice {
+    Udp,
+    Tcp,
+    #[cfg(feature = "quic")]
+    Quic,
+}
+
 #[derive(Debug)]
 struct ClientConfig {
     bind_address: SocketAddr,
     remote_address: SocketAddr,
-    max_packet_bytes: usize,
+    max_packet_bytes: Option<usize>,
     max_in_flight_frames: usize,
+    frame_deadline: Option<Duration>,
+    playout: PlayoutMode,
+    nack: bool,
+    partial_frames: bool,
+    record_path: Option<PathBuf>,
+    capture_path: Option<PathBuf>,
+    replay_path: Option<PathBuf>,
+    replay_speed: ReplaySpeed,
+    transport: TransportChoice,
     codec: CodecChoice,
 }
 
@@ -38,19 +84,108 @@ fn main() {
         }
     };
 
-    if let Err(error) = run_client(config) {
+    let result = if config.replay_path.is_some() {
+        run_replay(config)
+    } else {
+        run_client(config)
+    };
+    if let Err(error) = result {
         eprintln!("client error: {error}");
         std::process::exit(1);
     }
 }
 
 fn run_client(config: ClientConfig) -> Result<(), Box<dyn std::error::Error>> {
-    let transport = UdpTransport::bind(config.bind_address)?.connect(config.remote_address)?;
-    transport.set_read_timeout(Some(Duration::from_millis(250)))?;
-    let mut receiver = transport;
+    match config.transport {
+        TransportChoice::Udp => {
+            let transport =
+                UdpTransport::bind(config.bind_address)?.connect(config.remote_address)?;
+            transport.set_read_timeout(Some(RECEIVE_POLL_INTERVAL))?;
+            let local_address = transport.local_addr()?;
+            let remote_address = config.remote_address;
+            run_capturing(config, transport, local_address, remote_address)
+        }
+        TransportChoice::Tcp => {
+            let listener = TcpListener::bind(config.bind_address)?;
+            eprintln!(
+                "waiting for the host to connect on {} over TCP",
+                listener.local_addr()?
+            );
+            let (stream, remote_address) = listener.accept()?;
+            eprintln!("host connected from {remote_address}");
+            let transport = TcpTransport::new(stream)?;
+            transport.set_read_timeout(Some(RECEIVE_POLL_INTERVAL))?;
+            let local_address = transport.local_addr()?;
+            run_capturing(config, transport, local_address, remote_address)
+        }
+        #[cfg(feature = "quic")]
+        TransportChoice::Quic => {
+            let identity = QuicIdentity::generate()?;
+            let listener = QuicListener::bind(config.bind_address, &identity)?;
+            eprintln!(
+                "waiting for the host to connect on {} over QUIC",
+                listener.local_addr()?
+            );
+            eprintln!(
+                "certificate fingerprint: {}",
+                format_fingerprint(&identity.fingerprint())
+            );
+            let transport = listener.accept()?;
+            let remote_address = transport.remote_addr();
+            eprintln!("host connected from {remote_address}");
+            transport.set_read_timeout(Some(RECEIVE_POLL_INTERVAL))?;
+            let local_address = transport.local_addr()?;
+            run_capturing(config, transport, local_address, remote_address)
+        }
+    }
+}
+
+fn run_capturing<T: PacketReceiver + PacketSender>(
+    config: ClientConfig,
+    transport: T,
+    local_address: SocketAddr,
+    remote_address: SocketAddr,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match &config.capture_path {
+        Some(path) => {
+            let writer = PcapWriter::new(BufWriter::new(File::create(path)?))?;
+            eprintln!("capturing received datagrams to {}", path.display());
+            run_session(
+                config,
+                CaptureTransport::new(transport, writer, local_address, remote_address),
+            )
+        }
+        None => run_session(config, transport),
+    }
+}
+
+fn run_session<T: PacketReceiver + PacketSender>(
+    config: ClientConfig,
+    transport: T,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut receiver = Demultiplexer::new(
+        transport,
+        config.max_packet_bytes.unwrap_or(MAX_UDP_DATAGRAM_BYTES),
+    )
+    .with_receive_batch(RECEIVE_BATCH_DATAGRAMS);
 
     let mut reassembler = FrameReassembler::new(config.max_in_flight_frames);
-    let mut buffer = vec![0_u8; config.max_packet_bytes];
+    if let Some(frame_deadline) = config.frame_deadline {
+        reassembler = reassembler.with_frame_deadline(frame_deadline);
+    }
+    if config.partial_frames {
+        reassembler = reassembler.with_partial_frames();
+    }
+    let mut decode_order = DecodeOrder::new(DecodeOrderConfig::default());
+    let mut link = LinkHealth::new(config.nack, config.max_packet_bytes.is_none());
+    let mut playout = JitterBuffer::new(
+        JitterBufferConfig {
+            mode: config.playout,
+            ..JitterBufferConfig::default()
+        },
+        Instant::now(),
+    );
+    let mut recorder = open_recorder(&config)?;
 
     let mut last_report = Instant::now();
     let mut frames_received: u64 = 0;
@@ -58,85 +193,611 @@ fn run_client(config: ClientConfig) -> Result<(), Box<dyn std::error::Error>> {
 
     match config.codec {
         CodecChoice::Passthrough => {
-            let mut decoder = PassthroughCodec::default();
+            let mut decoder = PassthroughCodec;
             loop {
-                if let Some(frame) = receive_frame(&mut receiver, &mut buffer, &mut reassembler, &mut packets_received) {
-                    let encoded = EncodedFrame {
-                        timestamp: Duration::from_nanos(frame.timestamp_nanos),
-                        data: frame.payload,
-                        is_keyframe: true,
-                    };
-                    let _ = decoder.decode(&encoded);
+                if let Some(frame) = receive_frame(
+                    &mut receiver,
+                    &mut reassembler,
+                    &mut decode_order,
+                    &mut link,
+                    &mut packets_received,
+                )? {
+                    decode_order.push(frame, Instant::now());
+                }
+                release_in_order(
+                    &mut decode_order,
+                    &mut reassembler,
+                    &mut link,
+                    &mut playout,
+                    &mut recorder,
+                );
+
+                while let Some(encoded) =
+                    next_playout_frame(&mut playout, &mut link, Instant::now(), |_| true)
+                {
+                    if decoder.decode(&encoded).is_err() {
+                        link.keyframe_requests.request();
+                    }
                     frames_received += 1;
                 }
 
-                report(&mut last_report, &mut frames_received, &mut packets_received);
+                report(
+                    &mut last_report,
+                    &mut frames_received,
+                    &mut packets_received,
+                    &mut link,
+                    &reassembler,
+                    &decode_order,
+                    &playout,
+                );
             }
         }
         CodecChoice::H264 => {
             #[cfg(target_os = "macos")]
             {
                 let mut decoder = VideoToolboxH264Decoder::new()?;
+                let mut stream_format = None;
                 loop {
-                    if let Some(frame) = receive_frame(&mut receiver, &mut buffer, &mut reassembler, &mut packets_received) {
-                        let encoded = EncodedFrame {
-                            timestamp: Duration::from_nanos(frame.timestamp_nanos),
-                            data: frame.payload,
-                            is_keyframe: true,
-                        };
-                        let _ = decoder.decode(&encoded);
+                    if let Some(frame) = receive_frame(
+                        &mut receiver,
+                        &mut reassembler,
+                        &mut decode_order,
+                        &mut link,
+                        &mut packets_received,
+                    )? {
+                        decode_order.push(frame, Instant::now());
+                    }
+                    release_in_order(
+                        &mut decode_order,
+                        &mut reassembler,
+                        &mut link,
+                        &mut playout,
+                        &mut recorder,
+                    );
+
+                    while let Some(encoded) =
+                        next_playout_frame(&mut playout, &mut link, Instant::now(), |payload| {
+                            inspect_h264(payload, &mut stream_format)
+                        })
+                    {
+                        if decoder.decode(&encoded).is_err() {
+                            link.keyframe_requests.request();
+                        }
                         frames_received += 1;
                     }
 
-                    report(&mut last_report, &mut frames_received, &mut packets_received);
+                    report(
+                        &mut last_report,
+                        &mut frames_received,
+                        &mut packets_received,
+                        &mut link,
+                        &reassembler,
+                        &decode_order,
+                        &playout,
+                    );
                 }
             }
             #[cfg(not(target_os = "macos"))]
             {
-                return Err("H.264 codec is only supported on macOS".into());
+                Err("H.264 codec is only supported on macOS".into())
             }
         }
     }
 }
 
-fn receive_frame(
-    receiver: &mut UdpTransport,
-    buffer: &mut [u8],
+fn run_replay(config: ClientConfig) -> Result<(), Box<dyn std::error::Error>> {
+    match config.codec {
+        CodecChoice::Passthrough => replay_capture(&config, &mut PassthroughCodec, |_| true),
+        CodecChoice::H264 => {
+            #[cfg(target_os = "macos")]
+            {
+                let mut decoder = VideoToolboxH264Decoder::new()?;
+                let mut stream_format = None;
+                replay_capture(&config, &mut decoder, |payload| {
+                    inspect_h264(payload, &mut stream_format)
+                })
+            }
+            #[cfg(not(target_os = "macos"))]
+            {
+                Err("H.264 codec is only supported on macOS".into())
+            }
+        }
+    }
+}
+
+fn replay_capture<D: VideoDecoder>(
+    config: &ClientConfig,
+    decoder: &mut D,
+    mut is_keyframe: impl FnMut(&[u8]) -> bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let path = config.replay_path.as_ref().ok_or("missing --replay")?;
+    let mut reader = PcapReader::new(BufReader::new(File::open(path)?))?;
+    let mut reassembler = FrameReassembler::new(config.max_in_flight_frames);
+    if let Some(frame_deadline) = config.frame_deadline {
+        reassembler = reassembler.with_frame_deadline(frame_deadline);
+    }
+    let mut replay = Replay::new(reassembler, DecodeOrder::new(DecodeOrderConfig::default()));
+    let mut pacer = ReplayPacer::new(config.replay_speed);
+    let mut recorder = open_recorder(config)?;
+    let mut decode_errors: u64 = 0;
+    eprintln!("replaying {}", path.display());
+
+    loop {
+        let datagram = reader.next_datagram()?;
+        let events = match &datagram {
+            Some(datagram) => {
+                std::thread::sleep(pacer.wait_time(datagram.timestamp, Instant::now()));
+                replay.push(datagram)
+            }
+            None => replay.finish(),
+        };
+        for event in events {
+            match event {
+                ReplayEvent::Frame(ordered) => {
+                    if ordered.missing_before > 0 {
+                        eprintln!(
+                            "frame {} follows {} missing frame(s)",
+                            ordered.frame.frame_identifier, ordered.missing_before
+                        );
+                    }
+                    record_frame(&mut recorder, &ordered.frame);
+                    let encoded = EncodedFrame {
+                        timestamp: Duration::from_nanos(ordered.frame.timestamp_nanos),
+                        is_keyframe: is_keyframe(&ordered.frame.payload),
+                        data: ordered.frame.payload,
+                    };
+                    if decoder.decode(&encoded).is_err() {
+                        decode_errors += 1;
+                    }
+                }
+                ReplayEvent::Dropped(evicted) => eprintln!(
+                    "dropped frame {}: {}/{} chunks ({})",
+                    evicted.frame_identifier,
+                    evicted.chunks_received,
+                    evicted.chunks_total,
+                    evicted.reason
+                ),
+                ReplayEvent::Rejected(_) => {}
+            }
+        }
+        if datagram.is_none() {
+            break;
+        }
+    }
+
+    let capture = reader.stats();
+    let stats = replay.stats();
+    let reassembly = replay.reassembler().stats();
+    let order = replay.decode_order().stats();
+    eprintln!(
+        "replayed {} datagrams: video {}, other {}, rejected {}, skipped records {}",
+        stats.datagrams, stats.video_packets, stats.other_messages, stats.rejected, capture.skipped
+    );
+    eprintln!(
+        "frames: released {}, dropped {}, decode errors {}, recovered chunks {}, duplicate chunks {}, skipped in decode order {}",
+        stats.frames_released, stats.frames_dropped, decode_errors, reassembly.recovered_chunks, reassembly.duplicate_chunks, order.skipped
+    );
+    Ok(())
+}
+
+fn open_recorder(
+    config: &ClientConfig,
+) -> Result<Option<FileRecorder>, Box<dyn std::error::Error>> {
+    let Some(path) = &config.record_path else {
+        return Ok(None);
+    };
+    let recorder = FileRecorder::create(path)?;
+    eprintln!("recording to {} ({})", path.display(), recorder.format());
+    Ok(Some(recorder))
+}
+
+fn record_frame(recorder: &mut Option<FileRecorder>, frame: &ReassembledFrame) {
+    if let Some(active) = recorder {
+        if let Err(error) = active.record(frame.timestamp_nanos, &frame.payload) {
+            eprintln!("recording stopped: {error}");
+            *recorder = None;
+        }
+    }
+}
+
+#[derive(Debug)]
+struct LinkHealth {
+    last_ping: Option<Instant>,
+    last_rtt: Option<Duration>,
+    nack: Option<NackTracker>,
+    packets_nacked: u64,
+    keyframe_requests: KeyframeRequestThrottle,
+    keyframes_requested: u64,
+    reception: ReceptionTracker,
+    bytes_received: u64,
+    previous_transit_nanos: Option<i64>,
+    receiver_report_sent: Instant,
+    receiver_report: ReceiverReport,
+    follow_path_mtu: bool,
+}
+
+impl LinkHealth {
+    fn new(nack: bool, follow_path_mtu: bool) -> Self {
+        Self {
+            last_ping: None,
+            last_rtt: None,
+            nack: nack.then(|| NackTracker::new(NackConfig::default())),
+            packets_nacked: 0,
+            keyframe_requests: KeyframeRequestThrottle::new(KEYFRAME_REQUEST_INTERVAL),
+            keyframes_requested: 0,
+            reception: ReceptionTracker::new(Instant::now()),
+            bytes_received: 0,
+            previous_transit_nanos: None,
+            receiver_report_sent: Instant::now(),
+            receiver_report: ReceiverReport::default(),
+            follow_path_mtu,
+        }
+    }
+
+    fn take_receiver_report(&mut self) -> Option<ReceiverReport> {
+        let elapsed = self.receiver_report_sent.elapsed();
+        self.receiver_report_sent = Instant::now();
+        let bytes_received = std::mem::take(&mut self.bytes_received);
+        let interval = self.reception.take_interval();
+        if interval.expected == 0 {
+            return None;
+        }
+
+        let delay_trend_nanos = match (self.previous_transit_nanos, interval.mean_transit_nanos) {
+            (Some(previous), Some(current)) => current - previous,
+            _ => 0,
+        };
+        if interval.mean_transit_nanos.is_some() {
+            self.previous_transit_nanos = interval.mean_transit_nanos;
+        }
+
+        let stats = self.reception.stats();
+        let received_bitrate =
+            (bytes_received * 8) as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+        self.receiver_report = ReceiverReport {
+            fraction_lost: interval.fraction_lost,
+            cumulative_lost: saturate_i32(stats.cumulative_lost),
+            extended_highest_sequence: stats.extended_highest_sequence as u32,
+            jitter_micros: stats.jitter.as_micros().min(u128::from(u32::MAX)) as u32,
+            received_bitrate: received_bitrate.min(f64::from(u32::MAX)) as u32,
+            delay_trend_micros: saturate_i32(delay_trend_nanos / 1000),
+        };
+        Some(self.receiver_report)
+    }
+}
+
+fn receive_frame<T: PacketReceiver + PacketSender>(
+    receiver: &mut Demultiplexer<T>,
     reassembler: &mut FrameReassembler,
+    decode_order: &mut DecodeOrder,
+    link: &mut LinkHealth,
     packets_received: &mut u64,
-) -> Option<shared::core::reassembler::ReassembledFrame> {
-    match receiver.receive(buffer) {
-        Ok(bytes_received) => {
+) -> Result<Option<shared::core::reassembler::ReassembledFrame>, Box<dyn std::error::Error>> {
+    if link
+        .last_ping
+        .is_none_or(|sent| sent.elapsed() >= PING_INTERVAL)
+    {
+        let ping = HealthcheckPacket {
+            kind: HealthcheckKind::Ping,
+            timestamp_nanos: current_time_nanos(),
+        };
+        if let Err(error) = receiver.send(&ping.encode()) {
+            if !error.is_transient() {
+                return Err(error.into());
+            }
+        }
+        link.last_ping = Some(Instant::now());
+    }
+
+    reassembler.expire(Instant::now());
+    handle_evictions(reassembler, decode_order, link);
+
+    if let Some(tracker) = link.nack.as_mut() {
+        let missing = tracker.poll(Instant::now());
+        for sequence_numbers in missing.chunks(MAX_NACK_ENTRIES) {
+            let nack = ControlMessage::Nack {
+                sequence_numbers: sequence_numbers.to_vec(),
+            };
+            match receiver.send(&nack.encode()) {
+                Ok(_) => link.packets_nacked += sequence_numbers.len() as u64,
+                Err(error) if error.is_transient() => {}
+                Err(error) => return Err(error.into()),
+            }
+        }
+    }
+
+    if link.keyframe_requests.poll(Instant::now()) {
+        match receiver.send(&ControlMessage::KeyframeRequest.encode()) {
+            Ok(_) => link.keyframes_requested += 1,
+            Err(error) if error.is_transient() => {}
+            Err(error) => return Err(error.into()),
+        }
+    }
+
+    if link.receiver_report_sent.elapsed() >= RECEIVER_REPORT_INTERVAL {
+        if let Some(report) = link.take_receiver_report() {
+            match receiver.send(&ControlMessage::ReceiverReport(report).encode()) {
+                Ok(_) => {}
+                Err(error) if error.is_transient() => {}
+                Err(error) => return Err(error.into()),
+            }
+        }
+    }
+
+    match receiver.receive() {
+        Ok(Message::Video(packet)) => {
             *packets_received += 1;
-            let packet = decode_packet(&buffer[..bytes_received]).ok()?;
-            match reassembler.push_packet(packet) {
-                Ok(Some(frame)) => Some(frame),
-                Ok(None) => None,
-                Err(ReassemblyError::InvalidChunkIndex) => None,
-                Err(ReassemblyError::InconsistentChunkCount) => None,
+            link.bytes_received += packet.payload.len() as u64;
+            link.reception.on_packet(&packet.header, Instant::now());
+            if let Some(tracker) = link.nack.as_mut() {
+                tracker.on_packet(&packet.header, Instant::now());
+            }
+            let result = reassembler.push_packet(packet);
+            handle_evictions(reassembler, decode_order, link);
+
+            match result {
+                Ok(Some(frame)) => {
+                    if let Some(tracker) = link.nack.as_mut() {
+                        tracker.on_frame_complete(frame.frame_identifier);
+                    }
+                    Ok(Some(frame))
+                }
+                Ok(None) => Ok(None),
+                Err(ReassemblyError::InvalidChunkIndex) => Ok(None),
+                Err(ReassemblyError::InconsistentChunkCount) => Ok(None),
+            }
+        }
+        Ok(Message::Healthcheck(packet)) => {
+            match packet.kind {
+                HealthcheckKind::Ping => {
+                    let response = HealthcheckPacket {
+                        kind: HealthcheckKind::Pong,
+                        timestamp_nanos: packet.timestamp_nanos,
+                    };
+                    let _ = receiver.send(&response.encode());
+                }
+                HealthcheckKind::Pong => {
+                    let elapsed_nanos = current_time_nanos().saturating_sub(packet.timestamp_nanos);
+                    link.last_rtt = Some(Duration::from_nanos(elapsed_nanos));
+                }
+            }
+            Ok(None)
+        }
+        Ok(Message::Control(ControlMessage::PathProbe { datagram_bytes })) => {
+            let ack = ControlMessage::PathProbeAck { datagram_bytes };
+            match receiver.send(&ack.encode()) {
+                Ok(_) => Ok(None),
+                Err(error) if error.is_transient() => Ok(None),
+                Err(error) => Err(error.into()),
             }
         }
-        Err(_) => None,
+        Ok(Message::Control(ControlMessage::PathMtu { datagram_bytes })) => {
+            let datagram_bytes = (datagram_bytes as usize).min(MAX_UDP_DATAGRAM_BYTES);
+            if link.follow_path_mtu && datagram_bytes > VIDEO_PACKET_OVERHEAD {
+                receiver.set_max_datagram_bytes(datagram_bytes);
+                eprintln!("path MTU: receiving datagrams up to {datagram_bytes} bytes");
+            }
+            Ok(None)
+        }
+        Ok(Message::Control(_)) => Ok(None),
+        Err(error) if error.is_version_mismatch() => {
+            Err(format!("host speaks a different protocol: {error}").into())
+        }
+        Err(DemuxError::Transport(error)) if error.is_transient() => Ok(None),
+        Err(DemuxError::Transport(error)) => Err(error.into()),
+        Err(_) => Ok(None),
     }
 }
 
-fn report(last_report: &mut Instant, frames_received: &mut u64, packets_received: &mut u64) {
+fn handle_evictions(
+    reassembler: &mut FrameReassembler,
+    decode_order: &mut DecodeOrder,
+    link: &mut LinkHealth,
+) {
+    let mut partial_frames = reassembler.take_partial_frames();
+    for evicted in reassembler.take_evicted() {
+        eprintln!(
+            "dropped frame {}: {}/{} chunks ({})",
+            evicted.frame_identifier, evicted.chunks_received, evicted.chunks_total, evicted.reason
+        );
+        match salvage_partial_frame(&mut partial_frames, evicted.frame_identifier) {
+            Some(frame) => {
+                eprintln!(
+                    "salvaged {} bytes of frame {}",
+                    frame.payload.len(),
+                    frame.frame_identifier
+                );
+                decode_order.push(frame, Instant::now());
+            }
+            None => decode_order.give_up(evicted.frame_identifier),
+        }
+        if let Some(tracker) = link.nack.as_mut() {
+            tracker.on_frame_complete(evicted.frame_identifier);
+        }
+        link.keyframe_requests.request();
+    }
+}
+
+fn salvage_partial_frame(
+    partial_frames: &mut Vec<PartialFrame>,
+    frame_identifier: u32,
+) -> Option<ReassembledFrame> {
+    let position = partial_frames
+        .iter()
+        .position(|partial| partial.frame_identifier == frame_identifier)?;
+    let partial = partial_frames.swap_remove(position);
+    let chunks: Vec<Option<&[u8]>> = partial
+        .chunks
+        .iter()
+        .map(|chunk| chunk.as_deref())
+        .collect();
+    let payload = salvage_access_unit(&chunks);
+    let has_slice = parse_annex_b(&payload).is_ok_and(|nal_units| {
+        nal_units
+            .iter()
+            .any(|nal_unit| nal_unit.unit_type.is_slice())
+    });
+    has_slice.then_some(ReassembledFrame {
+        frame_identifier,
+        timestamp_nanos: partial.timestamp_nanos,
+        payload,
+    })
+}
+
+fn release_in_order(
+    decode_order: &mut DecodeOrder,
+    reassembler: &mut FrameReassembler,
+    link: &mut LinkHealth,
+    playout: &mut JitterBuffer,
+    recorder: &mut Option<FileRecorder>,
+) {
+    while let Some(ordered) = decode_order.pop(Instant::now()) {
+        if ordered.missing_before > 0 {
+            eprintln!(
+                "frame {} follows {} missing frame(s)",
+                ordered.frame.frame_identifier, ordered.missing_before
+            );
+            link.keyframe_requests.request();
+        }
+        reassembler.supersede_older_than(ordered.frame.frame_identifier);
+        record_frame(recorder, &ordered.frame);
+        playout.push(ordered.frame, Instant::now());
+    }
+}
+
+fn next_playout_frame(
+    playout: &mut JitterBuffer,
+    link: &mut LinkHealth,
+    now: Instant,
+    mut is_keyframe: impl FnMut(&[u8]) -> bool,
+) -> Option<EncodedFrame> {
+    let played = playout.pop_ready(now)?;
+    let is_keyframe = is_keyframe(&played.frame.payload);
+    if played.dropped_before > 0 && !is_keyframe {
+        eprintln!(
+            "frame {} follows {} frame(s) dropped at playout",
+            played.frame.frame_identifier, played.dropped_before
+        );
+        link.keyframe_requests.request();
+    }
+    Some(EncodedFrame {
+        timestamp: Duration::from_nanos(played.frame.timestamp_nanos),
+        data: played.frame.payload,
+        is_keyframe,
+    })
+}
+
+fn report(
+    last_report: &mut Instant,
+    frames_received: &mut u64,
+    packets_received: &mut u64,
+    link: &mut LinkHealth,
+    reassembler: &FrameReassembler,
+    decode_order: &DecodeOrder,
+    playout: &JitterBuffer,
+) {
     if last_report.elapsed() >= Duration::from_secs(1) {
+        let rtt = match link.last_rtt {
+            Some(rtt) => format!("{:.2} ms", rtt.as_secs_f64() * 1000.0),
+            None => "n/a".to_string(),
+        };
+        eprintln!(
+            "frames received: {frames_received}, packets received: {packets_received}, packets nacked: {}, keyframes requested: {}, rtt: {rtt}",
+            link.packets_nacked, link.keyframes_requested
+        );
+        let reception = link.reception.stats();
+        let receiver_report = link.receiver_report;
+        eprintln!(
+            "link: loss {:.1}%, cumulative lost {}, reordered {}, duplicates {}, jitter {:.2} ms, received {} kbps, delay trend {:+.2} ms",
+            f64::from(receiver_report.fraction_lost) * 100.0 / 256.0,
+            reception.cumulative_lost,
+            reception.reordered,
+            reception.duplicates,
+            reception.jitter.as_secs_f64() * 1000.0,
+            receiver_report.received_bitrate / 1000,
+            f64::from(receiver_report.delay_trend_micros) / 1000.0
+        );
+        let stats = reassembler.stats();
+        eprintln!(
+            "reassembly totals: completed {}, evicted {}, recovered {}, duplicates {}, late {}/{} (completed/evicted), invalid index {}, inconsistent count {}, buffered {} bytes",
+            stats.completed_frames,
+            stats.evicted_frames,
+            stats.recovered_chunks,
+            stats.duplicate_chunks,
+            stats.late_chunks_completed,
+            stats.late_chunks_evicted,
+            stats.invalid_chunk_indices,
+            stats.inconsistent_chunk_counts,
+            stats.bytes_buffered
+        );
+        let order = decode_order.stats();
         eprintln!(
-            "frames received: {frames_received}, packets received: {packets_received}"
+            "decode order: released {}, skipped {}, stale {}, resynced {}",
+            order.released, order.skipped, order.stale, order.resynced
+        );
+        let playout = playout.stats();
+        eprintln!(
+            "playout: target delay {:.1} ms, buffered {}, played {}, late {}, dropped {}",
+            playout.target_delay.as_secs_f64() * 1000.0,
+            playout.frames_buffered,
+            playout.frames_played,
+            playout.frames_late,
+            playout.frames_dropped
         );
         *last_report = Instant::now();
         *frames_received = 0;
         *packets_received = 0;
+        link.packets_nacked = 0;
+        link.keyframes_requested = 0;
     }
 }
 
+#[cfg(target_os = "macos")]
+fn inspect_h264(payload: &[u8], stream_format: &mut Option<SequenceParameterSet>) -> bool {
+    let info =
+        match parse_annex_b(payload).and_then(|nal_units| AccessUnitInfo::inspect(&nal_units)) {
+            Ok(info) => info,
+            Err(error) => {
+                eprintln!("h264 parse error: {error}");
+                return false;
+            }
+        };
+    if let Some(sps) = info.sps {
+        if *stream_format != Some(sps) {
+            eprintln!("h264 stream: {sps}");
+            *stream_format = Some(sps);
+        }
+    }
+    info.is_keyframe
+}
+
+fn saturate_i32(value: i64) -> i32 {
+    value.clamp(i64::from(i32::MIN), i64::from(i32::MAX)) as i32
+}
+
+fn current_time_nanos() -> u64 {
+    let now = std::time::SystemTime::now();
+    let duration = now
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_else(|_| Duration::from_secs(0));
+    duration.as_nanos() as u64
+}
+
 fn parse_args() -> Result<ClientConfig, String> {
     let mut bind_address: Option<SocketAddr> = None;
     let mut remote_address: Option<SocketAddr> = None;
-    let mut max_packet_bytes: usize = 2048;
+    let mut max_packet_bytes: Option<usize> = None;
     let mut max_in_flight_frames: usize = 8;
+    let mut frame_deadline = Some(Duration::from_millis(100));
+    let mut playout = JitterBufferConfig::default().mode;
+    let mut nack = true;
+    let mut partial_frames = false;
+    let mut record_path: Option<PathBuf> = None;
+    let mut capture_path: Option<PathBuf> = None;
+    let mut replay_path: Option<PathBuf> = None;
+    let mut replay_speed = ReplaySpeed::default();
     let mut auto_bind_port: Option<u16> = None;
+    let mut transport = TransportChoice::Udp;
     let mut codec = CodecChoice::Passthrough;
 
     let mut args = std::env::args().skip(1);
@@ -152,20 +813,55 @@ fn parse_args() -> Result<ClientConfig, String> {
             }
             "--max-packet-bytes" => {
                 let value = args.next().ok_or("missing --max-packet-bytes value")?;
-                max_packet_bytes = value
-                    .parse()
-                    .map_err(|_| "invalid max packet bytes")?;
+                max_packet_bytes = Some(value.parse().map_err(|_| "invalid max packet bytes")?);
             }
             "--max-in-flight-frames" => {
                 let value = args.next().ok_or("missing --max-in-flight-frames value")?;
-                max_in_flight_frames = value
-                    .parse()
-                    .map_err(|_| "invalid max in flight frames")?;
+                max_in_flight_frames = value.parse().map_err(|_| "invalid max in flight frames")?;
+            }
+            "--frame-deadline-ms" => {
+                let value = args.next().ok_or("missing --frame-deadline-ms value")?;
+                let millis: u64 = value.parse().map_err(|_| "invalid frame deadline")?;
+                frame_deadline = (millis > 0).then(|| Duration::from_millis(millis));
+            }
+            "--playout" => {
+                let value = args.next().ok_or("missing --playout value")?;
+                playout = parse_playout(&value)?;
+            }
+            "--no-nack" => {
+                nack = false;
+            }
+            "--partial-frames" => {
+                partial_frames = true;
+            }
+            "--record" => {
+                let value = args.next().ok_or("missing --record value")?;
+                let path = PathBuf::from(value);
+                if RecordFormat::from_path(&path).is_none() {
+                    return Err("invalid record path (use a .h264, .264 or .mp4 file)".to_string());
+                }
+                record_path = Some(path);
+            }
+            "--capture" => {
+                let value = args.next().ok_or("missing --capture value")?;
+                capture_path = Some(PathBuf::from(value));
+            }
+            "--replay" => {
+                let value = args.next().ok_or("missing --replay value")?;
+                replay_path = Some(PathBuf::from(value));
+            }
+            "--replay-speed" => {
+                let value = args.next().ok_or("missing --replay-speed value")?;
+                replay_speed = parse_replay_speed(&value)?;
             }
             "--auto-bind-port" => {
                 let value = args.next().ok_or("missing --auto-bind-port value")?;
                 auto_bind_port = Some(value.parse().map_err(|_| "invalid port")?);
             }
+            "--transport" => {
+                let value = args.next().ok_or("missing --transport value")?;
+                transport = parse_transport(&value)?;
+            }
             "--codec" => {
                 let value = args.next().ok_or("missing --codec value")?;
                 codec = parse_codec(&value)?;
@@ -185,6 +881,12 @@ fn parse_args() -> Result<ClientConfig, String> {
         }
     }
 
+    if replay_path.is_some() {
+        let unspecified = SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0);
+        bind_address = bind_address.or(Some(unspecified));
+        remote_address = remote_address.or(Some(unspecified));
+    }
+
     let bind_address = bind_address.ok_or("missing --bind (or use --auto-bind-port)")?;
     let remote_address = remote_address.ok_or("missing --remote")?;
 
@@ -193,10 +895,52 @@ fn parse_args() -> Result<ClientConfig, String> {
         remote_address,
         max_packet_bytes,
         max_in_flight_frames,
+        frame_deadline,
+        playout,
+        nack,
+        partial_frames,
+        record_path,
+        capture_path,
+        replay_path,
+        replay_speed,
+        transport,
         codec,
     })
 }
 
+fn parse_playout(value: &str) -> Result<PlayoutMode, String> {
+    let invalid = || "invalid playout (use latest, adaptive or adaptive:MIN_MS:MAX_MS)".to_string();
+    match value {
+        "latest" => Ok(PlayoutMode::LatestFrame),
+        "adaptive" => Ok(JitterBufferConfig::default().mode),
+        _ => {
+            let bounds = value.strip_prefix("adaptive:").ok_or_else(invalid)?;
+            let (min_delay, max_delay) = bounds.split_once(':').ok_or_else(invalid)?;
+            let min_delay: u64 = min_delay.parse().map_err(|_| invalid())?;
+            let max_delay: u64 = max_delay.parse().map_err(|_| invalid())?;
+            if min_delay > max_delay {
+                return Err(invalid());
+            }
+            Ok(PlayoutMode::Adaptive {
+                min_delay: Duration::from_millis(min_delay),
+                max_delay: Duration::from_millis(max_delay),
+            })
+        }
+    }
+}
+
+fn parse_transport(value: &str) -> Result<TransportChoice, String> {
+    match value {
+        "udp" => Ok(TransportChoice::Udp),
+        "tcp" => Ok(TransportChoice::Tcp),
+        #[cfg(feature = "quic")]
+        "quic" => Ok(TransportChoice::Quic),
+        #[cfg(not(feature = "quic"))]
+        "quic" => Err("QUIC support is not built in (rebuild with --features quic)".to_string()),
+        _ => Err("invalid transport (use udp, tcp or quic)".to_string()),
+    }
+}
+
 fn parse_codec(value: &str) -> Result<CodecChoice, String> {
     match value {
         "passthrough" => Ok(CodecChoice::Passthrough),
@@ -205,6 +949,16 @@ fn parse_codec(value: &str) -> Result<CodecChoice, String> {
     }
 }
 
+fn parse_replay_speed(value: &str) -> Result<ReplaySpeed, String> {
+    if value == "max" {
+        return Ok(ReplaySpeed::Unpaced);
+    }
+    match value.parse::<f64>() {
+        Ok(factor) if factor > 0.0 && factor.is_finite() => Ok(ReplaySpeed::Scaled(factor)),
+        _ => Err("invalid replay speed (use a factor such as 1 or 4, or max)".to_string()),
+    }
+}
+
 fn auto_bind_socket(port: u16) -> Result<Option<SocketAddr>, String> {
     #[cfg(target_os = "macos")]
     {
@@ -213,10 +967,7 @@ fn auto_bind_socket(port: u16) -> Result<Option<SocketAddr>, String> {
                 "auto-bind selected interface {} with IPv4 {}",
                 interface.name, interface.ipv4
             );
-            return Ok(Some(SocketAddr::new(
-                IpAddr::V4(interface.ipv4),
-                port,
-            )));
+            return Ok(Some(SocketAddr::new(IpAddr::V4(interface.ipv4), port)));
         }
     }
 
@@ -232,6 +983,64 @@ fn parse_socket_addr(value: &str) -> Result<SocketAddr, String> {
 
 fn print_usage() {
     eprintln!(
-        "usage: client --bind IP:PORT --remote IP:PORT [--max-packet-bytes N] [--max-in-flight-frames N] [--auto-bind-port PORT] [--codec passthrough|h264]"
+        "usage: client --bind IP:PORT --remote IP:PORT [--max-packet-bytes N] [--max-in-flight-frames N] [--frame-deadline-ms N] [--playout latest|adaptive|adaptive:MIN_MS:MAX_MS] [--no-nack] [--partial-frames] [--record PATH.h264|PATH.mp4] [--capture PATH.pcap] [--auto-bind-port PORT] [--transport udp|tcp|quic] [--codec passthrough|h264]"
     );
+    eprintln!(
+        "       client --replay PATH.pcap [--replay-speed FACTOR|max] [--max-in-flight-frames N] [--frame-deadline-ms N] [--record PATH.h264|PATH.mp4] [--codec passthrough|h264]"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{next_playout_frame, LinkHealth};
+    use shared::core::jitter_buffer::{JitterBuffer, JitterBufferConfig, PlayoutMode};
+    use shared::core::reassembler::ReassembledFrame;
+    use std::time::Instant;
+
+    fn frame(frame_identifier: u32) -> ReassembledFrame {
+        ReassembledFrame {
+            frame_identifier,
+            timestamp_nanos: 1_000_000_000 + u64::from(frame_identifier) * 16_666_667,
+            payload: vec![frame_identifier as u8; 32],
+        }
+    }
+
+    fn latest_frame_buffer(now: Instant) -> JitterBuffer {
+        JitterBuffer::new(
+            JitterBufferConfig {
+                mode: PlayoutMode::LatestFrame,
+                ..JitterBufferConfig::default()
+            },
+            now,
+        )
+    }
+
+    #[test]
+    fn dropping_a_frame_at_playout_requests_a_keyframe() {
+        let now = Instant::now();
+        let mut playout = latest_frame_buffer(now);
+        let mut link = LinkHealth::new(false, false);
+        playout.push(frame(0), now);
+        playout.push(frame(1), now);
+
+        let encoded = next_playout_frame(&mut playout, &mut link, now, |_| false)
+            .expect("newest frame plays");
+        assert_eq!(encoded.data, frame(1).payload);
+        assert!(!encoded.is_keyframe);
+        assert!(link.keyframe_requests.poll(now));
+    }
+
+    #[test]
+    fn keyframe_after_playout_drop_needs_no_request() {
+        let now = Instant::now();
+        let mut playout = latest_frame_buffer(now);
+        let mut link = LinkHealth::new(false, false);
+        playout.push(frame(0), now);
+        playout.push(frame(1), now);
+
+        let encoded =
+            next_playout_frame(&mut playout, &mut link, now, |_| true).expect("newest frame plays");
+        assert!(encoded.is_keyframe);
+        assert!(!link.keyframe_requests.poll(now));
+    }
 }
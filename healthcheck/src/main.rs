@@ -82,18 +82,15 @@ fn run_ping(
             last_send = Instant::now();
         }
 
-        match transport.receive(&mut buffer) {
-            Ok(bytes_received) => {
-                if let Ok(packet) = HealthcheckPacket::decode(&buffer[..bytes_received]) {
-                    if packet.kind == HealthcheckKind::Pong {
-                        let now = current_time_nanos();
-                        let elapsed_nanos = now.saturating_sub(packet.timestamp_nanos);
-                        let elapsed_ms = elapsed_nanos as f64 / 1_000_000.0;
-                        eprintln!("pong in {elapsed_ms:.2} ms");
-                    }
+        if let Ok(bytes_received) = transport.receive(&mut buffer) {
+            if let Ok(packet) = HealthcheckPacket::decode(&buffer[..bytes_received]) {
+                if packet.kind == HealthcheckKind::Pong {
+                    let now = current_time_nanos();
+                    let elapsed_nanos = now.saturating_sub(packet.timestamp_nanos);
+                    let elapsed_ms = elapsed_nanos as f64 / 1_000_000.0;
+                    eprintln!("pong in {elapsed_ms:.2} ms");
                 }
             }
-            Err(_) => {}
         }
     }
 }
@@ -1,10 +1,24 @@
 use shared::codec::dummy::PassthroughCodec;
+use shared::codec::h264::{parse_annex_b, ParameterSets};
 use shared::codec::types::{PixelFormat, RawFrame};
 use shared::codec::VideoEncoder;
-use shared::core::packet_codec::encode_packet;
-use shared::core::packetizer::{Packetizer, PacketizerConfig};
+use shared::core::congestion::{CongestionConfig, CongestionController};
+use shared::core::control::ControlMessage;
+use shared::core::fec::FecScheme;
+use shared::core::healthcheck::{HealthcheckKind, HealthcheckPacket};
+use shared::core::keyframe::KeyframeRequestThrottle;
+use shared::core::packet_codec::{encode_packet, VIDEO_PACKET_OVERHEAD};
+use shared::core::packetizer::{Packetization, Packetizer, PacketizerConfig};
+use shared::core::path_mtu::{PathMtuConfig, PathMtuProber, ProbeStep};
+use shared::core::retransmit::RetransmissionBuffer;
+use shared::core::rtp::{h264_sdp, rtp_timestamp, RtpH264Config, RtpH264Packetizer};
 use shared::core::sequence::SequenceNumber;
+use shared::transport::batch::send_all;
+use shared::transport::demux::{Demultiplexer, DemuxError, Message};
+use shared::transport::pacer::{Pacer, PacerConfig};
+use shared::transport::tcp::TcpTransport;
 use shared::transport::udp::UdpTransport;
+use shared::transport::{PacketReceiver, PacketSender};
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::time::{Duration, Instant};
 
@@ -12,6 +26,13 @@ use std::time::{Duration, Instant};
 use shared::codec::macos::h264::VideoToolboxH264Encoder;
 #[cfg(target_os = "macos")]
 use shared::platform::macos::network::detect_preferred_interface;
+#[cfg(feature = "quic")]
+use shared::transport::quic::{parse_fingerprint, QuicTransport};
+
+const MAX_CONTROL_DATAGRAM_BYTES: usize = 2048;
+const RETRANSMIT_MAX_AGE: Duration = Duration::from_secs(1);
+const INCOMING_POLL_INTERVAL: Duration = Duration::from_millis(1);
+const PACING_SLACK: Duration = Duration::from_micros(500);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum CodecChoice {
@@ -19,18 +40,83 @@ enum CodecChoice {
     H264,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TransportChoice {
+    Udp,
+    Tcp,
+    #[cfg(feature = "quic")]
+    Quic,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WireFormat {
+    Native,
+    Rtp,
+}
+
+#[derive(Debug, Default)]
+struct HostStats {
+    frames_sent: u64,
+    packets_retransmitted: u64,
+    packets_dropped: u64,
+    keyframes_requested: u64,
+    receiver_reports: u64,
+    fraction_lost: u8,
+}
+
+struct Feedback {
+    retransmission: RetransmissionBuffer,
+    keyframe_requests: KeyframeRequestThrottle,
+    congestion: CongestionController,
+    path_mtu: Option<PathMtuProber>,
+    discovered_datagram_bytes: Option<usize>,
+    peer_seen: bool,
+    stats: HostStats,
+}
+
+enum VideoOutput {
+    Native(Packetizer),
+    Rtp(RtpOutput),
+}
+
+struct RtpOutput {
+    packetizer: RtpH264Packetizer,
+    payload_type: u8,
+    destination: SocketAddr,
+    sdp_path: Option<String>,
+    sdp_written: bool,
+}
+
 #[derive(Debug)]
 struct HostConfig {
     bind_address: SocketAddr,
     remote_address: SocketAddr,
     payload_bytes: usize,
     max_payload_bytes: usize,
+    fec: FecScheme,
+    packetization: Packetization,
+    wire: WireFormat,
+    sdp_path: Option<String>,
+    path_mtu: bool,
+    path_mtu_max_bytes: usize,
+    retransmit_packets: usize,
+    min_keyframe_interval: Duration,
+    pacing_fraction: f64,
+    pacing_burst_bytes: usize,
     frame_interval: Duration,
     no_sleep: bool,
+    segmentation_offload: bool,
+    transport: TransportChoice,
+    #[cfg(feature = "quic")]
+    quic_fingerprint: Option<[u8; 32]>,
     codec: CodecChoice,
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
     width: u32,
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
     height: u32,
     bitrate: u32,
+    min_bitrate: u32,
+    max_bitrate: u32,
 }
 
 fn main() {
@@ -50,22 +136,122 @@ fn main() {
 }
 
 fn run_host(config: HostConfig) -> Result<(), Box<dyn std::error::Error>> {
-    let mut sender = UdpTransport::bind(config.bind_address)?;
-    let mut packetizer = Packetizer::new(
-        PacketizerConfig {
-            max_payload_bytes: config.max_payload_bytes,
-        },
-        SequenceNumber::new(0),
-    );
+    match config.transport {
+        TransportChoice::Udp => run_udp_host(config),
+        TransportChoice::Tcp => {
+            let transport = TcpTransport::connect(config.remote_address)?;
+            transport.set_nonblocking(true)?;
+            eprintln!("connected to {} over TCP", config.remote_address);
+            run_stream(config, transport, None)
+        }
+        #[cfg(feature = "quic")]
+        TransportChoice::Quic => {
+            let fingerprint = config
+                .quic_fingerprint
+                .ok_or("--transport quic requires --quic-fingerprint")?;
+            let transport =
+                QuicTransport::connect(config.bind_address, config.remote_address, fingerprint)?;
+            transport.set_nonblocking(true)?;
+            eprintln!("connected to {} over QUIC", config.remote_address);
+            let max_datagram_bytes = transport
+                .max_datagram_bytes()
+                .ok_or("client does not accept QUIC datagrams")?;
+            let mut config = config;
+            config.max_payload_bytes = config
+                .max_payload_bytes
+                .min(max_datagram_bytes.saturating_sub(VIDEO_PACKET_OVERHEAD));
+            run_stream(config, transport, None)
+        }
+    }
+}
+
+fn run_udp_host(config: HostConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let mut transport = UdpTransport::bind(config.bind_address)?.connect(config.remote_address)?;
+    transport.set_nonblocking(true)?;
+    if config.segmentation_offload && transport.enable_segmentation_offload() {
+        eprintln!("UDP segmentation offload enabled");
+    }
+    let mut path_mtu = (config.path_mtu && config.wire == WireFormat::Native).then(|| {
+        PathMtuProber::new(PathMtuConfig {
+            min_datagram_bytes: config.max_payload_bytes + VIDEO_PACKET_OVERHEAD,
+            max_datagram_bytes: config.path_mtu_max_bytes,
+            ..PathMtuConfig::default()
+        })
+    });
+    if path_mtu.is_some() {
+        if let Err(error) = transport.set_dont_fragment(true) {
+            eprintln!("path MTU discovery disabled: {error}");
+            path_mtu = None;
+        }
+    }
+    run_stream(config, transport, path_mtu)
+}
+
+fn run_stream<T: PacketReceiver + PacketSender>(
+    config: HostConfig,
+    transport: T,
+    path_mtu: Option<PathMtuProber>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut sender = Demultiplexer::new(transport, MAX_CONTROL_DATAGRAM_BYTES);
+    let mut output = match config.wire {
+        WireFormat::Native => VideoOutput::Native(Packetizer::new(
+            PacketizerConfig {
+                max_payload_bytes: config.max_payload_bytes,
+                fec: config.fec,
+                packetization: config.packetization,
+            },
+            SequenceNumber::new(0),
+        )),
+        WireFormat::Rtp => {
+            let rtp_config = RtpH264Config {
+                max_payload_bytes: config.max_payload_bytes,
+                ..RtpH264Config::default()
+            };
+            VideoOutput::Rtp(RtpOutput {
+                packetizer: RtpH264Packetizer::new(rtp_config, 0),
+                payload_type: rtp_config.payload_type,
+                destination: config.remote_address,
+                sdp_path: config.sdp_path.clone(),
+                sdp_written: false,
+            })
+        }
+    };
+
+    let mut feedback = Feedback {
+        retransmission: RetransmissionBuffer::new(config.retransmit_packets, RETRANSMIT_MAX_AGE),
+        keyframe_requests: KeyframeRequestThrottle::new(config.min_keyframe_interval),
+        congestion: CongestionController::new(
+            CongestionConfig {
+                min_bitrate: config.min_bitrate,
+                max_bitrate: config.max_bitrate,
+                ..CongestionConfig::default()
+            },
+            config.bitrate,
+        ),
+        path_mtu,
+        discovered_datagram_bytes: None,
+        peer_seen: false,
+        stats: HostStats::default(),
+    };
+    let mut applied_bitrate = config.bitrate;
+
+    let mut pacer = (config.pacing_fraction > 0.0).then(|| {
+        Pacer::new(PacerConfig {
+            target_bitrate: u64::from(config.bitrate),
+            burst_bytes: config.pacing_burst_bytes,
+            frame_interval: config.frame_interval,
+            spread_fraction: config.pacing_fraction,
+        })
+    });
 
     let mut frame_identifier: u32 = 0;
     let mut last_report = Instant::now();
-    let mut frames_sent: u64 = 0;
 
     match config.codec {
         CodecChoice::Passthrough => {
-            let mut encoder = PassthroughCodec::default();
+            let mut encoder = PassthroughCodec;
             loop {
+                let frame_deadline = next_frame_deadline(&config);
                 let timestamp_nanos = current_time_nanos();
                 let raw_frame = RawFrame {
                     width: 1,
@@ -75,37 +261,49 @@ fn run_host(config: HostConfig) -> Result<(), Box<dyn std::error::Error>> {
                     data: vec![0xAB; config.payload_bytes],
                 };
 
+                apply_target_bitrate(
+                    &mut encoder,
+                    pacer.as_mut(),
+                    &feedback,
+                    &mut applied_bitrate,
+                )?;
+                if feedback.keyframe_requests.poll(Instant::now()) {
+                    encoder.request_keyframe();
+                }
                 let encoded = encoder.encode(&raw_frame)?;
-                send_encoded(
+                if encoded.is_keyframe {
+                    feedback.keyframe_requests.on_keyframe(Instant::now());
+                }
+                send_frame(
                     &mut sender,
-                    config.remote_address,
-                    &mut packetizer,
+                    &mut output,
+                    pacer.as_mut(),
+                    &mut feedback,
                     frame_identifier,
                     timestamp_nanos,
                     &encoded.data,
                 )?;
 
-                frames_sent += 1;
+                feedback.stats.frames_sent += 1;
                 frame_identifier = frame_identifier.wrapping_add(1);
-                report(&mut last_report, &mut frames_sent);
-                if !config.no_sleep {
-                    std::thread::sleep(config.frame_interval);
-                }
+                report(
+                    &mut last_report,
+                    &mut feedback.stats,
+                    feedback.congestion.target_bitrate(),
+                );
+                service_until(&mut sender, &mut feedback, frame_deadline)?;
             }
         }
         CodecChoice::H264 => {
             #[cfg(target_os = "macos")]
             {
                 let fps = frame_rate_from_interval(config.frame_interval);
-                let mut encoder = VideoToolboxH264Encoder::new(
-                    config.width,
-                    config.height,
-                    config.bitrate,
-                    fps,
-                )?;
+                let mut encoder =
+                    VideoToolboxH264Encoder::new(config.width, config.height, config.bitrate, fps)?;
 
                 let raw_size = (config.width as usize) * (config.height as usize) * 4;
                 loop {
+                    let frame_deadline = next_frame_deadline(&config);
                     let timestamp_nanos = current_time_nanos();
                     let raw_frame = RawFrame {
                         width: config.width,
@@ -115,53 +313,337 @@ fn run_host(config: HostConfig) -> Result<(), Box<dyn std::error::Error>> {
                         data: vec![0x7F; raw_size],
                     };
 
+                    apply_target_bitrate(
+                        &mut encoder,
+                        pacer.as_mut(),
+                        &feedback,
+                        &mut applied_bitrate,
+                    )?;
+                    if feedback.keyframe_requests.poll(Instant::now()) {
+                        encoder.request_keyframe();
+                    }
                     let encoded = encoder.encode(&raw_frame)?;
-                    send_encoded(
+                    if encoded.is_keyframe {
+                        feedback.keyframe_requests.on_keyframe(Instant::now());
+                    }
+                    send_frame(
                         &mut sender,
-                        config.remote_address,
-                        &mut packetizer,
+                        &mut output,
+                        pacer.as_mut(),
+                        &mut feedback,
                         frame_identifier,
                         timestamp_nanos,
                         &encoded.data,
                     )?;
 
-                    frames_sent += 1;
+                    feedback.stats.frames_sent += 1;
                     frame_identifier = frame_identifier.wrapping_add(1);
-                    report(&mut last_report, &mut frames_sent);
-                    if !config.no_sleep {
-                        std::thread::sleep(config.frame_interval);
-                    }
+                    report(
+                        &mut last_report,
+                        &mut feedback.stats,
+                        feedback.congestion.target_bitrate(),
+                    );
+                    service_until(&mut sender, &mut feedback, frame_deadline)?;
                 }
             }
             #[cfg(not(target_os = "macos"))]
             {
-                return Err("H.264 codec is only supported on macOS".into());
+                Err("H.264 codec is only supported on macOS".into())
             }
         }
     }
 }
 
-fn send_encoded(
-    sender: &mut UdpTransport,
-    remote_address: SocketAddr,
+fn send_frame<T: PacketReceiver + PacketSender>(
+    sender: &mut Demultiplexer<T>,
+    output: &mut VideoOutput,
+    pacer: Option<&mut Pacer>,
+    feedback: &mut Feedback,
+    frame_identifier: u32,
+    timestamp_nanos: u64,
+    payload: &[u8],
+) -> Result<(), Box<dyn std::error::Error>> {
+    match output {
+        VideoOutput::Native(packetizer) => send_encoded(
+            sender,
+            packetizer,
+            pacer,
+            feedback,
+            frame_identifier,
+            timestamp_nanos,
+            payload,
+        ),
+        VideoOutput::Rtp(rtp) => send_rtp(sender, rtp, pacer, feedback, timestamp_nanos, payload),
+    }
+}
+
+fn send_encoded<T: PacketReceiver + PacketSender>(
+    sender: &mut Demultiplexer<T>,
     packetizer: &mut Packetizer,
+    mut pacer: Option<&mut Pacer>,
+    feedback: &mut Feedback,
     frame_identifier: u32,
     timestamp_nanos: u64,
     payload: &[u8],
 ) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(datagram_bytes) = feedback.discovered_datagram_bytes.take() {
+        let max_payload_bytes = datagram_bytes - VIDEO_PACKET_OVERHEAD;
+        packetizer.set_max_payload_bytes(max_payload_bytes);
+        eprintln!("path MTU: {datagram_bytes} byte datagrams, {max_payload_bytes} byte payloads");
+    }
+
     let packets = packetizer.packetize(frame_identifier, timestamp_nanos, payload)?;
-    for packet in packets {
-        let buffer = encode_packet(&packet);
-        sender.send_to(&buffer, remote_address)?;
+    let datagrams: Vec<_> = packets
+        .iter()
+        .map(|packet| (packet.header.sequence_number, encode_packet(packet)))
+        .collect();
+    if let Some(pacer) = pacer.as_deref_mut() {
+        pacer.begin_frame(datagrams.iter().map(|(_, buffer)| buffer.len()).sum());
+    }
+
+    let mut batch = Vec::with_capacity(datagrams.len());
+    for (sequence_number, buffer) in datagrams {
+        if let Some(send_at) = pacing_deadline(pacer.as_deref_mut(), buffer.len()) {
+            flush_encoded(sender, feedback, &mut batch)?;
+            service_until(sender, feedback, send_at)?;
+        }
+        batch.push((sequence_number, buffer));
+    }
+    flush_encoded(sender, feedback, &mut batch)
+}
+
+fn flush_encoded<T: PacketReceiver + PacketSender>(
+    sender: &mut Demultiplexer<T>,
+    feedback: &mut Feedback,
+    batch: &mut Vec<(SequenceNumber, Vec<u8>)>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let packets: Vec<&[u8]> = batch.iter().map(|(_, buffer)| buffer.as_slice()).collect();
+    feedback.stats.packets_dropped += send_all(sender, &packets)? as u64;
+    let now = Instant::now();
+    for (sequence_number, buffer) in std::mem::take(batch) {
+        feedback.retransmission.insert(sequence_number, buffer, now);
+    }
+    Ok(())
+}
+
+fn send_rtp<T: PacketReceiver + PacketSender>(
+    sender: &mut Demultiplexer<T>,
+    rtp: &mut RtpOutput,
+    mut pacer: Option<&mut Pacer>,
+    feedback: &mut Feedback,
+    timestamp_nanos: u64,
+    payload: &[u8],
+) -> Result<(), Box<dyn std::error::Error>> {
+    if !rtp.sdp_written {
+        write_sdp(rtp, payload)?;
+    }
+
+    let datagrams = rtp
+        .packetizer
+        .packetize(rtp_timestamp(timestamp_nanos), payload)?;
+    if let Some(pacer) = pacer.as_deref_mut() {
+        pacer.begin_frame(datagrams.iter().map(Vec::len).sum());
+    }
+    let mut start = 0;
+    for (index, buffer) in datagrams.iter().enumerate() {
+        if let Some(send_at) = pacing_deadline(pacer.as_deref_mut(), buffer.len()) {
+            let packets: Vec<&[u8]> = datagrams[start..index].iter().map(Vec::as_slice).collect();
+            feedback.stats.packets_dropped += send_all(sender, &packets)? as u64;
+            start = index;
+            service_until(sender, feedback, send_at)?;
+        }
+    }
+    let packets: Vec<&[u8]> = datagrams[start..].iter().map(Vec::as_slice).collect();
+    feedback.stats.packets_dropped += send_all(sender, &packets)? as u64;
+    Ok(())
+}
+
+fn write_sdp(rtp: &mut RtpOutput, access_unit: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+    let nal_units = parse_annex_b(access_unit)?;
+    let parameter_sets = ParameterSets::extract(&nal_units);
+    if !parameter_sets.is_complete() {
+        return Ok(());
+    }
+
+    let sdp = h264_sdp(rtp.destination, rtp.payload_type, &parameter_sets);
+    match &rtp.sdp_path {
+        Some(path) => {
+            std::fs::write(path, &sdp)?;
+            eprintln!("wrote SDP to {path}");
+        }
+        None => eprint!("{sdp}"),
+    }
+    rtp.sdp_written = true;
+    Ok(())
+}
+
+fn pacing_deadline(pacer: Option<&mut Pacer>, packet_bytes: usize) -> Option<Instant> {
+    let now = Instant::now();
+    let send_at = pacer?.schedule(packet_bytes, now);
+    (send_at > now + PACING_SLACK).then_some(send_at)
+}
+
+fn apply_target_bitrate(
+    encoder: &mut impl VideoEncoder,
+    pacer: Option<&mut Pacer>,
+    feedback: &Feedback,
+    applied_bitrate: &mut u32,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let target_bitrate = feedback.congestion.target_bitrate();
+    if target_bitrate == *applied_bitrate {
+        return Ok(());
+    }
+
+    encoder.set_bitrate(target_bitrate)?;
+    if let Some(pacer) = pacer {
+        pacer.set_target_bitrate(u64::from(target_bitrate));
     }
+    *applied_bitrate = target_bitrate;
     Ok(())
 }
 
-fn report(last_report: &mut Instant, frames_sent: &mut u64) {
+fn next_frame_deadline(config: &HostConfig) -> Instant {
+    if config.no_sleep {
+        Instant::now()
+    } else {
+        Instant::now() + config.frame_interval
+    }
+}
+
+fn service_until<T: PacketReceiver + PacketSender>(
+    demultiplexer: &mut Demultiplexer<T>,
+    feedback: &mut Feedback,
+    deadline: Instant,
+) -> Result<(), Box<dyn std::error::Error>> {
+    loop {
+        service_incoming(demultiplexer, feedback)?;
+        service_path_mtu(demultiplexer, feedback)?;
+        let now = Instant::now();
+        if now >= deadline {
+            return Ok(());
+        }
+        std::thread::sleep((deadline - now).min(INCOMING_POLL_INTERVAL));
+    }
+}
+
+fn service_incoming<T: PacketReceiver + PacketSender>(
+    demultiplexer: &mut Demultiplexer<T>,
+    feedback: &mut Feedback,
+) -> Result<(), Box<dyn std::error::Error>> {
+    loop {
+        let message = demultiplexer.receive();
+        if message.is_ok() {
+            feedback.peer_seen = true;
+        }
+        match message {
+            Ok(Message::Healthcheck(packet)) => {
+                if packet.kind == HealthcheckKind::Ping {
+                    let response = HealthcheckPacket {
+                        kind: HealthcheckKind::Pong,
+                        timestamp_nanos: packet.timestamp_nanos,
+                    };
+                    if let Err(error) = demultiplexer.send(&response.encode()) {
+                        if !error.is_transient() {
+                            return Err(error.into());
+                        }
+                    }
+                }
+            }
+            Ok(Message::Control(ControlMessage::Nack { sequence_numbers })) => {
+                let now = Instant::now();
+                for sequence_number in sequence_numbers {
+                    let Some(buffer) = feedback.retransmission.get(sequence_number, now) else {
+                        continue;
+                    };
+                    match send_all(demultiplexer, &[buffer])? {
+                        0 => feedback.stats.packets_retransmitted += 1,
+                        dropped => feedback.stats.packets_dropped += dropped as u64,
+                    }
+                }
+            }
+            Ok(Message::Control(ControlMessage::KeyframeRequest)) => {
+                feedback.keyframe_requests.request();
+                feedback.stats.keyframes_requested += 1;
+            }
+            Ok(Message::Control(ControlMessage::ReceiverReport(report))) => {
+                feedback.congestion.on_report(&report);
+                feedback.stats.receiver_reports += 1;
+                feedback.stats.fraction_lost = report.fraction_lost;
+            }
+            Ok(Message::Control(ControlMessage::PathProbeAck { datagram_bytes })) => {
+                if let Some(prober) = feedback.path_mtu.as_mut() {
+                    prober.on_ack(datagram_bytes as usize);
+                }
+            }
+            Ok(Message::Control(_)) => {}
+            Ok(Message::Video(_)) => {}
+            Err(DemuxError::Transport(error)) if error.is_transient() => return Ok(()),
+            Err(DemuxError::Transport(error)) => return Err(error.into()),
+            Err(error) if error.is_version_mismatch() => {
+                return Err(format!("client speaks a different protocol: {error}").into());
+            }
+            Err(_) => {}
+        }
+    }
+}
+
+fn service_path_mtu<T: PacketReceiver + PacketSender>(
+    demultiplexer: &mut Demultiplexer<T>,
+    feedback: &mut Feedback,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if !feedback.peer_seen {
+        return Ok(());
+    }
+    let Some(prober) = feedback.path_mtu.as_mut() else {
+        return Ok(());
+    };
+
+    match prober.poll(Instant::now()) {
+        ProbeStep::Send(datagram_bytes) => {
+            let probe = ControlMessage::PathProbe {
+                datagram_bytes: datagram_bytes as u32,
+            };
+            match demultiplexer.send(&probe.encode()) {
+                Ok(_) => {}
+                Err(error) if error.is_message_too_large() => prober.on_too_large(datagram_bytes),
+                Err(error) if error.is_transient() => {}
+                Err(error) => return Err(error.into()),
+            }
+        }
+        ProbeStep::Wait => {}
+        ProbeStep::Complete(datagram_bytes) => {
+            let result = ControlMessage::PathMtu {
+                datagram_bytes: datagram_bytes as u32,
+            };
+            match demultiplexer.send(&result.encode()) {
+                Ok(_) => {}
+                Err(error) if error.is_transient() => {}
+                Err(error) => return Err(error.into()),
+            }
+            feedback.path_mtu = None;
+            feedback.discovered_datagram_bytes = Some(datagram_bytes);
+        }
+    }
+    Ok(())
+}
+
+fn report(last_report: &mut Instant, stats: &mut HostStats, target_bitrate: u32) {
     if last_report.elapsed() >= Duration::from_secs(1) {
-        eprintln!("frames sent: {frames_sent}");
+        eprintln!(
+            "frames sent: {} packets retransmitted: {} packets dropped: {} keyframes requested: {}",
+            stats.frames_sent,
+            stats.packets_retransmitted,
+            stats.packets_dropped,
+            stats.keyframes_requested
+        );
+        eprintln!(
+            "receiver reports: {} loss: {:.1}% target bitrate: {} kbps",
+            stats.receiver_reports,
+            f64::from(stats.fraction_lost) * 100.0 / 256.0,
+            target_bitrate / 1000
+        );
         *last_report = Instant::now();
-        *frames_sent = 0;
+        *stats = HostStats::default();
     }
 }
 
@@ -173,6 +655,7 @@ fn current_time_nanos() -> u64 {
     duration.as_nanos() as u64
 }
 
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
 fn frame_rate_from_interval(interval: Duration) -> u32 {
     let millis = interval.as_millis().max(1) as u32;
     1000 / millis
@@ -183,13 +666,29 @@ fn parse_args() -> Result<HostConfig, String> {
     let mut remote_address: Option<SocketAddr> = None;
     let mut payload_bytes: usize = 1024;
     let mut max_payload_bytes: usize = 1200;
+    let mut fec = FecScheme::None;
+    let mut packetization = Packetization::Fixed;
+    let mut wire = WireFormat::Native;
+    let mut sdp_path = None;
+    let mut path_mtu = true;
+    let mut path_mtu_max_bytes: usize = 8972;
+    let mut retransmit_packets: usize = 1024;
+    let mut min_keyframe_interval = Duration::from_millis(250);
+    let mut pacing_fraction: f64 = 0.5;
+    let mut pacing_burst_bytes: usize = 12_000;
     let mut frame_interval = Duration::from_millis(16);
     let mut auto_bind_port: Option<u16> = None;
+    let mut transport = TransportChoice::Udp;
+    #[cfg(feature = "quic")]
+    let mut quic_fingerprint = None;
     let mut codec = CodecChoice::Passthrough;
     let mut width: u32 = 320;
     let mut height: u32 = 180;
     let mut bitrate: u32 = 3_000_000;
+    let mut min_bitrate: u32 = 250_000;
+    let mut max_bitrate: u32 = 20_000_000;
     let mut no_sleep = false;
+    let mut segmentation_offload = true;
 
     let mut args = std::env::args().skip(1);
     while let Some(argument) = args.next() {
@@ -208,9 +707,51 @@ fn parse_args() -> Result<HostConfig, String> {
             }
             "--max-payload-bytes" => {
                 let value = args.next().ok_or("missing --max-payload-bytes value")?;
-                max_payload_bytes = value
-                    .parse()
-                    .map_err(|_| "invalid max payload bytes")?;
+                max_payload_bytes = value.parse().map_err(|_| "invalid max payload bytes")?;
+            }
+            "--packetization" => {
+                let value = args.next().ok_or("missing --packetization value")?;
+                packetization = parse_packetization(&value)?;
+            }
+            "--wire" => {
+                let value = args.next().ok_or("missing --wire value")?;
+                wire = parse_wire(&value)?;
+            }
+            "--sdp" => {
+                sdp_path = Some(args.next().ok_or("missing --sdp value")?);
+            }
+            "--fec" => {
+                let value = args.next().ok_or("missing --fec value")?;
+                fec = parse_fec(&value)?;
+            }
+            "--path-mtu-max-bytes" => {
+                let value = args.next().ok_or("missing --path-mtu-max-bytes value")?;
+                path_mtu_max_bytes = value.parse().map_err(|_| "invalid path mtu max bytes")?;
+            }
+            "--no-path-mtu" => {
+                path_mtu = false;
+            }
+            "--retransmit-packets" => {
+                let value = args.next().ok_or("missing --retransmit-packets value")?;
+                retransmit_packets = value.parse().map_err(|_| "invalid retransmit packets")?;
+            }
+            "--min-keyframe-interval-ms" => {
+                let value = args
+                    .next()
+                    .ok_or("missing --min-keyframe-interval-ms value")?;
+                let millis: u64 = value.parse().map_err(|_| "invalid min keyframe interval")?;
+                min_keyframe_interval = Duration::from_millis(millis);
+            }
+            "--pacing-fraction" => {
+                let value = args.next().ok_or("missing --pacing-fraction value")?;
+                pacing_fraction = value.parse().map_err(|_| "invalid pacing fraction")?;
+                if !(0.0..=1.0).contains(&pacing_fraction) {
+                    return Err("pacing fraction must be between 0 and 1".to_string());
+                }
+            }
+            "--pacing-burst-bytes" => {
+                let value = args.next().ok_or("missing --pacing-burst-bytes value")?;
+                pacing_burst_bytes = value.parse().map_err(|_| "invalid pacing burst bytes")?;
             }
             "--frame-interval-ms" => {
                 let value = args.next().ok_or("missing --frame-interval-ms value")?;
@@ -221,6 +762,16 @@ fn parse_args() -> Result<HostConfig, String> {
                 let value = args.next().ok_or("missing --auto-bind-port value")?;
                 auto_bind_port = Some(value.parse().map_err(|_| "invalid port")?);
             }
+            "--transport" => {
+                let value = args.next().ok_or("missing --transport value")?;
+                transport = parse_transport(&value)?;
+            }
+            #[cfg(feature = "quic")]
+            "--quic-fingerprint" => {
+                let value = args.next().ok_or("missing --quic-fingerprint value")?;
+                quic_fingerprint =
+                    Some(parse_fingerprint(&value).ok_or("invalid quic fingerprint")?);
+            }
             "--codec" => {
                 let value = args.next().ok_or("missing --codec value")?;
                 codec = parse_codec(&value)?;
@@ -237,6 +788,17 @@ fn parse_args() -> Result<HostConfig, String> {
                 let value = args.next().ok_or("missing --bitrate value")?;
                 bitrate = value.parse().map_err(|_| "invalid bitrate")?;
             }
+            "--min-bitrate" => {
+                let value = args.next().ok_or("missing --min-bitrate value")?;
+                min_bitrate = value.parse().map_err(|_| "invalid min bitrate")?;
+            }
+            "--max-bitrate" => {
+                let value = args.next().ok_or("missing --max-bitrate value")?;
+                max_bitrate = value.parse().map_err(|_| "invalid max bitrate")?;
+            }
+            "--no-gso" => {
+                segmentation_offload = false;
+            }
             "--no-sleep" => {
                 no_sleep = true;
             }
@@ -257,21 +819,62 @@ fn parse_args() -> Result<HostConfig, String> {
 
     let bind_address = bind_address.ok_or("missing --bind (or use --auto-bind-port)")?;
     let remote_address = remote_address.ok_or("missing --remote")?;
+    if !(min_bitrate..=max_bitrate).contains(&bitrate) {
+        return Err("--bitrate must be between --min-bitrate and --max-bitrate".to_string());
+    }
+    if wire == WireFormat::Rtp && codec != CodecChoice::H264 {
+        return Err("--wire rtp requires --codec h264".to_string());
+    }
+    if wire == WireFormat::Rtp && transport != TransportChoice::Udp {
+        return Err("--wire rtp requires --transport udp".to_string());
+    }
+    #[cfg(feature = "quic")]
+    if transport == TransportChoice::Quic && quic_fingerprint.is_none() {
+        return Err("--transport quic requires --quic-fingerprint".to_string());
+    }
 
     Ok(HostConfig {
         bind_address,
         remote_address,
         payload_bytes,
         max_payload_bytes,
+        fec,
+        packetization,
+        wire,
+        sdp_path,
+        path_mtu,
+        path_mtu_max_bytes,
+        retransmit_packets,
+        min_keyframe_interval,
+        pacing_fraction,
+        pacing_burst_bytes,
         frame_interval,
         no_sleep,
+        segmentation_offload,
+        transport,
+        #[cfg(feature = "quic")]
+        quic_fingerprint,
         codec,
         width,
         height,
         bitrate,
+        min_bitrate,
+        max_bitrate,
     })
 }
 
+fn parse_transport(value: &str) -> Result<TransportChoice, String> {
+    match value {
+        "udp" => Ok(TransportChoice::Udp),
+        "tcp" => Ok(TransportChoice::Tcp),
+        #[cfg(feature = "quic")]
+        "quic" => Ok(TransportChoice::Quic),
+        #[cfg(not(feature = "quic"))]
+        "quic" => Err("QUIC support is not built in (rebuild with --features quic)".to_string()),
+        _ => Err("invalid transport (use udp, tcp or quic)".to_string()),
+    }
+}
+
 fn parse_codec(value: &str) -> Result<CodecChoice, String> {
     match value {
         "passthrough" => Ok(CodecChoice::Passthrough),
@@ -280,6 +883,46 @@ fn parse_codec(value: &str) -> Result<CodecChoice, String> {
     }
 }
 
+fn parse_wire(value: &str) -> Result<WireFormat, String> {
+    match value {
+        "native" => Ok(WireFormat::Native),
+        "rtp" => Ok(WireFormat::Rtp),
+        _ => Err("invalid wire format (use native or rtp)".to_string()),
+    }
+}
+
+fn parse_packetization(value: &str) -> Result<Packetization, String> {
+    match value {
+        "fixed" => Ok(Packetization::Fixed),
+        "nal" => Ok(Packetization::NalUnits),
+        _ => Err("invalid packetization (use fixed or nal)".to_string()),
+    }
+}
+
+fn parse_fec(value: &str) -> Result<FecScheme, String> {
+    let invalid = || "invalid fec (use none, xor:GROUP_SIZE or rs:DATA:PARITY)".to_string();
+    match value.split_once(':') {
+        None if value == "none" => Ok(FecScheme::None),
+        Some(("xor", group_size)) => {
+            let group_size: u8 = group_size.parse().map_err(|_| invalid())?;
+            if group_size == 0 {
+                return Err(invalid());
+            }
+            Ok(FecScheme::XorParity { group_size })
+        }
+        Some(("rs", shards)) => {
+            let (data_shards, parity_shards) = shards.split_once(':').ok_or_else(invalid)?;
+            let scheme = FecScheme::ReedSolomon {
+                data_shards: data_shards.parse().map_err(|_| invalid())?,
+                parity_shards: parity_shards.parse().map_err(|_| invalid())?,
+            };
+            scheme.validate().map_err(|_| invalid())?;
+            Ok(scheme)
+        }
+        _ => Err(invalid()),
+    }
+}
+
 fn auto_bind_socket(port: u16) -> Result<Option<SocketAddr>, String> {
     #[cfg(target_os = "macos")]
     {
@@ -288,10 +931,7 @@ fn auto_bind_socket(port: u16) -> Result<Option<SocketAddr>, String> {
                 "auto-bind selected interface {} with IPv4 {}",
                 interface.name, interface.ipv4
             );
-            return Ok(Some(SocketAddr::new(
-                IpAddr::V4(interface.ipv4),
-                port,
-            )));
+            return Ok(Some(SocketAddr::new(IpAddr::V4(interface.ipv4), port)));
         }
     }
 
@@ -307,6 +947,6 @@ fn parse_socket_addr(value: &str) -> Result<SocketAddr, String> {
 
 fn print_usage() {
     eprintln!(
-        "usage: host --bind IP:PORT --remote IP:PORT [--payload-bytes N] [--max-payload-bytes N] [--frame-interval-ms N] [--auto-bind-port PORT] [--codec passthrough|h264] [--width N --height N --bitrate N] [--no-sleep]"
+        "usage: host --bind IP:PORT --remote IP:PORT [--payload-bytes N] [--max-payload-bytes N] [--fec none|xor:N|rs:D:P] [--packetization fixed|nal] [--wire native|rtp] [--sdp PATH] [--path-mtu-max-bytes N] [--no-path-mtu] [--retransmit-packets N] [--min-keyframe-interval-ms N] [--pacing-fraction F] [--pacing-burst-bytes N] [--frame-interval-ms N] [--auto-bind-port PORT] [--codec passthrough|h264] [--width N --height N --bitrate N] [--min-bitrate N] [--max-bitrate N] [--transport udp|tcp|quic] [--quic-fingerprint HEX] [--no-gso] [--no-sleep]"
     );
 }
@@ -43,7 +43,7 @@ mod tests {
 
     #[test]
     fn encode_decode_round_trip() {
-        let mut codec = PassthroughCodec::default();
+        let mut codec = PassthroughCodec;
         let frame = RawFrame {
             width: 1,
             height: 1,
@@ -0,0 +1,783 @@
+const HIGH_PROFILES: [u8; 13] = [100, 110, 122, 244, 44, 83, 86, 118, 128, 138, 139, 134, 135];
+const MAX_EXP_GOLOMB_LEADING_ZEROS: u32 = 31;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NalUnitType {
+    NonIdrSlice,
+    PartitionA,
+    PartitionB,
+    PartitionC,
+    IdrSlice,
+    Sei,
+    Sps,
+    Pps,
+    AccessUnitDelimiter,
+    EndOfSequence,
+    EndOfStream,
+    FillerData,
+    Other(u8),
+}
+
+impl NalUnitType {
+    pub fn from_header(header: u8) -> Self {
+        match header & 0x1F {
+            1 => NalUnitType::NonIdrSlice,
+            2 => NalUnitType::PartitionA,
+            3 => NalUnitType::PartitionB,
+            4 => NalUnitType::PartitionC,
+            5 => NalUnitType::IdrSlice,
+            6 => NalUnitType::Sei,
+            7 => NalUnitType::Sps,
+            8 => NalUnitType::Pps,
+            9 => NalUnitType::AccessUnitDelimiter,
+            10 => NalUnitType::EndOfSequence,
+            11 => NalUnitType::EndOfStream,
+            12 => NalUnitType::FillerData,
+            other => NalUnitType::Other(other),
+        }
+    }
+
+    pub fn is_slice(self) -> bool {
+        matches!(
+            self,
+            NalUnitType::NonIdrSlice | NalUnitType::PartitionA | NalUnitType::IdrSlice
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NalUnit<'a> {
+    pub unit_type: NalUnitType,
+    pub nal_ref_idc: u8,
+    pub data: &'a [u8],
+}
+
+impl<'a> NalUnit<'a> {
+    pub fn parse(data: &'a [u8]) -> Result<Self, H264Error> {
+        let header = *data.first().ok_or(H264Error::EmptyNalUnit)?;
+        if header & 0x80 != 0 {
+            return Err(H264Error::ForbiddenBitSet);
+        }
+        Ok(Self {
+            unit_type: NalUnitType::from_header(header),
+            nal_ref_idc: (header >> 5) & 0x03,
+            data,
+        })
+    }
+
+    pub fn rbsp(&self) -> Vec<u8> {
+        remove_emulation_prevention(&self.data[1..])
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Profile {
+    ConstrainedBaseline,
+    Baseline,
+    Main,
+    Extended,
+    High,
+    High10,
+    High422,
+    High444,
+    Other(u8),
+}
+
+impl std::fmt::Display for Profile {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Profile::ConstrainedBaseline => write!(formatter, "Constrained Baseline"),
+            Profile::Baseline => write!(formatter, "Baseline"),
+            Profile::Main => write!(formatter, "Main"),
+            Profile::Extended => write!(formatter, "Extended"),
+            Profile::High => write!(formatter, "High"),
+            Profile::High10 => write!(formatter, "High 10"),
+            Profile::High422 => write!(formatter, "High 4:2:2"),
+            Profile::High444 => write!(formatter, "High 4:4:4 Predictive"),
+            Profile::Other(profile_idc) => write!(formatter, "profile {profile_idc}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SequenceParameterSet {
+    pub profile_idc: u8,
+    pub constraint_flags: u8,
+    pub level_idc: u8,
+    pub seq_parameter_set_id: u32,
+    pub chroma_format_idc: u32,
+    pub bit_depth_luma: u32,
+    pub bit_depth_chroma: u32,
+    pub max_num_ref_frames: u32,
+    pub frame_mbs_only: bool,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl SequenceParameterSet {
+    pub fn parse(nal_unit: &NalUnit<'_>) -> Result<Self, H264Error> {
+        if nal_unit.unit_type != NalUnitType::Sps {
+            return Err(H264Error::UnexpectedNalUnit(nal_unit.unit_type));
+        }
+        let rbsp = nal_unit.rbsp();
+        let mut reader = BitReader::new(&rbsp);
+
+        let profile_idc = reader.read_bits(8)? as u8;
+        let constraint_flags = reader.read_bits(8)? as u8;
+        let level_idc = reader.read_bits(8)? as u8;
+        let seq_parameter_set_id = reader.read_ue()?;
+
+        let mut chroma_format_idc = 1;
+        let mut separate_colour_plane = false;
+        let mut bit_depth_luma = 8;
+        let mut bit_depth_chroma = 8;
+        if HIGH_PROFILES.contains(&profile_idc) {
+            chroma_format_idc = reader.read_ue()?;
+            if chroma_format_idc > 3 {
+                return Err(H264Error::InvalidSps("chroma_format_idc"));
+            }
+            if chroma_format_idc == 3 {
+                separate_colour_plane = reader.read_flag()?;
+            }
+            bit_depth_luma = 8 + reader.read_ue()?;
+            bit_depth_chroma = 8 + reader.read_ue()?;
+            reader.read_flag()?;
+            if reader.read_flag()? {
+                let lists = if chroma_format_idc == 3 { 12 } else { 8 };
+                for index in 0..lists {
+                    if reader.read_flag()? {
+                        skip_scaling_list(&mut reader, if index < 6 { 16 } else { 64 })?;
+                    }
+                }
+            }
+        }
+
+        reader.read_ue()?;
+        match reader.read_ue()? {
+            0 => {
+                reader.read_ue()?;
+            }
+            1 => {
+                reader.read_flag()?;
+                reader.read_se()?;
+                reader.read_se()?;
+                let cycle = reader.read_ue()?;
+                if cycle > 255 {
+                    return Err(H264Error::InvalidSps(
+                        "num_ref_frames_in_pic_order_cnt_cycle",
+                    ));
+                }
+                for _ in 0..cycle {
+                    reader.read_se()?;
+                }
+            }
+            2 => {}
+            _ => return Err(H264Error::InvalidSps("pic_order_cnt_type")),
+        }
+
+        let max_num_ref_frames = reader.read_ue()?;
+        reader.read_flag()?;
+        let width_in_mbs = u64::from(reader.read_ue()?) + 1;
+        let height_in_map_units = u64::from(reader.read_ue()?) + 1;
+        let frame_mbs_only = reader.read_flag()?;
+        if !frame_mbs_only {
+            reader.read_flag()?;
+        }
+        reader.read_flag()?;
+
+        let field_factor = if frame_mbs_only { 1 } else { 2 };
+        let (crop_unit_x, crop_unit_y) = if separate_colour_plane || chroma_format_idc == 0 {
+            (1, field_factor)
+        } else {
+            let sub_width = if chroma_format_idc == 3 { 1 } else { 2 };
+            let sub_height = if chroma_format_idc == 1 { 2 } else { 1 };
+            (sub_width, sub_height * field_factor)
+        };
+        let mut crop = [0_u64; 4];
+        if reader.read_flag()? {
+            for offset in &mut crop {
+                *offset = u64::from(reader.read_ue()?);
+            }
+        }
+
+        let full_width = width_in_mbs * 16;
+        let full_height = height_in_map_units * 16 * field_factor;
+        let crop_width = crop_unit_x * (crop[0] + crop[1]);
+        let crop_height = crop_unit_y * (crop[2] + crop[3]);
+        if crop_width >= full_width || crop_height >= full_height {
+            return Err(H264Error::InvalidSps("frame cropping"));
+        }
+        let width = u32::try_from(full_width - crop_width)
+            .map_err(|_| H264Error::InvalidSps("pic_width_in_mbs"))?;
+        let height = u32::try_from(full_height - crop_height)
+            .map_err(|_| H264Error::InvalidSps("pic_height_in_map_units"))?;
+
+        Ok(Self {
+            profile_idc,
+            constraint_flags,
+            level_idc,
+            seq_parameter_set_id,
+            chroma_format_idc,
+            bit_depth_luma,
+            bit_depth_chroma,
+            max_num_ref_frames,
+            frame_mbs_only,
+            width,
+            height,
+        })
+    }
+
+    pub fn profile(&self) -> Profile {
+        match self.profile_idc {
+            66 if self.constraint_flags & 0x40 != 0 => Profile::ConstrainedBaseline,
+            66 => Profile::Baseline,
+            77 => Profile::Main,
+            88 => Profile::Extended,
+            100 => Profile::High,
+            110 => Profile::High10,
+            122 => Profile::High422,
+            244 => Profile::High444,
+            other => Profile::Other(other),
+        }
+    }
+
+    pub fn level(&self) -> String {
+        let constraint_set3 = self.constraint_flags & 0x10 != 0;
+        if self.level_idc == 9
+            || (self.level_idc == 11 && constraint_set3 && self.profile_idc <= 88)
+        {
+            return "1b".to_string();
+        }
+        format!("{}.{}", self.level_idc / 10, self.level_idc % 10)
+    }
+}
+
+impl std::fmt::Display for SequenceParameterSet {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            formatter,
+            "{} level {} {}x{}",
+            self.profile(),
+            self.level(),
+            self.width,
+            self.height
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParameterSets<'a> {
+    pub sps: Vec<&'a [u8]>,
+    pub pps: Vec<&'a [u8]>,
+}
+
+impl<'a> ParameterSets<'a> {
+    pub fn extract(nal_units: &[NalUnit<'a>]) -> Self {
+        let mut parameter_sets = Self::default();
+        for nal_unit in nal_units {
+            match nal_unit.unit_type {
+                NalUnitType::Sps => parameter_sets.sps.push(nal_unit.data),
+                NalUnitType::Pps => parameter_sets.pps.push(nal_unit.data),
+                _ => {}
+            }
+        }
+        parameter_sets
+    }
+
+    pub fn is_complete(&self) -> bool {
+        !self.sps.is_empty() && !self.pps.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessUnitInfo {
+    pub is_keyframe: bool,
+    pub sps: Option<SequenceParameterSet>,
+}
+
+impl AccessUnitInfo {
+    pub fn inspect(nal_units: &[NalUnit<'_>]) -> Result<Self, H264Error> {
+        let is_keyframe = nal_units
+            .iter()
+            .any(|nal_unit| nal_unit.unit_type == NalUnitType::IdrSlice);
+        let sps = nal_units
+            .iter()
+            .find(|nal_unit| nal_unit.unit_type == NalUnitType::Sps)
+            .map(SequenceParameterSet::parse)
+            .transpose()?;
+        Ok(Self { is_keyframe, sps })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum H264Error {
+    MissingStartCode,
+    EmptyNalUnit,
+    ForbiddenBitSet,
+    InvalidLengthSize(usize),
+    TruncatedNalUnit { expected: usize, available: usize },
+    UnexpectedNalUnit(NalUnitType),
+    UnexpectedEnd,
+    InvalidSps(&'static str),
+}
+
+impl std::fmt::Display for H264Error {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            H264Error::MissingStartCode => {
+                write!(formatter, "annex b stream does not start with a start code")
+            }
+            H264Error::EmptyNalUnit => write!(formatter, "empty nal unit"),
+            H264Error::ForbiddenBitSet => write!(formatter, "nal unit forbidden_zero_bit is set"),
+            H264Error::InvalidLengthSize(size) => {
+                write!(formatter, "invalid avcc length size {size}")
+            }
+            H264Error::TruncatedNalUnit {
+                expected,
+                available,
+            } => write!(
+                formatter,
+                "nal unit truncated: expected {expected} bytes, {available} available"
+            ),
+            H264Error::UnexpectedNalUnit(unit_type) => {
+                write!(formatter, "unexpected nal unit type {unit_type:?}")
+            }
+            H264Error::UnexpectedEnd => write!(formatter, "bitstream ended unexpectedly"),
+            H264Error::InvalidSps(field) => write!(formatter, "invalid sps {field}"),
+        }
+    }
+}
+
+impl std::error::Error for H264Error {}
+
+pub fn parse_annex_b(data: &[u8]) -> Result<Vec<NalUnit<'_>>, H264Error> {
+    let mut nal_units = Vec::new();
+    let Some((mut start, _)) = find_start_code(data, 0) else {
+        return if data.iter().all(|&byte| byte == 0) {
+            Ok(nal_units)
+        } else {
+            Err(H264Error::MissingStartCode)
+        };
+    };
+    if data[..start].iter().any(|&byte| byte != 0) {
+        return Err(H264Error::MissingStartCode);
+    }
+
+    loop {
+        let payload_start = start + 3;
+        let (end, next) = match find_start_code(data, payload_start) {
+            Some((next, zeros_before)) => (next - zeros_before, Some(next)),
+            None => (trim_trailing_zeros(data, payload_start), None),
+        };
+        if end > payload_start {
+            nal_units.push(NalUnit::parse(&data[payload_start..end])?);
+        }
+        match next {
+            Some(next) => start = next,
+            None => return Ok(nal_units),
+        }
+    }
+}
+
+pub fn split_annex_b(data: &[u8]) -> Result<Vec<&[u8]>, H264Error> {
+    parse_annex_b(data)?;
+    let mut units = Vec::new();
+    let Some((first, _)) = find_start_code(data, 0) else {
+        return Ok(units);
+    };
+
+    let mut unit_start = 0;
+    let mut search_from = first + 3;
+    while let Some((position, _)) = find_start_code(data, search_from) {
+        let boundary = if data[position - 1] == 0 {
+            position - 1
+        } else {
+            position
+        };
+        units.push(&data[unit_start..boundary]);
+        unit_start = boundary;
+        search_from = position + 3;
+    }
+    units.push(&data[unit_start..]);
+    Ok(units)
+}
+
+pub fn salvage_access_unit(chunks: &[Option<&[u8]>]) -> Vec<u8> {
+    let mut salvaged = Vec::new();
+    let mut index = 0;
+    while index < chunks.len() {
+        let start = index;
+        index += 1;
+        let Some(first) = chunks[start] else {
+            continue;
+        };
+        if !begins_with_start_code(first) {
+            continue;
+        }
+        while let Some(Some(chunk)) = chunks.get(index) {
+            if begins_with_start_code(chunk) {
+                break;
+            }
+            index += 1;
+        }
+
+        let interrupted = matches!(chunks.get(index), Some(None));
+        let may_continue =
+            index > start + 1 || split_annex_b(first).map_or(true, |units| units.len() == 1);
+        if interrupted && may_continue {
+            continue;
+        }
+        for chunk in chunks[start..index].iter().flatten() {
+            salvaged.extend_from_slice(chunk);
+        }
+    }
+    salvaged
+}
+
+pub fn parse_avcc(data: &[u8], length_size: usize) -> Result<Vec<NalUnit<'_>>, H264Error> {
+    if !matches!(length_size, 1 | 2 | 4) {
+        return Err(H264Error::InvalidLengthSize(length_size));
+    }
+
+    let mut nal_units = Vec::new();
+    let mut offset = 0;
+    while offset < data.len() {
+        let available = data.len() - offset;
+        if available < length_size {
+            return Err(H264Error::TruncatedNalUnit {
+                expected: length_size,
+                available,
+            });
+        }
+        let length = data[offset..offset + length_size]
+            .iter()
+            .fold(0_usize, |length, &byte| (length << 8) | usize::from(byte));
+        offset += length_size;
+        let available = data.len() - offset;
+        if length > available {
+            return Err(H264Error::TruncatedNalUnit {
+                expected: length,
+                available,
+            });
+        }
+        nal_units.push(NalUnit::parse(&data[offset..offset + length])?);
+        offset += length;
+    }
+    Ok(nal_units)
+}
+
+pub fn remove_emulation_prevention(data: &[u8]) -> Vec<u8> {
+    let mut rbsp = Vec::with_capacity(data.len());
+    let mut zeros = 0;
+    for &byte in data {
+        if zeros >= 2 && byte == 0x03 {
+            zeros = 0;
+            continue;
+        }
+        zeros = if byte == 0 { zeros + 1 } else { 0 };
+        rbsp.push(byte);
+    }
+    rbsp
+}
+
+fn begins_with_start_code(data: &[u8]) -> bool {
+    let zeros = data.iter().take_while(|&&byte| byte == 0).count();
+    zeros >= 2 && data.get(zeros) == Some(&1)
+}
+
+fn find_start_code(data: &[u8], from: usize) -> Option<(usize, usize)> {
+    let position = data
+        .get(from..)?
+        .windows(3)
+        .position(|window| window == [0, 0, 1])?
+        + from;
+    let zeros_before = data[from..position]
+        .iter()
+        .rev()
+        .take_while(|&&byte| byte == 0)
+        .count();
+    Some((position, zeros_before))
+}
+
+fn trim_trailing_zeros(data: &[u8], from: usize) -> usize {
+    let trailing = data[from..]
+        .iter()
+        .rev()
+        .take_while(|&&byte| byte == 0)
+        .count();
+    data.len() - trailing
+}
+
+fn skip_scaling_list(reader: &mut BitReader<'_>, size: usize) -> Result<(), H264Error> {
+    let mut last_scale = 8_i64;
+    let mut next_scale = 8_i64;
+    for _ in 0..size {
+        if next_scale != 0 {
+            let delta = i64::from(reader.read_se()?);
+            next_scale = (last_scale + delta).rem_euclid(256);
+        }
+        if next_scale != 0 {
+            last_scale = next_scale;
+        }
+    }
+    Ok(())
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    fn read_flag(&mut self) -> Result<bool, H264Error> {
+        let byte = self
+            .data
+            .get(self.position / 8)
+            .ok_or(H264Error::UnexpectedEnd)?;
+        let bit = (byte >> (7 - self.position % 8)) & 1;
+        self.position += 1;
+        Ok(bit == 1)
+    }
+
+    fn read_bits(&mut self, count: u32) -> Result<u32, H264Error> {
+        let mut value = 0_u32;
+        for _ in 0..count {
+            value = (value << 1) | u32::from(self.read_flag()?);
+        }
+        Ok(value)
+    }
+
+    fn read_ue(&mut self) -> Result<u32, H264Error> {
+        let mut leading_zeros = 0;
+        while !self.read_flag()? {
+            leading_zeros += 1;
+            if leading_zeros > MAX_EXP_GOLOMB_LEADING_ZEROS {
+                return Err(H264Error::InvalidSps("exp-golomb code too long"));
+            }
+        }
+        let suffix = u64::from(self.read_bits(leading_zeros)?);
+        u32::try_from((1_u64 << leading_zeros) - 1 + suffix)
+            .map_err(|_| H264Error::InvalidSps("exp-golomb value out of range"))
+    }
+
+    fn read_se(&mut self) -> Result<i32, H264Error> {
+        let code = i64::from(self.read_ue()?);
+        let value = if code % 2 == 1 {
+            (code + 1) / 2
+        } else {
+            -(code / 2)
+        };
+        Ok(value as i32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        parse_annex_b, parse_avcc, remove_emulation_prevention, salvage_access_unit, split_annex_b,
+        AccessUnitInfo, H264Error, NalUnit, NalUnitType, ParameterSets, Profile,
+        SequenceParameterSet,
+    };
+
+    const BASELINE_SPS_320X240: [u8; 8] = [0x67, 0x42, 0xC0, 0x1E, 0xDA, 0x05, 0x07, 0xE4];
+    const HIGH_SPS_1920X1080: [u8; 11] = [
+        0x67, 0x64, 0x00, 0x28, 0xAC, 0xDA, 0x01, 0xE0, 0x08, 0x9F, 0x95,
+    ];
+    const PPS: [u8; 4] = [0x68, 0xCE, 0x3C, 0x80];
+    const IDR_SLICE: [u8; 4] = [0x65, 0x88, 0x84, 0x21];
+    const NON_IDR_SLICE: [u8; 3] = [0x41, 0x9A, 0x02];
+
+    fn annex_b(nal_units: &[&[u8]]) -> Vec<u8> {
+        let mut stream = Vec::new();
+        for (index, nal_unit) in nal_units.iter().enumerate() {
+            if index % 2 == 0 {
+                stream.extend_from_slice(&[0, 0, 0, 1]);
+            } else {
+                stream.extend_from_slice(&[0, 0, 1]);
+            }
+            stream.extend_from_slice(nal_unit);
+        }
+        stream
+    }
+
+    fn avcc(nal_units: &[&[u8]]) -> Vec<u8> {
+        let mut stream = Vec::new();
+        for nal_unit in nal_units {
+            stream.extend_from_slice(&(nal_unit.len() as u32).to_be_bytes());
+            stream.extend_from_slice(nal_unit);
+        }
+        stream
+    }
+
+    #[test]
+    fn splits_annex_b_on_three_and_four_byte_start_codes() {
+        let stream = annex_b(&[&HIGH_SPS_1920X1080, &PPS, &IDR_SLICE]);
+        let nal_units = parse_annex_b(&stream).expect("parse");
+
+        let types: Vec<NalUnitType> = nal_units.iter().map(|unit| unit.unit_type).collect();
+        assert_eq!(
+            types,
+            vec![NalUnitType::Sps, NalUnitType::Pps, NalUnitType::IdrSlice]
+        );
+        assert_eq!(nal_units[0].data, &HIGH_SPS_1920X1080);
+        assert_eq!(nal_units[1].data, &PPS);
+        assert_eq!(nal_units[2].data, &IDR_SLICE);
+        assert_eq!(nal_units[2].nal_ref_idc, 3);
+    }
+
+    #[test]
+    fn strips_trailing_zero_bytes_between_annex_b_units() {
+        let mut stream = annex_b(&[&NON_IDR_SLICE]);
+        stream.extend_from_slice(&[0, 0, 0, 0, 0, 1]);
+        stream.extend_from_slice(&PPS);
+        stream.extend_from_slice(&[0, 0]);
+
+        let nal_units = parse_annex_b(&stream).expect("parse");
+        assert_eq!(nal_units.len(), 2);
+        assert_eq!(nal_units[0].data, &NON_IDR_SLICE);
+        assert_eq!(nal_units[1].data, &PPS);
+    }
+
+    #[test]
+    fn rejects_annex_b_without_leading_start_code() {
+        assert_eq!(parse_annex_b(&IDR_SLICE), Err(H264Error::MissingStartCode));
+        assert_eq!(parse_annex_b(&[]), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn parses_avcc_length_prefixed_units() {
+        let stream = avcc(&[&BASELINE_SPS_320X240, &PPS, &NON_IDR_SLICE]);
+        let nal_units = parse_avcc(&stream, 4).expect("parse");
+        assert_eq!(nal_units.len(), 3);
+        assert_eq!(nal_units[2].unit_type, NalUnitType::NonIdrSlice);
+
+        assert_eq!(
+            parse_avcc(&stream[..stream.len() - 1], 4),
+            Err(H264Error::TruncatedNalUnit {
+                expected: NON_IDR_SLICE.len(),
+                available: NON_IDR_SLICE.len() - 1,
+            })
+        );
+        assert_eq!(parse_avcc(&stream, 3), Err(H264Error::InvalidLengthSize(3)));
+    }
+
+    #[test]
+    fn rejects_forbidden_zero_bit() {
+        assert_eq!(
+            NalUnit::parse(&[0xE5, 0x00]),
+            Err(H264Error::ForbiddenBitSet)
+        );
+    }
+
+    #[test]
+    fn removes_emulation_prevention_bytes() {
+        assert_eq!(
+            remove_emulation_prevention(&[0x00, 0x00, 0x03, 0x01, 0x00, 0x00, 0x03, 0x00, 0x03]),
+            vec![0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x03]
+        );
+    }
+
+    #[test]
+    fn decodes_baseline_sps() {
+        let nal_unit = NalUnit::parse(&BASELINE_SPS_320X240).expect("nal unit");
+        let sps = SequenceParameterSet::parse(&nal_unit).expect("sps");
+
+        assert_eq!(sps.profile(), Profile::ConstrainedBaseline);
+        assert_eq!(sps.level(), "3.0");
+        assert_eq!((sps.width, sps.height), (320, 240));
+        assert_eq!(sps.chroma_format_idc, 1);
+        assert!(sps.frame_mbs_only);
+    }
+
+    #[test]
+    fn decodes_high_profile_sps_with_cropping() {
+        let nal_unit = NalUnit::parse(&HIGH_SPS_1920X1080).expect("nal unit");
+        let sps = SequenceParameterSet::parse(&nal_unit).expect("sps");
+
+        assert_eq!(sps.profile(), Profile::High);
+        assert_eq!(sps.level(), "4.0");
+        assert_eq!((sps.width, sps.height), (1920, 1080));
+        assert_eq!(sps.bit_depth_luma, 8);
+        assert_eq!(sps.to_string(), "High level 4.0 1920x1080");
+    }
+
+    #[test]
+    fn truncated_sps_is_an_error() {
+        let nal_unit = NalUnit::parse(&HIGH_SPS_1920X1080[..6]).expect("nal unit");
+        assert_eq!(
+            SequenceParameterSet::parse(&nal_unit),
+            Err(H264Error::UnexpectedEnd)
+        );
+    }
+
+    #[test]
+    fn inspects_keyframes_and_parameter_sets() {
+        let keyframe = annex_b(&[&HIGH_SPS_1920X1080, &PPS, &IDR_SLICE]);
+        let nal_units = parse_annex_b(&keyframe).expect("parse");
+        let parameter_sets = ParameterSets::extract(&nal_units);
+        assert!(parameter_sets.is_complete());
+        assert_eq!(parameter_sets.sps, vec![&HIGH_SPS_1920X1080[..]]);
+
+        let info = AccessUnitInfo::inspect(&nal_units).expect("inspect");
+        assert!(info.is_keyframe);
+        assert_eq!(
+            info.sps.map(|sps| (sps.width, sps.height)),
+            Some((1920, 1080))
+        );
+
+        let delta = annex_b(&[&NON_IDR_SLICE]);
+        let info =
+            AccessUnitInfo::inspect(&parse_annex_b(&delta).expect("parse")).expect("inspect");
+        assert!(!info.is_keyframe);
+        assert_eq!(info.sps, None);
+    }
+
+    #[test]
+    fn splits_annex_b_into_units_with_start_codes() {
+        let stream = annex_b(&[&HIGH_SPS_1920X1080, &PPS, &IDR_SLICE]);
+        let units = split_annex_b(&stream).expect("split");
+
+        assert_eq!(units.len(), 3);
+        assert_eq!(units.concat(), stream);
+        assert_eq!(units[0], [&[0, 0, 0, 1][..], &HIGH_SPS_1920X1080].concat());
+        assert_eq!(units[1], [&[0, 0, 1][..], &PPS].concat());
+        assert_eq!(units[2], [&[0, 0, 0, 1][..], &IDR_SLICE].concat());
+    }
+
+    #[test]
+    fn salvages_complete_nal_units_around_a_lost_chunk() {
+        let parameter_sets = annex_b(&[&HIGH_SPS_1920X1080, &PPS]);
+        let first_slice = annex_b(&[&NON_IDR_SLICE]);
+        let second_slice = annex_b(&[&IDR_SLICE]);
+        let chunks = [
+            Some(&parameter_sets[..]),
+            Some(&first_slice[..]),
+            None,
+            Some(&second_slice[..]),
+        ];
+
+        let salvaged = salvage_access_unit(&chunks);
+        assert_eq!(salvaged, [&parameter_sets[..], &second_slice[..]].concat());
+    }
+
+    #[test]
+    fn salvage_drops_fragmented_unit_missing_a_piece() {
+        let slice = annex_b(&[&[0x65, 0x88, 0x84, 0x21, 0x10, 0x22, 0x33, 0x44]]);
+        let next = annex_b(&[&NON_IDR_SLICE]);
+        let complete = [Some(&slice[..5]), Some(&slice[5..]), Some(&next[..])];
+        assert_eq!(
+            salvage_access_unit(&complete),
+            [&slice[..], &next[..]].concat()
+        );
+
+        let missing_tail = [Some(&slice[..5]), Some(&slice[5..9]), None, Some(&next[..])];
+        assert_eq!(salvage_access_unit(&missing_tail), next);
+
+        let missing_head = [None, Some(&slice[5..]), Some(&next[..])];
+        assert_eq!(salvage_access_unit(&missing_head), next);
+    }
+}
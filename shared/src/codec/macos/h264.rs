@@ -33,8 +33,10 @@ extern "C" {
         encoder: *mut VtH264EncoderOpaque,
         rgba_data: *const u8,
         rgba_size: usize,
+        force_keyframe: bool,
         out_frame: *mut VtH264EncodedFrame,
     ) -> bool;
+    fn vt_h264_encoder_set_bitrate(encoder: *mut VtH264EncoderOpaque, bitrate: u32) -> bool;
 
     fn vt_h264_decoder_create() -> *mut VtH264DecoderOpaque;
     fn vt_h264_decoder_destroy(decoder: *mut VtH264DecoderOpaque);
@@ -51,6 +53,7 @@ pub struct VideoToolboxH264Encoder {
     handle: *mut VtH264EncoderOpaque,
     width: u32,
     height: u32,
+    force_keyframe: bool,
 }
 
 impl VideoToolboxH264Encoder {
@@ -59,7 +62,12 @@ impl VideoToolboxH264Encoder {
         if handle.is_null() {
             return Err(CodecError::Unsupported);
         }
-        Ok(Self { handle, width, height })
+        Ok(Self {
+            handle,
+            width,
+            height,
+            force_keyframe: false,
+        })
     }
 }
 
@@ -93,12 +101,16 @@ impl VideoEncoder for VideoToolboxH264Encoder {
                 self.handle,
                 frame.data.as_ptr(),
                 frame.data.len(),
+                self.force_keyframe,
                 &mut out,
             )
         };
         if !ok || out.data.is_null() || out.size == 0 {
             return Err(CodecError::InternalError);
         }
+        if out.is_keyframe {
+            self.force_keyframe = false;
+        }
 
         let bytes = unsafe { std::slice::from_raw_parts(out.data, out.size) };
         Ok(EncodedFrame {
@@ -107,6 +119,18 @@ impl VideoEncoder for VideoToolboxH264Encoder {
             is_keyframe: out.is_keyframe,
         })
     }
+
+    fn request_keyframe(&mut self) {
+        self.force_keyframe = true;
+    }
+
+    fn set_bitrate(&mut self, bitrate: u32) -> Result<(), CodecError> {
+        if unsafe { vt_h264_encoder_set_bitrate(self.handle, bitrate) } {
+            Ok(())
+        } else {
+            Err(CodecError::InternalError)
+        }
+    }
 }
 
 #[derive(Debug)]
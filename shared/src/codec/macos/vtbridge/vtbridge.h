@@ -28,8 +28,12 @@ vt_h264_encoder_t *vt_h264_encoder_create(uint32_t width, uint32_t height, uint3
 void vt_h264_encoder_destroy(vt_h264_encoder_t *encoder);
 
 // Encodes an RGBA frame (width*height*4 bytes). Returns true on success.
+// When force_keyframe is set the frame is encoded as an IDR.
 // Output frame data is owned by encoder until next encode call.
-bool vt_h264_encoder_encode(vt_h264_encoder_t *encoder, const uint8_t *rgba_data, size_t rgba_size, vt_h264_encoded_frame_t *out_frame);
+bool vt_h264_encoder_encode(vt_h264_encoder_t *encoder, const uint8_t *rgba_data, size_t rgba_size, bool force_keyframe, vt_h264_encoded_frame_t *out_frame);
+
+// Updates the average bitrate used for subsequent frames. Returns true on success.
+bool vt_h264_encoder_set_bitrate(vt_h264_encoder_t *encoder, uint32_t bitrate);
 
 vt_h264_decoder_t *vt_h264_decoder_create(void);
 void vt_h264_decoder_destroy(vt_h264_decoder_t *decoder);
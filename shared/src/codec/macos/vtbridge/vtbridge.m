@@ -186,6 +186,20 @@ vt_h264_encoder_t *vt_h264_encoder_create(uint32_t width, uint32_t height, uint3
     return encoder;
 }
 
+bool vt_h264_encoder_set_bitrate(vt_h264_encoder_t *encoder, uint32_t bitrate) {
+    if (!encoder || !encoder->session) {
+        return false;
+    }
+
+    CFNumberRef bitrate_number = CFNumberCreate(NULL, kCFNumberSInt32Type, &bitrate);
+    if (!bitrate_number) {
+        return false;
+    }
+    OSStatus status = VTSessionSetProperty(encoder->session, kVTCompressionPropertyKey_AverageBitRate, bitrate_number);
+    CFRelease(bitrate_number);
+    return status == noErr;
+}
+
 void vt_h264_encoder_destroy(vt_h264_encoder_t *encoder) {
     if (!encoder) {
         return;
@@ -203,7 +217,7 @@ void vt_h264_encoder_destroy(vt_h264_encoder_t *encoder) {
     free(encoder);
 }
 
-bool vt_h264_encoder_encode(vt_h264_encoder_t *encoder, const uint8_t *rgba_data, size_t rgba_size, vt_h264_encoded_frame_t *out_frame) {
+bool vt_h264_encoder_encode(vt_h264_encoder_t *encoder, const uint8_t *rgba_data, size_t rgba_size, bool force_keyframe, vt_h264_encoded_frame_t *out_frame) {
     if (!encoder || !encoder->session || !rgba_data || rgba_size == 0) {
         return false;
     }
@@ -242,7 +256,9 @@ bool vt_h264_encoder_encode(vt_h264_encoder_t *encoder, const uint8_t *rgba_data
     CVPixelBufferUnlockBaseAddress(pixel_buffer, 0);
 
     CMTime pts = CMTimeMakeWithSeconds(CACurrentMediaTime(), 1000000000);
-    OSStatus status = VTCompressionSessionEncodeFrame(encoder->session, pixel_buffer, pts, kCMTimeInvalid, NULL, NULL, NULL);
+    NSDictionary *frame_properties = force_keyframe ? @{(id)kVTEncodeFrameOptionKey_ForceKeyFrame: @YES} : nil;
+    OSStatus status = VTCompressionSessionEncodeFrame(encoder->session, pixel_buffer, pts, kCMTimeInvalid,
+                                                      (__bridge CFDictionaryRef)frame_properties, NULL, NULL);
     CVPixelBufferRelease(pixel_buffer);
 
     if (status != noErr) {
@@ -1,5 +1,8 @@
 pub mod types;
 pub mod dummy;
+pub mod h264;
+pub mod mp4;
+pub mod record;
 #[cfg(target_os = "macos")]
 pub mod macos;
 
@@ -7,6 +10,12 @@ use types::{DecodedFrame, EncodedFrame, RawFrame};
 
 pub trait VideoEncoder {
     fn encode(&mut self, frame: &RawFrame) -> Result<EncodedFrame, CodecError>;
+
+    fn request_keyframe(&mut self) {}
+
+    fn set_bitrate(&mut self, _bitrate: u32) -> Result<(), CodecError> {
+        Ok(())
+    }
 }
 
 pub trait VideoDecoder {
@@ -0,0 +1,424 @@
+use crate::codec::h264::{parse_annex_b, H264Error, NalUnit, NalUnitType, SequenceParameterSet};
+use std::io::Write;
+
+pub const MP4_TIMESCALE: u32 = 90_000;
+
+const TRACK_ID: u32 = 1;
+const DEFAULT_SAMPLE_DURATION: u32 = MP4_TIMESCALE / 60;
+const KEYFRAME_SAMPLE_FLAGS: u32 = 0x0200_0000;
+const DELTA_SAMPLE_FLAGS: u32 = 0x0101_0000;
+const TRUN_FLAGS: u32 = 0x0000_0701;
+const TFHD_DEFAULT_BASE_IS_MOOF: u32 = 0x0002_0000;
+const UNITY_MATRIX: [u32; 9] = [0x0001_0000, 0, 0, 0, 0x0001_0000, 0, 0, 0, 0x4000_0000];
+
+#[derive(Debug)]
+pub enum Mp4Error {
+    Io(std::io::Error),
+    Bitstream(H264Error),
+}
+
+impl From<std::io::Error> for Mp4Error {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<H264Error> for Mp4Error {
+    fn from(error: H264Error) -> Self {
+        Self::Bitstream(error)
+    }
+}
+
+impl std::fmt::Display for Mp4Error {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Mp4Error::Io(error) => write!(formatter, "mp4 io error: {error}"),
+            Mp4Error::Bitstream(error) => write!(formatter, "mp4 bitstream error: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for Mp4Error {}
+
+pub struct FragmentedMp4Writer<W: Write> {
+    writer: W,
+    initialized: bool,
+    sequence_number: u32,
+    first_timestamp_nanos: u64,
+    last_decode_time: Option<u64>,
+    last_duration: u32,
+}
+
+impl<W: Write> FragmentedMp4Writer<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            initialized: false,
+            sequence_number: 1,
+            first_timestamp_nanos: 0,
+            last_decode_time: None,
+            last_duration: DEFAULT_SAMPLE_DURATION,
+        }
+    }
+
+    pub fn write_access_unit(
+        &mut self,
+        timestamp_nanos: u64,
+        access_unit: &[u8],
+    ) -> Result<bool, Mp4Error> {
+        let nal_units = parse_annex_b(access_unit)?;
+        if !self.initialized {
+            if !self.write_header(&nal_units)? {
+                return Ok(false);
+            }
+            self.first_timestamp_nanos = timestamp_nanos;
+        }
+
+        let mut sample = Vec::with_capacity(access_unit.len());
+        for nal_unit in &nal_units {
+            if matches!(
+                nal_unit.unit_type,
+                NalUnitType::Sps | NalUnitType::Pps | NalUnitType::AccessUnitDelimiter
+            ) {
+                continue;
+            }
+            sample.extend_from_slice(&(nal_unit.data.len() as u32).to_be_bytes());
+            sample.extend_from_slice(nal_unit.data);
+        }
+        if sample.is_empty() {
+            return Ok(false);
+        }
+        let is_keyframe = nal_units
+            .iter()
+            .any(|nal_unit| nal_unit.unit_type == NalUnitType::IdrSlice);
+
+        let elapsed_nanos = timestamp_nanos.saturating_sub(self.first_timestamp_nanos);
+        let mut decode_time =
+            (u128::from(elapsed_nanos) * u128::from(MP4_TIMESCALE) / 1_000_000_000) as u64;
+        if let Some(last_decode_time) = self.last_decode_time {
+            decode_time = decode_time.max(last_decode_time + 1);
+            self.last_duration = u32::try_from(decode_time - last_decode_time).unwrap_or(u32::MAX);
+        }
+        self.last_decode_time = Some(decode_time);
+
+        let flags = if is_keyframe {
+            KEYFRAME_SAMPLE_FLAGS
+        } else {
+            DELTA_SAMPLE_FLAGS
+        };
+        let fragment = self.fragment(decode_time, self.last_duration, flags, &sample);
+        self.writer.write_all(&fragment)?;
+        self.writer.write_all(&mp4_box(b"mdat", &sample))?;
+        self.writer.flush()?;
+        self.sequence_number += 1;
+        Ok(true)
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write_header(&mut self, nal_units: &[NalUnit<'_>]) -> Result<bool, Mp4Error> {
+        let find = |unit_type| {
+            nal_units
+                .iter()
+                .find(|nal_unit| nal_unit.unit_type == unit_type)
+        };
+        let (Some(sps_unit), Some(pps_unit)) = (find(NalUnitType::Sps), find(NalUnitType::Pps))
+        else {
+            return Ok(false);
+        };
+        let sps = SequenceParameterSet::parse(sps_unit)?;
+
+        let mut ftyp = Vec::new();
+        ftyp.extend_from_slice(b"isom");
+        ftyp.extend_from_slice(&0x200_u32.to_be_bytes());
+        for brand in [b"isom", b"iso6", b"avc1", b"mp41"] {
+            ftyp.extend_from_slice(brand);
+        }
+        self.writer.write_all(&mp4_box(b"ftyp", &ftyp))?;
+        self.writer
+            .write_all(&movie_box(&sps, sps_unit.data, pps_unit.data))?;
+        self.initialized = true;
+        Ok(true)
+    }
+
+    fn fragment(&self, decode_time: u64, duration: u32, flags: u32, sample: &[u8]) -> Vec<u8> {
+        let build = |data_offset: u32| {
+            let mut mfhd = Vec::new();
+            mfhd.extend_from_slice(&self.sequence_number.to_be_bytes());
+
+            let mut trun = Vec::new();
+            trun.extend_from_slice(&1_u32.to_be_bytes());
+            trun.extend_from_slice(&data_offset.to_be_bytes());
+            trun.extend_from_slice(&duration.to_be_bytes());
+            trun.extend_from_slice(&(sample.len() as u32).to_be_bytes());
+            trun.extend_from_slice(&flags.to_be_bytes());
+
+            let traf = [
+                full_box(
+                    b"tfhd",
+                    0,
+                    TFHD_DEFAULT_BASE_IS_MOOF,
+                    &TRACK_ID.to_be_bytes(),
+                ),
+                full_box(b"tfdt", 1, 0, &decode_time.to_be_bytes()),
+                full_box(b"trun", 0, TRUN_FLAGS, &trun),
+            ]
+            .concat();
+            mp4_box(
+                b"moof",
+                &[full_box(b"mfhd", 0, 0, &mfhd), mp4_box(b"traf", &traf)].concat(),
+            )
+        };
+        let moof_bytes = build(0).len();
+        build((moof_bytes + 8) as u32)
+    }
+}
+
+fn movie_box(sps: &SequenceParameterSet, sps_data: &[u8], pps_data: &[u8]) -> Vec<u8> {
+    let mut mvhd = Vec::new();
+    mvhd.extend_from_slice(&[0; 8]);
+    mvhd.extend_from_slice(&1000_u32.to_be_bytes());
+    mvhd.extend_from_slice(&0_u32.to_be_bytes());
+    mvhd.extend_from_slice(&0x0001_0000_u32.to_be_bytes());
+    mvhd.extend_from_slice(&0x0100_u16.to_be_bytes());
+    mvhd.extend_from_slice(&[0; 10]);
+    push_matrix(&mut mvhd);
+    mvhd.extend_from_slice(&[0; 24]);
+    mvhd.extend_from_slice(&(TRACK_ID + 1).to_be_bytes());
+
+    let mut tkhd = Vec::new();
+    tkhd.extend_from_slice(&[0; 8]);
+    tkhd.extend_from_slice(&TRACK_ID.to_be_bytes());
+    tkhd.extend_from_slice(&[0; 4]);
+    tkhd.extend_from_slice(&0_u32.to_be_bytes());
+    tkhd.extend_from_slice(&[0; 16]);
+    push_matrix(&mut tkhd);
+    tkhd.extend_from_slice(&(sps.width << 16).to_be_bytes());
+    tkhd.extend_from_slice(&(sps.height << 16).to_be_bytes());
+
+    let mut mdhd = Vec::new();
+    mdhd.extend_from_slice(&[0; 8]);
+    mdhd.extend_from_slice(&MP4_TIMESCALE.to_be_bytes());
+    mdhd.extend_from_slice(&0_u32.to_be_bytes());
+    mdhd.extend_from_slice(&0x55C4_u16.to_be_bytes());
+    mdhd.extend_from_slice(&[0; 2]);
+
+    let mut hdlr = Vec::new();
+    hdlr.extend_from_slice(&[0; 4]);
+    hdlr.extend_from_slice(b"vide");
+    hdlr.extend_from_slice(&[0; 12]);
+    hdlr.extend_from_slice(b"VideoHandler\0");
+
+    let dinf = mp4_box(
+        b"dref",
+        &[
+            &[0, 0, 0, 0][..],
+            &1_u32.to_be_bytes(),
+            &full_box(b"url ", 0, 1, &[]),
+        ]
+        .concat(),
+    );
+    let empty_table = 0_u32.to_be_bytes();
+    let stbl = [
+        full_box(
+            b"stsd",
+            0,
+            0,
+            &[
+                &1_u32.to_be_bytes()[..],
+                &sample_entry(sps, sps_data, pps_data),
+            ]
+            .concat(),
+        ),
+        full_box(b"stts", 0, 0, &empty_table),
+        full_box(b"stsc", 0, 0, &empty_table),
+        full_box(b"stsz", 0, 0, &[0; 8]),
+        full_box(b"stco", 0, 0, &empty_table),
+    ]
+    .concat();
+    let minf = [
+        full_box(b"vmhd", 0, 1, &[0; 8]),
+        mp4_box(b"dinf", &dinf),
+        mp4_box(b"stbl", &stbl),
+    ]
+    .concat();
+    let mdia = [
+        full_box(b"mdhd", 0, 0, &mdhd),
+        full_box(b"hdlr", 0, 0, &hdlr),
+        mp4_box(b"minf", &minf),
+    ]
+    .concat();
+    let trak = [full_box(b"tkhd", 0, 3, &tkhd), mp4_box(b"mdia", &mdia)].concat();
+
+    let mut trex = Vec::new();
+    trex.extend_from_slice(&TRACK_ID.to_be_bytes());
+    trex.extend_from_slice(&1_u32.to_be_bytes());
+    trex.extend_from_slice(&[0; 12]);
+
+    mp4_box(
+        b"moov",
+        &[
+            full_box(b"mvhd", 0, 0, &mvhd),
+            mp4_box(b"trak", &trak),
+            mp4_box(b"mvex", &full_box(b"trex", 0, 0, &trex)),
+        ]
+        .concat(),
+    )
+}
+
+fn sample_entry(sps: &SequenceParameterSet, sps_data: &[u8], pps_data: &[u8]) -> Vec<u8> {
+    let mut avcc = vec![1, sps_data[1], sps_data[2], sps_data[3], 0xFF, 0xE1];
+    avcc.extend_from_slice(&(sps_data.len() as u16).to_be_bytes());
+    avcc.extend_from_slice(sps_data);
+    avcc.push(1);
+    avcc.extend_from_slice(&(pps_data.len() as u16).to_be_bytes());
+    avcc.extend_from_slice(pps_data);
+
+    let mut avc1 = Vec::new();
+    avc1.extend_from_slice(&[0; 6]);
+    avc1.extend_from_slice(&1_u16.to_be_bytes());
+    avc1.extend_from_slice(&[0; 16]);
+    avc1.extend_from_slice(&(sps.width as u16).to_be_bytes());
+    avc1.extend_from_slice(&(sps.height as u16).to_be_bytes());
+    avc1.extend_from_slice(&0x0048_0000_u32.to_be_bytes());
+    avc1.extend_from_slice(&0x0048_0000_u32.to_be_bytes());
+    avc1.extend_from_slice(&[0; 4]);
+    avc1.extend_from_slice(&1_u16.to_be_bytes());
+    avc1.extend_from_slice(&[0; 32]);
+    avc1.extend_from_slice(&0x0018_u16.to_be_bytes());
+    avc1.extend_from_slice(&0xFFFF_u16.to_be_bytes());
+    avc1.extend_from_slice(&mp4_box(b"avcC", &avcc));
+    mp4_box(b"avc1", &avc1)
+}
+
+fn push_matrix(body: &mut Vec<u8>) {
+    for value in UNITY_MATRIX {
+        body.extend_from_slice(&value.to_be_bytes());
+    }
+}
+
+fn mp4_box(kind: &[u8; 4], body: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(8 + body.len());
+    output.extend_from_slice(&((8 + body.len()) as u32).to_be_bytes());
+    output.extend_from_slice(kind);
+    output.extend_from_slice(body);
+    output
+}
+
+fn full_box(kind: &[u8; 4], version: u8, flags: u32, body: &[u8]) -> Vec<u8> {
+    let header = (u32::from(version) << 24) | (flags & 0x00FF_FFFF);
+    mp4_box(kind, &[&header.to_be_bytes()[..], body].concat())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FragmentedMp4Writer, DELTA_SAMPLE_FLAGS, KEYFRAME_SAMPLE_FLAGS};
+
+    const SPS: [u8; 8] = [0x67, 0x42, 0xC0, 0x1E, 0xDA, 0x05, 0x07, 0xE4];
+    const PPS: [u8; 4] = [0x68, 0xCE, 0x3C, 0x80];
+    const IDR_SLICE: [u8; 4] = [0x65, 0x88, 0x84, 0x21];
+    const NON_IDR_SLICE: [u8; 3] = [0x41, 0x9A, 0x02];
+
+    fn annex_b(nal_units: &[&[u8]]) -> Vec<u8> {
+        nal_units
+            .iter()
+            .flat_map(|unit| [&[0, 0, 0, 1][..], unit].concat())
+            .collect()
+    }
+
+    fn boxes(data: &[u8]) -> Vec<([u8; 4], &[u8])> {
+        let mut boxes = Vec::new();
+        let mut offset = 0;
+        while offset < data.len() {
+            let size = u32::from_be_bytes(data[offset..offset + 4].try_into().unwrap()) as usize;
+            let kind = data[offset + 4..offset + 8].try_into().unwrap();
+            boxes.push((kind, &data[offset + 8..offset + size]));
+            offset += size;
+        }
+        boxes
+    }
+
+    fn child<'a>(parent: &'a [u8], path: &[&[u8; 4]]) -> &'a [u8] {
+        path.iter().fold(parent, |body, kind| {
+            boxes(body)
+                .into_iter()
+                .find(|(found, _)| found == *kind)
+                .map(|(_, body)| body)
+                .expect("box present")
+        })
+    }
+
+    fn u32_at(data: &[u8], offset: usize) -> u32 {
+        u32::from_be_bytes(data[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn writes_init_segment_then_one_fragment_per_frame() {
+        let mut writer = FragmentedMp4Writer::new(Vec::new());
+        assert!(!writer
+            .write_access_unit(0, &annex_b(&[&NON_IDR_SLICE]))
+            .expect("write"));
+        assert!(writer
+            .write_access_unit(1_000_000_000, &annex_b(&[&SPS, &PPS, &IDR_SLICE]))
+            .expect("write"));
+        assert!(writer
+            .write_access_unit(1_020_000_000, &annex_b(&[&NON_IDR_SLICE]))
+            .expect("write"));
+
+        let output = writer.into_inner();
+        let top_level: Vec<[u8; 4]> = boxes(&output).iter().map(|(kind, _)| *kind).collect();
+        assert_eq!(
+            top_level,
+            vec![*b"ftyp", *b"moov", *b"moof", *b"mdat", *b"moof", *b"mdat"]
+        );
+
+        let moov = boxes(&output)[1].1;
+        let tkhd = child(moov, &[b"trak", b"tkhd"]);
+        assert_eq!(u32_at(tkhd, 76), 320 << 16);
+        assert_eq!(u32_at(tkhd, 80), 240 << 16);
+        let stsd = child(moov, &[b"trak", b"mdia", b"minf", b"stbl", b"stsd"]);
+        let avcc = child(&child(&stsd[8..], &[b"avc1"])[78..], &[b"avcC"]);
+        assert_eq!(&avcc[..6], &[1, 0x42, 0xC0, 0x1E, 0xFF, 0xE1]);
+        assert_eq!(&avcc[8..16], &SPS);
+    }
+
+    #[test]
+    fn fragments_carry_avcc_samples_and_decode_times() {
+        let mut writer = FragmentedMp4Writer::new(Vec::new());
+        writer
+            .write_access_unit(500_000_000, &annex_b(&[&SPS, &PPS, &IDR_SLICE]))
+            .expect("write");
+        writer
+            .write_access_unit(520_000_000, &annex_b(&[&NON_IDR_SLICE]))
+            .expect("write");
+
+        let output = writer.into_inner();
+        let top_level = boxes(&output);
+        let (first_moof, first_mdat) = (top_level[2].1, top_level[3].1);
+        let (second_moof, second_mdat) = (top_level[4].1, top_level[5].1);
+
+        assert_eq!(first_mdat, &[0, 0, 0, 4, 0x65, 0x88, 0x84, 0x21]);
+        assert_eq!(second_mdat, &[0, 0, 0, 3, 0x41, 0x9A, 0x02]);
+
+        let first_tfdt = child(first_moof, &[b"traf", b"tfdt"]);
+        let second_tfdt = child(second_moof, &[b"traf", b"tfdt"]);
+        assert_eq!(u64::from_be_bytes(first_tfdt[4..12].try_into().unwrap()), 0);
+        assert_eq!(
+            u64::from_be_bytes(second_tfdt[4..12].try_into().unwrap()),
+            1800
+        );
+
+        let first_trun = child(first_moof, &[b"traf", b"trun"]);
+        let second_trun = child(second_moof, &[b"traf", b"trun"]);
+        assert_eq!(u32_at(first_trun, 8) as usize, first_moof.len() + 16);
+        assert_eq!(u32_at(first_trun, 16), 8);
+        assert_eq!(u32_at(first_trun, 20), KEYFRAME_SAMPLE_FLAGS);
+        assert_eq!(u32_at(second_trun, 12), 1800);
+        assert_eq!(u32_at(second_trun, 20), DELTA_SAMPLE_FLAGS);
+
+        let mfhd = child(second_moof, &[b"mfhd"]);
+        assert_eq!(u32_at(mfhd, 4), 2);
+    }
+}
@@ -0,0 +1,188 @@
+use crate::codec::mp4::{FragmentedMp4Writer, Mp4Error};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordFormat {
+    AnnexB,
+    FragmentedMp4,
+}
+
+impl RecordFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "h264" | "264" => Some(RecordFormat::AnnexB),
+            "mp4" => Some(RecordFormat::FragmentedMp4),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for RecordFormat {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecordFormat::AnnexB => write!(formatter, "annex b elementary stream"),
+            RecordFormat::FragmentedMp4 => write!(formatter, "fragmented mp4"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum RecordError {
+    Io(std::io::Error),
+    Mp4(Mp4Error),
+    UnsupportedExtension,
+}
+
+impl From<std::io::Error> for RecordError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<Mp4Error> for RecordError {
+    fn from(error: Mp4Error) -> Self {
+        Self::Mp4(error)
+    }
+}
+
+impl std::fmt::Display for RecordError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecordError::Io(error) => write!(formatter, "record io error: {error}"),
+            RecordError::Mp4(error) => write!(formatter, "{error}"),
+            RecordError::UnsupportedExtension => {
+                write!(formatter, "record path must end in .h264, .264 or .mp4")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RecorderStats {
+    pub frames_written: u64,
+    pub frames_skipped: u64,
+}
+
+enum RecordSink<W: Write> {
+    AnnexB(W),
+    FragmentedMp4(FragmentedMp4Writer<W>),
+}
+
+pub struct Recorder<W: Write> {
+    sink: RecordSink<W>,
+    stats: RecorderStats,
+}
+
+pub type FileRecorder = Recorder<BufWriter<File>>;
+
+impl FileRecorder {
+    pub fn create(path: &Path) -> Result<Self, RecordError> {
+        let format = RecordFormat::from_path(path).ok_or(RecordError::UnsupportedExtension)?;
+        let file = File::create(path)?;
+        Ok(Recorder::new(BufWriter::new(file), format))
+    }
+}
+
+impl<W: Write> Recorder<W> {
+    pub fn new(writer: W, format: RecordFormat) -> Self {
+        let sink = match format {
+            RecordFormat::AnnexB => RecordSink::AnnexB(writer),
+            RecordFormat::FragmentedMp4 => {
+                RecordSink::FragmentedMp4(FragmentedMp4Writer::new(writer))
+            }
+        };
+        Self {
+            sink,
+            stats: RecorderStats::default(),
+        }
+    }
+
+    pub fn format(&self) -> RecordFormat {
+        match self.sink {
+            RecordSink::AnnexB(_) => RecordFormat::AnnexB,
+            RecordSink::FragmentedMp4(_) => RecordFormat::FragmentedMp4,
+        }
+    }
+
+    pub fn record(&mut self, timestamp_nanos: u64, payload: &[u8]) -> Result<(), RecordError> {
+        let written = match &mut self.sink {
+            RecordSink::AnnexB(writer) => {
+                writer.write_all(payload)?;
+                writer.flush()?;
+                true
+            }
+            RecordSink::FragmentedMp4(writer) => {
+                writer.write_access_unit(timestamp_nanos, payload)?
+            }
+        };
+        if written {
+            self.stats.frames_written += 1;
+        } else {
+            self.stats.frames_skipped += 1;
+        }
+        Ok(())
+    }
+
+    pub fn stats(&self) -> RecorderStats {
+        self.stats
+    }
+
+    pub fn into_inner(self) -> W {
+        match self.sink {
+            RecordSink::AnnexB(writer) => writer,
+            RecordSink::FragmentedMp4(writer) => writer.into_inner(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RecordFormat, Recorder};
+    use std::path::Path;
+
+    #[test]
+    fn picks_format_from_extension() {
+        assert_eq!(
+            RecordFormat::from_path(Path::new("capture.h264")),
+            Some(RecordFormat::AnnexB)
+        );
+        assert_eq!(
+            RecordFormat::from_path(Path::new("capture.MP4")),
+            Some(RecordFormat::FragmentedMp4)
+        );
+        assert_eq!(RecordFormat::from_path(Path::new("capture.mkv")), None);
+        assert_eq!(RecordFormat::from_path(Path::new("capture")), None);
+    }
+
+    #[test]
+    fn annex_b_recording_keeps_exact_bytes() {
+        let mut recorder = Recorder::new(Vec::new(), RecordFormat::AnnexB);
+        recorder
+            .record(1, &[0, 0, 0, 1, 0x65, 0x88])
+            .expect("record");
+        recorder
+            .record(2, &[0, 0, 0, 1, 0x41, 0x9A])
+            .expect("record");
+
+        assert_eq!(recorder.stats().frames_written, 2);
+        assert_eq!(
+            recorder.into_inner(),
+            vec![0, 0, 0, 1, 0x65, 0x88, 0, 0, 0, 1, 0x41, 0x9A]
+        );
+    }
+
+    #[test]
+    fn mp4_recording_skips_frames_before_parameter_sets() {
+        let mut recorder = Recorder::new(Vec::new(), RecordFormat::FragmentedMp4);
+        recorder
+            .record(1, &[0, 0, 0, 1, 0x41, 0x9A, 0x02])
+            .expect("record");
+
+        assert_eq!(recorder.stats().frames_skipped, 1);
+        assert!(recorder.into_inner().is_empty());
+    }
+}
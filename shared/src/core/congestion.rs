@@ -0,0 +1,294 @@
+use crate::core::control::ReceiverReport;
+use std::time::Duration;
+
+const RECEIVED_BITRATE_HEADROOM: f64 = 1.5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CongestionConfig {
+    pub min_bitrate: u32,
+    pub max_bitrate: u32,
+    pub increase_factor: f64,
+    pub decrease_factor: f64,
+    pub overuse_delay_trend: Duration,
+    pub low_loss_fraction: u8,
+    pub high_loss_fraction: u8,
+}
+
+impl Default for CongestionConfig {
+    fn default() -> Self {
+        Self {
+            min_bitrate: 250_000,
+            max_bitrate: 20_000_000,
+            increase_factor: 1.08,
+            decrease_factor: 0.85,
+            overuse_delay_trend: Duration::from_millis(2),
+            low_loss_fraction: 5,
+            high_loss_fraction: 26,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BandwidthUsage {
+    Normal,
+    Overusing,
+    Underusing,
+}
+
+#[derive(Debug, Clone)]
+pub struct CongestionController {
+    config: CongestionConfig,
+    target_bitrate: u32,
+    usage: BandwidthUsage,
+}
+
+impl CongestionController {
+    pub fn new(config: CongestionConfig, initial_bitrate: u32) -> Self {
+        Self {
+            config,
+            target_bitrate: initial_bitrate.clamp(config.min_bitrate, config.max_bitrate),
+            usage: BandwidthUsage::Normal,
+        }
+    }
+
+    pub fn target_bitrate(&self) -> u32 {
+        self.target_bitrate
+    }
+
+    pub fn usage(&self) -> BandwidthUsage {
+        self.usage
+    }
+
+    pub fn on_report(&mut self, report: &ReceiverReport) -> u32 {
+        let current = f64::from(self.target_bitrate);
+        let received = f64::from(report.received_bitrate);
+        self.usage = self.classify(report.delay_trend_micros);
+
+        let loss_based = if report.fraction_lost > self.config.high_loss_fraction {
+            let loss = f64::from(report.fraction_lost) / 256.0;
+            current * (1.0 - 0.5 * loss)
+        } else if report.fraction_lost < self.config.low_loss_fraction
+            && self.usage == BandwidthUsage::Normal
+        {
+            let increased = current * self.config.increase_factor;
+            if received > 0.0 {
+                increased.min(current.max(received * RECEIVED_BITRATE_HEADROOM))
+            } else {
+                increased
+            }
+        } else {
+            current
+        };
+
+        let delay_based = match self.usage {
+            BandwidthUsage::Overusing if received > 0.0 => {
+                self.config.decrease_factor * received.min(current)
+            }
+            BandwidthUsage::Overusing => self.config.decrease_factor * current,
+            BandwidthUsage::Normal | BandwidthUsage::Underusing => f64::INFINITY,
+        };
+
+        let target = loss_based.min(delay_based).round();
+        self.target_bitrate = target.clamp(
+            f64::from(self.config.min_bitrate),
+            f64::from(self.config.max_bitrate),
+        ) as u32;
+        self.target_bitrate
+    }
+
+    fn classify(&self, delay_trend_micros: i32) -> BandwidthUsage {
+        let threshold = self.config.overuse_delay_trend.as_micros() as i64;
+        let trend = i64::from(delay_trend_micros);
+        if trend > threshold {
+            BandwidthUsage::Overusing
+        } else if trend < -threshold {
+            BandwidthUsage::Underusing
+        } else {
+            BandwidthUsage::Normal
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BandwidthUsage, CongestionConfig, CongestionController};
+    use crate::core::control::ReceiverReport;
+
+    fn report(fraction_lost: u8, received_bitrate: u32, delay_trend_micros: i32) -> ReceiverReport {
+        ReceiverReport {
+            fraction_lost,
+            received_bitrate,
+            delay_trend_micros,
+            ..ReceiverReport::default()
+        }
+    }
+
+    fn replay(controller: &mut CongestionController, trace: &[ReceiverReport]) -> Vec<u32> {
+        trace
+            .iter()
+            .map(|report| controller.on_report(report))
+            .collect()
+    }
+
+    #[test]
+    fn clean_link_ramps_up_within_received_headroom() {
+        let mut controller = CongestionController::new(CongestionConfig::default(), 1_000_000);
+        let trace = [
+            report(0, 1_000_000, 0),
+            report(0, 1_080_000, 100),
+            report(0, 1_000_000, -100),
+            report(0, 700_000, 0),
+        ];
+
+        assert_eq!(
+            replay(&mut controller, &trace),
+            vec![1_080_000, 1_166_400, 1_259_712, 1_259_712]
+        );
+    }
+
+    #[test]
+    fn heavy_loss_backs_off_proportionally() {
+        let mut controller = CongestionController::new(CongestionConfig::default(), 4_000_000);
+        let trace = [
+            report(64, 4_000_000, 0),
+            report(12, 3_500_000, 0),
+            report(128, 3_500_000, 0),
+        ];
+
+        assert_eq!(
+            replay(&mut controller, &trace),
+            vec![3_500_000, 3_500_000, 2_625_000]
+        );
+    }
+
+    #[test]
+    fn rising_delay_drops_below_received_bitrate() {
+        let mut controller = CongestionController::new(CongestionConfig::default(), 8_000_000);
+
+        assert_eq!(
+            controller.on_report(&report(0, 6_000_000, 5_000)),
+            5_100_000
+        );
+        assert_eq!(controller.usage(), BandwidthUsage::Overusing);
+
+        assert_eq!(
+            controller.on_report(&report(0, 5_000_000, -5_000)),
+            5_100_000
+        );
+        assert_eq!(controller.usage(), BandwidthUsage::Underusing);
+
+        assert_eq!(controller.on_report(&report(0, 5_000_000, 0)), 5_508_000);
+        assert_eq!(controller.usage(), BandwidthUsage::Normal);
+    }
+
+    #[test]
+    fn stays_within_configured_bounds() {
+        let config = CongestionConfig {
+            min_bitrate: 500_000,
+            max_bitrate: 2_000_000,
+            ..CongestionConfig::default()
+        };
+        let mut controller = CongestionController::new(config, 10_000_000);
+        assert_eq!(controller.target_bitrate(), 2_000_000);
+
+        for _ in 0..20 {
+            controller.on_report(&report(200, 100_000, 50_000));
+        }
+        assert_eq!(controller.target_bitrate(), 500_000);
+
+        for _ in 0..50 {
+            controller.on_report(&report(0, 0, 0));
+        }
+        assert_eq!(controller.target_bitrate(), 2_000_000);
+    }
+
+    struct Bottleneck {
+        capacity_bitrate: f64,
+        buffer_bits: f64,
+        queued_bits: f64,
+        previous_delay_micros: f64,
+    }
+
+    impl Bottleneck {
+        fn report(&mut self, send_bitrate: u32, interval_secs: f64) -> ReceiverReport {
+            let sent_bits = f64::from(send_bitrate) * interval_secs;
+            let delivered_bits =
+                (self.queued_bits + sent_bits).min(self.capacity_bitrate * interval_secs);
+            let mut queued_bits = self.queued_bits + sent_bits - delivered_bits;
+            let lost_bits = (queued_bits - self.buffer_bits).max(0.0);
+            queued_bits -= lost_bits;
+
+            let delay_micros = (self.queued_bits + queued_bits) / 2.0 / self.capacity_bitrate * 1e6;
+            let delay_trend_micros = delay_micros - self.previous_delay_micros;
+            self.previous_delay_micros = delay_micros;
+            self.queued_bits = queued_bits;
+
+            ReceiverReport {
+                fraction_lost: (lost_bits / sent_bits.max(1.0) * 256.0).min(255.0) as u8,
+                received_bitrate: (delivered_bits / interval_secs) as u32,
+                delay_trend_micros: delay_trend_micros.round() as i32,
+                ..ReceiverReport::default()
+            }
+        }
+    }
+
+    #[test]
+    fn follows_a_bottleneck_through_a_capacity_drop() {
+        let mut controller = CongestionController::new(CongestionConfig::default(), 2_000_000);
+        let mut link = Bottleneck {
+            capacity_bitrate: 8_000_000.0,
+            buffer_bits: 400_000.0,
+            queued_bits: 0.0,
+            previous_delay_micros: 0.0,
+        };
+        let mut trace = Vec::new();
+        for step in 0..160 {
+            link.capacity_bitrate = if (40..80).contains(&step) {
+                3_000_000.0
+            } else {
+                8_000_000.0
+            };
+            let report = link.report(controller.target_bitrate(), 0.25);
+            controller.on_report(&report);
+            trace.push((report, controller.target_bitrate()));
+        }
+
+        let targets =
+            |range: std::ops::Range<usize>| trace[range].iter().map(|(_, target)| *target);
+        assert!(targets(20..40).all(|target| (6_500_000..=9_000_000).contains(&target)));
+        assert!(trace[40].0.fraction_lost > 0);
+        assert!(trace[41].1 < 3_000_000);
+        assert!(targets(42..80).all(|target| (2_000_000..=3_300_000).contains(&target)));
+        assert!(targets(100..160).all(|target| (6_500_000..=9_000_000).contains(&target)));
+        assert_eq!(
+            trace
+                .iter()
+                .filter(|(report, _)| report.fraction_lost > 0)
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn replaying_a_trace_is_deterministic() {
+        let trace: Vec<ReceiverReport> = (0..200_u32)
+            .map(|step| {
+                report(
+                    (step * 37 % 40) as u8,
+                    2_000_000 + step * 10_000,
+                    (step as i32 % 7 - 3) * 1_000,
+                )
+            })
+            .collect();
+
+        let first = replay(
+            &mut CongestionController::new(CongestionConfig::default(), 3_000_000),
+            &trace,
+        );
+        let second = replay(
+            &mut CongestionController::new(CongestionConfig::default(), 3_000_000),
+            &trace,
+        );
+        assert_eq!(first, second);
+    }
+}
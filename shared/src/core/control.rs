@@ -0,0 +1,310 @@
+use crate::core::envelope::{EnvelopeError, EnvelopeHeader, MessageKind, ENVELOPE_HEADER_LENGTH};
+use crate::core::sequence::SequenceNumber;
+
+pub const MAX_NACK_ENTRIES: usize = 256;
+
+const CONTROL_HEADER_LENGTH: usize = 4;
+const CONTROL_TYPE_NACK: u8 = 1;
+const CONTROL_TYPE_KEYFRAME_REQUEST: u8 = 2;
+const CONTROL_TYPE_RECEIVER_REPORT: u8 = 3;
+const CONTROL_TYPE_PATH_PROBE: u8 = 4;
+const CONTROL_TYPE_PATH_PROBE_ACK: u8 = 5;
+const CONTROL_TYPE_PATH_MTU: u8 = 6;
+const RECEIVER_REPORT_LENGTH: usize = 24;
+const DATAGRAM_SIZE_LENGTH: usize = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControlMessage {
+    Nack {
+        sequence_numbers: Vec<SequenceNumber>,
+    },
+    KeyframeRequest,
+    ReceiverReport(ReceiverReport),
+    PathProbe {
+        datagram_bytes: u32,
+    },
+    PathProbeAck {
+        datagram_bytes: u32,
+    },
+    PathMtu {
+        datagram_bytes: u32,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReceiverReport {
+    pub fraction_lost: u8,
+    pub cumulative_lost: i32,
+    pub extended_highest_sequence: u32,
+    pub jitter_micros: u32,
+    pub received_bitrate: u32,
+    pub delay_trend_micros: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControlError {
+    BufferTooSmall,
+    Envelope(EnvelopeError),
+    UnexpectedMessageKind(MessageKind),
+    UnknownControlType(u8),
+    TooManyEntries,
+}
+
+impl From<EnvelopeError> for ControlError {
+    fn from(error: EnvelopeError) -> Self {
+        match error {
+            EnvelopeError::BufferTooSmall => Self::BufferTooSmall,
+            other => Self::Envelope(other),
+        }
+    }
+}
+
+impl std::fmt::Display for ControlError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ControlError::BufferTooSmall => write!(formatter, "control message too small"),
+            ControlError::Envelope(error) => write!(formatter, "{error}"),
+            ControlError::UnexpectedMessageKind(kind) => {
+                write!(formatter, "expected control message, got {kind:?}")
+            }
+            ControlError::UnknownControlType(value) => {
+                write!(formatter, "unknown control message type {value}")
+            }
+            ControlError::TooManyEntries => {
+                write!(formatter, "control message has too many entries")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ControlError {}
+
+impl ControlMessage {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buffer = vec![0_u8; ENVELOPE_HEADER_LENGTH + CONTROL_HEADER_LENGTH];
+        EnvelopeHeader::new(MessageKind::Control)
+            .encode(&mut buffer[..ENVELOPE_HEADER_LENGTH])
+            .expect("envelope buffer is sized correctly");
+
+        match self {
+            ControlMessage::Nack { sequence_numbers } => {
+                let count = sequence_numbers.len().min(MAX_NACK_ENTRIES);
+                buffer[ENVELOPE_HEADER_LENGTH] = CONTROL_TYPE_NACK;
+                buffer[ENVELOPE_HEADER_LENGTH + 2..ENVELOPE_HEADER_LENGTH + 4]
+                    .copy_from_slice(&(count as u16).to_be_bytes());
+                for sequence_number in &sequence_numbers[..count] {
+                    buffer.extend_from_slice(&sequence_number.value().to_be_bytes());
+                }
+            }
+            ControlMessage::KeyframeRequest => {
+                buffer[ENVELOPE_HEADER_LENGTH] = CONTROL_TYPE_KEYFRAME_REQUEST;
+            }
+            ControlMessage::ReceiverReport(report) => {
+                buffer[ENVELOPE_HEADER_LENGTH] = CONTROL_TYPE_RECEIVER_REPORT;
+                buffer[ENVELOPE_HEADER_LENGTH + 2..ENVELOPE_HEADER_LENGTH + 4]
+                    .copy_from_slice(&1_u16.to_be_bytes());
+                buffer.extend_from_slice(&[report.fraction_lost, 0, 0, 0]);
+                buffer.extend_from_slice(&report.cumulative_lost.to_be_bytes());
+                buffer.extend_from_slice(&report.extended_highest_sequence.to_be_bytes());
+                buffer.extend_from_slice(&report.jitter_micros.to_be_bytes());
+                buffer.extend_from_slice(&report.received_bitrate.to_be_bytes());
+                buffer.extend_from_slice(&report.delay_trend_micros.to_be_bytes());
+            }
+            ControlMessage::PathProbe { datagram_bytes } => {
+                buffer[ENVELOPE_HEADER_LENGTH] = CONTROL_TYPE_PATH_PROBE;
+                buffer.extend_from_slice(&datagram_bytes.to_be_bytes());
+                let padded_length = (*datagram_bytes as usize).max(buffer.len());
+                buffer.resize(padded_length, 0);
+            }
+            ControlMessage::PathProbeAck { datagram_bytes } => {
+                buffer[ENVELOPE_HEADER_LENGTH] = CONTROL_TYPE_PATH_PROBE_ACK;
+                buffer.extend_from_slice(&datagram_bytes.to_be_bytes());
+            }
+            ControlMessage::PathMtu { datagram_bytes } => {
+                buffer[ENVELOPE_HEADER_LENGTH] = CONTROL_TYPE_PATH_MTU;
+                buffer.extend_from_slice(&datagram_bytes.to_be_bytes());
+            }
+        }
+
+        buffer
+    }
+
+    pub fn decode(buffer: &[u8]) -> Result<Self, ControlError> {
+        let envelope = EnvelopeHeader::decode(buffer)?;
+        if envelope.kind != MessageKind::Control {
+            return Err(ControlError::UnexpectedMessageKind(envelope.kind));
+        }
+
+        if buffer.len() < ENVELOPE_HEADER_LENGTH + CONTROL_HEADER_LENGTH {
+            return Err(ControlError::BufferTooSmall);
+        }
+
+        let header =
+            &buffer[ENVELOPE_HEADER_LENGTH..ENVELOPE_HEADER_LENGTH + CONTROL_HEADER_LENGTH];
+        let body = &buffer[ENVELOPE_HEADER_LENGTH + CONTROL_HEADER_LENGTH..];
+        match header[0] {
+            CONTROL_TYPE_NACK => {
+                let count = u16::from_be_bytes([header[2], header[3]]) as usize;
+                if count > MAX_NACK_ENTRIES {
+                    return Err(ControlError::TooManyEntries);
+                }
+                if body.len() < count * 4 {
+                    return Err(ControlError::BufferTooSmall);
+                }
+
+                let sequence_numbers = body[..count * 4]
+                    .chunks_exact(4)
+                    .map(|bytes| SequenceNumber::new(u32::from_be_bytes(bytes.try_into().unwrap())))
+                    .collect();
+                Ok(ControlMessage::Nack { sequence_numbers })
+            }
+            CONTROL_TYPE_KEYFRAME_REQUEST => Ok(ControlMessage::KeyframeRequest),
+            CONTROL_TYPE_RECEIVER_REPORT => {
+                if body.len() < RECEIVER_REPORT_LENGTH {
+                    return Err(ControlError::BufferTooSmall);
+                }
+
+                let word =
+                    |offset: usize| -> [u8; 4] { body[offset..offset + 4].try_into().unwrap() };
+                Ok(ControlMessage::ReceiverReport(ReceiverReport {
+                    fraction_lost: body[0],
+                    cumulative_lost: i32::from_be_bytes(word(4)),
+                    extended_highest_sequence: u32::from_be_bytes(word(8)),
+                    jitter_micros: u32::from_be_bytes(word(12)),
+                    received_bitrate: u32::from_be_bytes(word(16)),
+                    delay_trend_micros: i32::from_be_bytes(word(20)),
+                }))
+            }
+            CONTROL_TYPE_PATH_PROBE | CONTROL_TYPE_PATH_PROBE_ACK | CONTROL_TYPE_PATH_MTU => {
+                if body.len() < DATAGRAM_SIZE_LENGTH {
+                    return Err(ControlError::BufferTooSmall);
+                }
+
+                let datagram_bytes = u32::from_be_bytes(body[..4].try_into().unwrap());
+                match header[0] {
+                    CONTROL_TYPE_PATH_PROBE if buffer.len() < datagram_bytes as usize => {
+                        Err(ControlError::BufferTooSmall)
+                    }
+                    CONTROL_TYPE_PATH_PROBE => Ok(ControlMessage::PathProbe { datagram_bytes }),
+                    CONTROL_TYPE_PATH_PROBE_ACK => {
+                        Ok(ControlMessage::PathProbeAck { datagram_bytes })
+                    }
+                    _ => Ok(ControlMessage::PathMtu { datagram_bytes }),
+                }
+            }
+            other => Err(ControlError::UnknownControlType(other)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ControlError, ControlMessage, ReceiverReport, MAX_NACK_ENTRIES};
+    use crate::core::envelope::{EnvelopeHeader, MessageKind, ENVELOPE_HEADER_LENGTH};
+    use crate::core::sequence::SequenceNumber;
+
+    #[test]
+    fn nack_round_trip() {
+        let message = ControlMessage::Nack {
+            sequence_numbers: vec![SequenceNumber::new(7), SequenceNumber::new(u32::MAX)],
+        };
+
+        let decoded = ControlMessage::decode(&message.encode()).expect("decode");
+        assert_eq!(decoded, message);
+    }
+
+    #[test]
+    fn nack_encode_caps_entries() {
+        let message = ControlMessage::Nack {
+            sequence_numbers: (0..(MAX_NACK_ENTRIES as u32 + 10))
+                .map(SequenceNumber::new)
+                .collect(),
+        };
+
+        match ControlMessage::decode(&message.encode()).expect("decode") {
+            ControlMessage::Nack { sequence_numbers } => {
+                assert_eq!(sequence_numbers.len(), MAX_NACK_ENTRIES);
+            }
+            other => panic!("unexpected message {other:?}"),
+        }
+    }
+
+    #[test]
+    fn keyframe_request_round_trip() {
+        let message = ControlMessage::KeyframeRequest;
+
+        let decoded = ControlMessage::decode(&message.encode()).expect("decode");
+        assert_eq!(decoded, message);
+    }
+
+    #[test]
+    fn receiver_report_round_trip() {
+        let message = ControlMessage::ReceiverReport(ReceiverReport {
+            fraction_lost: 26,
+            cumulative_lost: -3,
+            extended_highest_sequence: u32::MAX,
+            jitter_micros: 1_250,
+            received_bitrate: 4_000_000,
+            delay_trend_micros: -800,
+        });
+        let encoded = message.encode();
+
+        assert_eq!(ControlMessage::decode(&encoded), Ok(message));
+        assert_eq!(
+            ControlMessage::decode(&encoded[..encoded.len() - 1]),
+            Err(ControlError::BufferTooSmall)
+        );
+    }
+
+    #[test]
+    fn path_probe_is_padded_to_datagram_size() {
+        let message = ControlMessage::PathProbe {
+            datagram_bytes: 1400,
+        };
+        let encoded = message.encode();
+
+        assert_eq!(encoded.len(), 1400);
+        assert_eq!(ControlMessage::decode(&encoded), Ok(message));
+        assert_eq!(
+            ControlMessage::decode(&encoded[..1399]),
+            Err(ControlError::BufferTooSmall)
+        );
+    }
+
+    #[test]
+    fn path_mtu_messages_round_trip() {
+        for message in [
+            ControlMessage::PathProbeAck {
+                datagram_bytes: 8972,
+            },
+            ControlMessage::PathMtu {
+                datagram_bytes: 1472,
+            },
+        ] {
+            assert_eq!(ControlMessage::decode(&message.encode()), Ok(message));
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_control_type() {
+        let mut buffer = vec![0_u8; ENVELOPE_HEADER_LENGTH + 4];
+        EnvelopeHeader::new(MessageKind::Control)
+            .encode(&mut buffer)
+            .unwrap();
+        buffer[ENVELOPE_HEADER_LENGTH] = 0xEE;
+
+        let result = ControlMessage::decode(&buffer);
+        assert_eq!(result, Err(ControlError::UnknownControlType(0xEE)));
+    }
+
+    #[test]
+    fn rejects_truncated_nack() {
+        let message = ControlMessage::Nack {
+            sequence_numbers: vec![SequenceNumber::new(1), SequenceNumber::new(2)],
+        };
+        let encoded = message.encode();
+
+        let result = ControlMessage::decode(&encoded[..encoded.len() - 1]);
+        assert_eq!(result, Err(ControlError::BufferTooSmall));
+    }
+}
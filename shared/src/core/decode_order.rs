@@ -0,0 +1,326 @@
+use crate::core::reassembler::ReassembledFrame;
+use crate::core::sequence::serial_distance;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+const GIVEN_UP_CAPACITY: usize = 64;
+const RESYNC_DISTANCE_FACTOR: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeOrderConfig {
+    pub max_wait: Duration,
+    pub max_held_frames: usize,
+}
+
+impl Default for DecodeOrderConfig {
+    fn default() -> Self {
+        Self {
+            max_wait: Duration::from_millis(50),
+            max_held_frames: 8,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderedFrame {
+    pub frame: ReassembledFrame,
+    pub missing_before: u32,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DecodeOrderStats {
+    pub released: u64,
+    pub skipped: u64,
+    pub stale: u64,
+    pub resynced: u64,
+}
+
+#[derive(Debug, Clone)]
+struct HeldFrame {
+    frame: ReassembledFrame,
+    held_since: Instant,
+}
+
+#[derive(Debug, Clone)]
+pub struct DecodeOrder {
+    config: DecodeOrderConfig,
+    next_expected: Option<u32>,
+    held: Vec<HeldFrame>,
+    given_up: VecDeque<u32>,
+    pending_gap: u32,
+    consecutive_stale: usize,
+    stats: DecodeOrderStats,
+}
+
+impl DecodeOrder {
+    pub fn new(config: DecodeOrderConfig) -> Self {
+        Self {
+            config,
+            next_expected: None,
+            held: Vec::new(),
+            given_up: VecDeque::with_capacity(GIVEN_UP_CAPACITY),
+            pending_gap: 0,
+            consecutive_stale: 0,
+            stats: DecodeOrderStats::default(),
+        }
+    }
+
+    pub fn push(&mut self, frame: ReassembledFrame, now: Instant) {
+        let frame_identifier = frame.frame_identifier;
+        if self.is_stale(frame_identifier) {
+            self.consecutive_stale += 1;
+            if !self.is_far_behind(frame_identifier)
+                && self.consecutive_stale < self.config.max_held_frames.max(1)
+            {
+                self.stats.stale += 1;
+                return;
+            }
+            self.resync();
+        }
+        self.consecutive_stale = 0;
+
+        let reference = self
+            .next_expected
+            .or_else(|| self.held.first().map(|held| held.frame.frame_identifier))
+            .unwrap_or(frame_identifier);
+        match self
+            .held
+            .binary_search_by_key(&serial_distance(reference, frame_identifier), |held| {
+                serial_distance(reference, held.frame.frame_identifier)
+            }) {
+            Ok(_) => {}
+            Err(position) => self.held.insert(
+                position,
+                HeldFrame {
+                    frame,
+                    held_since: now,
+                },
+            ),
+        }
+    }
+
+    pub fn give_up(&mut self, frame_identifier: u32) {
+        if self.is_stale(frame_identifier) || self.given_up.contains(&frame_identifier) {
+            return;
+        }
+        if self.given_up.len() == GIVEN_UP_CAPACITY {
+            self.given_up.pop_front();
+        }
+        self.given_up.push_back(frame_identifier);
+    }
+
+    pub fn pop(&mut self, now: Instant) -> Option<OrderedFrame> {
+        loop {
+            let head = self.held.first()?;
+            let head_identifier = head.frame.frame_identifier;
+
+            let Some(next_expected) = self.next_expected else {
+                return Some(self.release());
+            };
+            if head_identifier == next_expected {
+                return Some(self.release());
+            }
+
+            if let Some(position) = self
+                .given_up
+                .iter()
+                .position(|&given_up| given_up == next_expected)
+            {
+                self.given_up.remove(position);
+                self.skip(1);
+                continue;
+            }
+
+            let waited = now.saturating_duration_since(head.held_since);
+            if waited >= self.config.max_wait || self.held.len() > self.config.max_held_frames {
+                let missing = serial_distance(next_expected, head_identifier).max(0) as u32;
+                self.skip(missing);
+                return Some(self.release());
+            }
+            return None;
+        }
+    }
+
+    pub fn stats(&self) -> DecodeOrderStats {
+        self.stats
+    }
+
+    fn is_stale(&self, frame_identifier: u32) -> bool {
+        self.next_expected
+            .is_some_and(|next| serial_distance(next, frame_identifier) < 0)
+    }
+
+    fn is_far_behind(&self, frame_identifier: u32) -> bool {
+        let limit = (self.config.max_held_frames.max(1) * RESYNC_DISTANCE_FACTOR)
+            .min(i32::MAX as usize) as i32;
+        self.next_expected
+            .is_some_and(|next| serial_distance(next, frame_identifier) < -limit)
+    }
+
+    fn resync(&mut self) {
+        self.next_expected = None;
+        self.held.clear();
+        self.given_up.clear();
+        self.pending_gap = 0;
+        self.stats.resynced += 1;
+    }
+
+    fn skip(&mut self, count: u32) {
+        if let Some(next_expected) = self.next_expected {
+            self.next_expected = Some(next_expected.wrapping_add(count));
+        }
+        self.pending_gap += count;
+        self.stats.skipped += u64::from(count);
+    }
+
+    fn release(&mut self) -> OrderedFrame {
+        let held = self.held.remove(0);
+        let frame_identifier = held.frame.frame_identifier;
+        let next_expected = frame_identifier.wrapping_add(1);
+        self.next_expected = Some(next_expected);
+        self.given_up
+            .retain(|&given_up| serial_distance(next_expected, given_up) >= 0);
+        self.stats.released += 1;
+
+        OrderedFrame {
+            frame: held.frame,
+            missing_before: std::mem::take(&mut self.pending_gap),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DecodeOrder, DecodeOrderConfig};
+    use crate::core::reassembler::ReassembledFrame;
+    use std::time::{Duration, Instant};
+
+    fn frame(frame_identifier: u32) -> ReassembledFrame {
+        ReassembledFrame {
+            frame_identifier,
+            timestamp_nanos: u64::from(frame_identifier),
+            payload: vec![0],
+        }
+    }
+
+    fn released(order: &mut DecodeOrder, now: Instant) -> Vec<(u32, u32)> {
+        std::iter::from_fn(|| order.pop(now))
+            .map(|ordered| (ordered.frame.frame_identifier, ordered.missing_before))
+            .collect()
+    }
+
+    #[test]
+    fn holds_newer_frame_until_older_completes() {
+        let mut order = DecodeOrder::new(DecodeOrderConfig::default());
+        let now = Instant::now();
+
+        order.push(frame(10), now);
+        assert_eq!(released(&mut order, now), vec![(10, 0)]);
+
+        order.push(frame(12), now);
+        assert!(released(&mut order, now).is_empty());
+
+        order.push(frame(11), now);
+        assert_eq!(released(&mut order, now), vec![(11, 0), (12, 0)]);
+    }
+
+    #[test]
+    fn never_releases_older_frame_after_newer() {
+        let mut order = DecodeOrder::new(DecodeOrderConfig::default());
+        let start = Instant::now();
+
+        order.push(frame(1), start);
+        order.push(frame(3), start);
+        let later = start + Duration::from_millis(60);
+        assert_eq!(released(&mut order, later), vec![(1, 0), (3, 1)]);
+
+        order.push(frame(2), later);
+        assert!(released(&mut order, later).is_empty());
+        let stats = order.stats();
+        assert_eq!(stats.stale, 1);
+        assert_eq!(stats.skipped, 1);
+    }
+
+    #[test]
+    fn skips_given_up_frames_immediately() {
+        let mut order = DecodeOrder::new(DecodeOrderConfig::default());
+        let now = Instant::now();
+
+        order.push(frame(5), now);
+        let _ = released(&mut order, now);
+
+        order.give_up(6);
+        order.give_up(7);
+        order.push(frame(8), now);
+        assert_eq!(released(&mut order, now), vec![(8, 2)]);
+    }
+
+    #[test]
+    fn skips_when_too_many_frames_are_held() {
+        let mut order = DecodeOrder::new(DecodeOrderConfig {
+            max_wait: Duration::from_secs(10),
+            max_held_frames: 2,
+        });
+        let now = Instant::now();
+
+        order.push(frame(0), now);
+        let _ = released(&mut order, now);
+        for frame_identifier in 2..5 {
+            order.push(frame(frame_identifier), now);
+        }
+
+        assert_eq!(released(&mut order, now), vec![(2, 1), (3, 0), (4, 0)]);
+    }
+
+    #[test]
+    fn resyncs_when_the_host_restarts_its_frame_identifiers() {
+        let mut order = DecodeOrder::new(DecodeOrderConfig::default());
+        let now = Instant::now();
+
+        order.push(frame(100_000), now);
+        order.push(frame(100_002), now);
+        order.give_up(100_003);
+        assert_eq!(released(&mut order, now), vec![(100_000, 0)]);
+
+        for frame_identifier in 0..3 {
+            order.push(frame(frame_identifier), now);
+        }
+        assert_eq!(released(&mut order, now), vec![(0, 0), (1, 0), (2, 0)]);
+        let stats = order.stats();
+        assert_eq!(stats.resynced, 1);
+        assert_eq!(stats.stale, 0);
+    }
+
+    #[test]
+    fn resyncs_after_consecutive_stale_frames() {
+        let mut order = DecodeOrder::new(DecodeOrderConfig::default());
+        let now = Instant::now();
+
+        order.push(frame(20), now);
+        let _ = released(&mut order, now);
+        for frame_identifier in 0..7 {
+            order.push(frame(frame_identifier), now);
+        }
+        assert!(released(&mut order, now).is_empty());
+        assert_eq!(order.stats().stale, 7);
+
+        order.push(frame(7), now);
+        assert_eq!(released(&mut order, now), vec![(7, 0)]);
+        order.push(frame(8), now);
+        assert_eq!(released(&mut order, now), vec![(8, 0)]);
+        assert_eq!(order.stats().resynced, 1);
+    }
+
+    #[test]
+    fn orders_across_identifier_wrap() {
+        let mut order = DecodeOrder::new(DecodeOrderConfig::default());
+        let now = Instant::now();
+
+        order.push(frame(u32::MAX - 1), now);
+        let _ = released(&mut order, now);
+        order.push(frame(0), now);
+        order.push(frame(u32::MAX), now);
+
+        assert_eq!(released(&mut order, now), vec![(u32::MAX, 0), (0, 0)]);
+    }
+}
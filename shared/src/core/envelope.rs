@@ -0,0 +1,159 @@
+pub const ENVELOPE_MAGIC: [u8; 4] = *b"TBDP";
+pub const PROTOCOL_VERSION: u8 = 1;
+pub const ENVELOPE_HEADER_LENGTH: usize = 4 + 1 + 1 + 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageKind {
+    Video,
+    Healthcheck,
+    Control,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnvelopeHeader {
+    pub kind: MessageKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnvelopeError {
+    BufferTooSmall,
+    InvalidMagic,
+    UnsupportedVersion(u8),
+    UnknownMessageKind(u8),
+}
+
+impl std::fmt::Display for EnvelopeError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EnvelopeError::BufferTooSmall => write!(formatter, "envelope buffer too small"),
+            EnvelopeError::InvalidMagic => write!(formatter, "envelope magic mismatch"),
+            EnvelopeError::UnsupportedVersion(version) => write!(
+                formatter,
+                "unsupported protocol version {version} (expected {PROTOCOL_VERSION})"
+            ),
+            EnvelopeError::UnknownMessageKind(kind) => {
+                write!(formatter, "unknown message kind {kind}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for EnvelopeError {}
+
+impl MessageKind {
+    fn to_byte(self) -> u8 {
+        match self {
+            MessageKind::Video => 1,
+            MessageKind::Healthcheck => 2,
+            MessageKind::Control => 3,
+        }
+    }
+
+    fn from_byte(value: u8) -> Result<Self, EnvelopeError> {
+        match value {
+            1 => Ok(MessageKind::Video),
+            2 => Ok(MessageKind::Healthcheck),
+            3 => Ok(MessageKind::Control),
+            _ => Err(EnvelopeError::UnknownMessageKind(value)),
+        }
+    }
+}
+
+impl EnvelopeHeader {
+    pub fn new(kind: MessageKind) -> Self {
+        Self { kind }
+    }
+
+    pub fn encode(self, buffer: &mut [u8]) -> Result<(), EnvelopeError> {
+        if buffer.len() < ENVELOPE_HEADER_LENGTH {
+            return Err(EnvelopeError::BufferTooSmall);
+        }
+
+        buffer[0..4].copy_from_slice(&ENVELOPE_MAGIC);
+        buffer[4] = PROTOCOL_VERSION;
+        buffer[5] = self.kind.to_byte();
+        buffer[6..8].fill(0);
+
+        Ok(())
+    }
+
+    pub fn decode(buffer: &[u8]) -> Result<Self, EnvelopeError> {
+        if buffer.len() < ENVELOPE_HEADER_LENGTH {
+            return Err(EnvelopeError::BufferTooSmall);
+        }
+
+        if buffer[0..4] != ENVELOPE_MAGIC {
+            return Err(EnvelopeError::InvalidMagic);
+        }
+
+        if buffer[4] != PROTOCOL_VERSION {
+            return Err(EnvelopeError::UnsupportedVersion(buffer[4]));
+        }
+
+        let kind = MessageKind::from_byte(buffer[5])?;
+        Ok(Self { kind })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        EnvelopeError, EnvelopeHeader, MessageKind, ENVELOPE_HEADER_LENGTH, PROTOCOL_VERSION,
+    };
+
+    #[test]
+    fn round_trip_every_kind() {
+        for kind in [
+            MessageKind::Video,
+            MessageKind::Healthcheck,
+            MessageKind::Control,
+        ] {
+            let mut buffer = [0_u8; ENVELOPE_HEADER_LENGTH];
+            EnvelopeHeader::new(kind).encode(&mut buffer).unwrap();
+
+            let decoded = EnvelopeHeader::decode(&buffer).unwrap();
+            assert_eq!(decoded.kind, kind);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_magic() {
+        let buffer = *b"TBDH\x01\x01\x00\x00";
+        let result = EnvelopeHeader::decode(&buffer);
+        assert_eq!(result, Err(EnvelopeError::InvalidMagic));
+    }
+
+    #[test]
+    fn rejects_unsupported_version() {
+        let mut buffer = [0_u8; ENVELOPE_HEADER_LENGTH];
+        EnvelopeHeader::new(MessageKind::Video)
+            .encode(&mut buffer)
+            .unwrap();
+        buffer[4] = PROTOCOL_VERSION + 1;
+
+        let result = EnvelopeHeader::decode(&buffer);
+        assert_eq!(
+            result,
+            Err(EnvelopeError::UnsupportedVersion(PROTOCOL_VERSION + 1))
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_kind() {
+        let mut buffer = [0_u8; ENVELOPE_HEADER_LENGTH];
+        EnvelopeHeader::new(MessageKind::Video)
+            .encode(&mut buffer)
+            .unwrap();
+        buffer[5] = 0xEE;
+
+        let result = EnvelopeHeader::decode(&buffer);
+        assert_eq!(result, Err(EnvelopeError::UnknownMessageKind(0xEE)));
+    }
+
+    #[test]
+    fn decode_fails_on_small_buffer() {
+        let buffer = [0_u8; ENVELOPE_HEADER_LENGTH - 1];
+        let result = EnvelopeHeader::decode(&buffer);
+        assert_eq!(result, Err(EnvelopeError::BufferTooSmall));
+    }
+}
@@ -0,0 +1,317 @@
+use crate::core::reed_solomon;
+
+pub const FEC_DESCRIPTOR_LENGTH: usize = 4;
+pub const XOR_PARITY_OVERHEAD: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FecScheme {
+    None,
+    XorParity { group_size: u8 },
+    ReedSolomon { data_shards: u8, parity_shards: u8 },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FecError {
+    InvalidScheme,
+    InvalidParameters,
+}
+
+impl std::fmt::Display for FecError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FecError::InvalidScheme => write!(formatter, "unknown fec scheme"),
+            FecError::InvalidParameters => write!(formatter, "invalid fec parameters"),
+        }
+    }
+}
+
+impl std::error::Error for FecError {}
+
+impl FecScheme {
+    pub fn validate(self) -> Result<(), FecError> {
+        match self {
+            FecScheme::None => Ok(()),
+            FecScheme::XorParity { group_size } => {
+                if group_size == 0 {
+                    return Err(FecError::InvalidParameters);
+                }
+                Ok(())
+            }
+            FecScheme::ReedSolomon {
+                data_shards,
+                parity_shards,
+            } => {
+                let total = data_shards as usize + parity_shards as usize;
+                if data_shards == 0 || parity_shards == 0 || total > reed_solomon::MAX_TOTAL_SHARDS
+                {
+                    return Err(FecError::InvalidParameters);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    pub fn overhead_bytes(self) -> usize {
+        match self {
+            FecScheme::None => 0,
+            FecScheme::XorParity { .. } => XOR_PARITY_OVERHEAD,
+            FecScheme::ReedSolomon { .. } => reed_solomon::LENGTH_PREFIX_BYTES,
+        }
+    }
+
+    pub fn group_count(self, data_chunks: u16) -> u16 {
+        match self {
+            FecScheme::None => 0,
+            FecScheme::XorParity { group_size } => {
+                data_chunks.div_ceil(u16::from(group_size.max(1)))
+            }
+            FecScheme::ReedSolomon { data_shards, .. } => {
+                data_chunks.div_ceil(u16::from(data_shards.max(1)))
+            }
+        }
+    }
+
+    pub fn parity_per_group(self) -> u16 {
+        match self {
+            FecScheme::None => 0,
+            FecScheme::XorParity { .. } => 1,
+            FecScheme::ReedSolomon { parity_shards, .. } => u16::from(parity_shards),
+        }
+    }
+
+    pub fn parity_chunks(self, data_chunks: u16) -> u16 {
+        self.group_count(data_chunks)
+            .saturating_mul(self.parity_per_group())
+    }
+
+    pub fn group_of_data_chunk(self, chunk_index: u16, data_chunks: u16) -> Option<u16> {
+        match self {
+            FecScheme::None => None,
+            FecScheme::XorParity { group_size } => Some(chunk_index / u16::from(group_size.max(1))),
+            FecScheme::ReedSolomon { .. } => {
+                Some(chunk_index % self.group_count(data_chunks).max(1))
+            }
+        }
+    }
+
+    pub fn group_of_parity_chunk(self, parity_index: u16) -> u16 {
+        parity_index / self.parity_per_group().max(1)
+    }
+
+    pub fn group_members(self, group: u16, data_chunks: u16) -> Vec<u16> {
+        match self {
+            FecScheme::None => Vec::new(),
+            FecScheme::XorParity { group_size } => {
+                let group_size = u16::from(group_size.max(1));
+                let start = group.saturating_mul(group_size);
+                let end = start.saturating_add(group_size).min(data_chunks);
+                (start..end).collect()
+            }
+            FecScheme::ReedSolomon { .. } => {
+                let stride = self.group_count(data_chunks).max(1) as usize;
+                (group..data_chunks).step_by(stride).collect()
+            }
+        }
+    }
+
+    pub fn encode(self, buffer: &mut [u8]) {
+        buffer[..FEC_DESCRIPTOR_LENGTH].fill(0);
+        match self {
+            FecScheme::None => {}
+            FecScheme::XorParity { group_size } => {
+                buffer[0] = 1;
+                buffer[1] = group_size;
+            }
+            FecScheme::ReedSolomon {
+                data_shards,
+                parity_shards,
+            } => {
+                buffer[0] = 2;
+                buffer[1] = data_shards;
+                buffer[2] = parity_shards;
+            }
+        }
+    }
+
+    pub fn decode(buffer: &[u8]) -> Result<Self, FecError> {
+        let scheme = match buffer[0] {
+            0 => FecScheme::None,
+            1 => FecScheme::XorParity {
+                group_size: buffer[1],
+            },
+            2 => FecScheme::ReedSolomon {
+                data_shards: buffer[1],
+                parity_shards: buffer[2],
+            },
+            _ => return Err(FecError::InvalidScheme),
+        };
+        scheme.validate()?;
+        Ok(scheme)
+    }
+
+    pub fn encode_parity(self, members: &[&[u8]]) -> Vec<Vec<u8>> {
+        match self {
+            FecScheme::None => Vec::new(),
+            FecScheme::XorParity { .. } => vec![xor_parity(members)],
+            FecScheme::ReedSolomon { parity_shards, .. } => {
+                reed_solomon::encode(members, parity_shards as usize)
+            }
+        }
+    }
+
+    pub fn recover(
+        self,
+        members: &[Option<&[u8]>],
+        parity: &[Option<&[u8]>],
+    ) -> Option<Vec<(usize, Vec<u8>)>> {
+        match self {
+            FecScheme::None => None,
+            FecScheme::XorParity { .. } => {
+                let mut missing = members
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, member)| member.is_none())
+                    .map(|(position, _)| position);
+                let position = missing.next()?;
+                if missing.next().is_some() {
+                    return None;
+                }
+
+                let parity = parity.first().copied().flatten()?;
+                let present: Vec<&[u8]> = members.iter().flatten().copied().collect();
+                let recovered = recover_xor(parity, &present)?;
+                Some(vec![(position, recovered)])
+            }
+            FecScheme::ReedSolomon { .. } => {
+                let recovered = reed_solomon::reconstruct(members, parity)?;
+                Some(
+                    recovered
+                        .into_iter()
+                        .enumerate()
+                        .filter(|(position, _)| members[*position].is_none())
+                        .collect(),
+                )
+            }
+        }
+    }
+}
+
+pub fn xor_parity(chunks: &[&[u8]]) -> Vec<u8> {
+    let longest = chunks.iter().map(|chunk| chunk.len()).max().unwrap_or(0);
+    let mut parity = vec![0_u8; XOR_PARITY_OVERHEAD + longest];
+
+    let mut length_parity: u16 = 0;
+    for chunk in chunks {
+        length_parity ^= chunk.len() as u16;
+        xor_into(&mut parity[XOR_PARITY_OVERHEAD..], chunk);
+    }
+    parity[..XOR_PARITY_OVERHEAD].copy_from_slice(&length_parity.to_be_bytes());
+    parity
+}
+
+pub fn recover_xor(parity: &[u8], present: &[&[u8]]) -> Option<Vec<u8>> {
+    if parity.len() < XOR_PARITY_OVERHEAD {
+        return None;
+    }
+
+    let mut length = u16::from_be_bytes([parity[0], parity[1]]);
+    let mut recovered = parity[XOR_PARITY_OVERHEAD..].to_vec();
+    for chunk in present {
+        if chunk.len() > recovered.len() {
+            return None;
+        }
+        length ^= chunk.len() as u16;
+        xor_into(&mut recovered, chunk);
+    }
+
+    let length = length as usize;
+    if length > recovered.len() {
+        return None;
+    }
+    recovered.truncate(length);
+    Some(recovered)
+}
+
+fn xor_into(target: &mut [u8], source: &[u8]) {
+    for (target_byte, source_byte) in target.iter_mut().zip(source) {
+        *target_byte ^= source_byte;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{recover_xor, xor_parity, FecError, FecScheme, FEC_DESCRIPTOR_LENGTH};
+
+    #[test]
+    fn xor_recovers_any_single_chunk() {
+        let chunks: Vec<&[u8]> = vec![b"alpha", b"bravo!", b"char"];
+        let parity = xor_parity(&chunks);
+
+        for missing in 0..chunks.len() {
+            let present: Vec<&[u8]> = chunks
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| *index != missing)
+                .map(|(_, chunk)| *chunk)
+                .collect();
+
+            let recovered = recover_xor(&parity, &present).expect("recover");
+            assert_eq!(recovered, chunks[missing]);
+        }
+    }
+
+    #[test]
+    fn xor_groups_are_contiguous() {
+        let scheme = FecScheme::XorParity { group_size: 3 };
+        assert_eq!(scheme.group_count(7), 3);
+        assert_eq!(scheme.parity_chunks(7), 3);
+        assert_eq!(scheme.group_members(1, 7), vec![3, 4, 5]);
+        assert_eq!(scheme.group_members(2, 7), vec![6]);
+        assert_eq!(scheme.group_of_data_chunk(5, 7), Some(1));
+    }
+
+    #[test]
+    fn reed_solomon_groups_are_interleaved() {
+        let scheme = FecScheme::ReedSolomon {
+            data_shards: 4,
+            parity_shards: 2,
+        };
+        assert_eq!(scheme.group_count(10), 3);
+        assert_eq!(scheme.parity_chunks(10), 6);
+        assert_eq!(scheme.group_members(0, 10), vec![0, 3, 6, 9]);
+        assert_eq!(scheme.group_members(2, 10), vec![2, 5, 8]);
+        assert_eq!(scheme.group_of_data_chunk(7, 10), Some(1));
+        assert_eq!(scheme.group_of_parity_chunk(5), 2);
+    }
+
+    #[test]
+    fn reed_solomon_descriptor_round_trip() {
+        let scheme = FecScheme::ReedSolomon {
+            data_shards: 10,
+            parity_shards: 4,
+        };
+        let mut buffer = [0_u8; FEC_DESCRIPTOR_LENGTH];
+        scheme.encode(&mut buffer);
+        assert_eq!(FecScheme::decode(&buffer), Ok(scheme));
+    }
+
+    #[test]
+    fn rejects_oversized_reed_solomon_code() {
+        let buffer = [2_u8, 200, 100, 0];
+        assert_eq!(FecScheme::decode(&buffer), Err(FecError::InvalidParameters));
+    }
+
+    #[test]
+    fn descriptor_round_trip() {
+        let scheme = FecScheme::XorParity { group_size: 4 };
+        let mut buffer = [0_u8; FEC_DESCRIPTOR_LENGTH];
+        scheme.encode(&mut buffer);
+        assert_eq!(FecScheme::decode(&buffer), Ok(scheme));
+    }
+
+    #[test]
+    fn rejects_zero_group_size() {
+        let buffer = [1_u8, 0, 0, 0];
+        assert_eq!(FecScheme::decode(&buffer), Err(FecError::InvalidParameters));
+    }
+}
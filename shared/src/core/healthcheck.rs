@@ -1,3 +1,5 @@
+use crate::core::envelope::{EnvelopeError, EnvelopeHeader, MessageKind, ENVELOPE_HEADER_LENGTH};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum HealthcheckKind {
     Ping,
@@ -10,44 +12,59 @@ pub struct HealthcheckPacket {
     pub timestamp_nanos: u64,
 }
 
-const HEALTHCHECK_MAGIC: [u8; 4] = *b"TBDH";
-const HEALTHCHECK_LENGTH: usize = 16;
+pub const HEALTHCHECK_LENGTH: usize = ENVELOPE_HEADER_LENGTH + 4 + 8;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum HealthcheckError {
     BufferTooSmall,
-    InvalidMagic,
+    Envelope(EnvelopeError),
+    UnexpectedMessageKind(MessageKind),
     InvalidKind,
 }
 
+impl From<EnvelopeError> for HealthcheckError {
+    fn from(error: EnvelopeError) -> Self {
+        match error {
+            EnvelopeError::BufferTooSmall => Self::BufferTooSmall,
+            other => Self::Envelope(other),
+        }
+    }
+}
+
 impl HealthcheckPacket {
     pub fn encode(self) -> [u8; HEALTHCHECK_LENGTH] {
         let mut buffer = [0_u8; HEALTHCHECK_LENGTH];
-        buffer[0..4].copy_from_slice(&HEALTHCHECK_MAGIC);
-        buffer[4] = match self.kind {
+        EnvelopeHeader::new(MessageKind::Healthcheck)
+            .encode(&mut buffer[..ENVELOPE_HEADER_LENGTH])
+            .expect("envelope buffer is sized correctly");
+
+        let body = &mut buffer[ENVELOPE_HEADER_LENGTH..];
+        body[0] = match self.kind {
             HealthcheckKind::Ping => 1,
             HealthcheckKind::Pong => 2,
         };
-        buffer[8..16].copy_from_slice(&self.timestamp_nanos.to_be_bytes());
+        body[4..12].copy_from_slice(&self.timestamp_nanos.to_be_bytes());
         buffer
     }
 
     pub fn decode(buffer: &[u8]) -> Result<Self, HealthcheckError> {
-        if buffer.len() < HEALTHCHECK_LENGTH {
-            return Err(HealthcheckError::BufferTooSmall);
+        let envelope = EnvelopeHeader::decode(buffer)?;
+        if envelope.kind != MessageKind::Healthcheck {
+            return Err(HealthcheckError::UnexpectedMessageKind(envelope.kind));
         }
 
-        if buffer[0..4] != HEALTHCHECK_MAGIC {
-            return Err(HealthcheckError::InvalidMagic);
+        if buffer.len() < HEALTHCHECK_LENGTH {
+            return Err(HealthcheckError::BufferTooSmall);
         }
 
-        let kind = match buffer[4] {
+        let body = &buffer[ENVELOPE_HEADER_LENGTH..];
+        let kind = match body[0] {
             1 => HealthcheckKind::Ping,
             2 => HealthcheckKind::Pong,
             _ => return Err(HealthcheckError::InvalidKind),
         };
 
-        let timestamp_nanos = u64::from_be_bytes(buffer[8..16].try_into().unwrap());
+        let timestamp_nanos = u64::from_be_bytes(body[4..12].try_into().unwrap());
 
         Ok(Self {
             kind,
@@ -56,13 +73,24 @@ impl HealthcheckPacket {
     }
 
     pub fn is_healthcheck_packet(buffer: &[u8]) -> bool {
-        buffer.len() >= HEALTHCHECK_LENGTH && buffer[0..4] == HEALTHCHECK_MAGIC
+        buffer.len() >= HEALTHCHECK_LENGTH
+            && matches!(
+                EnvelopeHeader::decode(buffer),
+                Ok(EnvelopeHeader {
+                    kind: MessageKind::Healthcheck
+                })
+            )
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{HealthcheckKind, HealthcheckPacket};
+    use super::{HealthcheckError, HealthcheckKind, HealthcheckPacket};
+    use crate::core::envelope::MessageKind;
+    use crate::core::fec::FecScheme;
+    use crate::core::packet::{VideoPacket, VideoPacketHeader};
+    use crate::core::packet_codec::encode_packet;
+    use crate::core::sequence::SequenceNumber;
 
     #[test]
     fn round_trip_encode_decode() {
@@ -75,4 +103,25 @@ mod tests {
         let decoded = HealthcheckPacket::decode(&encoded).expect("decode");
         assert_eq!(decoded, packet);
     }
+
+    #[test]
+    fn rejects_video_packet() {
+        let video = encode_packet(&VideoPacket {
+            header: VideoPacketHeader {
+                sequence_number: SequenceNumber::new(1),
+                timestamp_nanos: 0,
+                frame_identifier: 0,
+                chunk_index: 0,
+                chunks_total: 1,
+                fec: FecScheme::None,
+            },
+            payload: vec![0_u8; 32],
+        });
+
+        assert!(!HealthcheckPacket::is_healthcheck_packet(&video));
+        assert_eq!(
+            HealthcheckPacket::decode(&video),
+            Err(HealthcheckError::UnexpectedMessageKind(MessageKind::Video))
+        );
+    }
 }
@@ -0,0 +1,365 @@
+use crate::core::reassembler::ReassembledFrame;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+const JITTER_GAIN: f64 = 16.0;
+const JITTER_MULTIPLIER: f64 = 3.0;
+const TRANSIT_WINDOW: usize = 128;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayoutMode {
+    LatestFrame,
+    Adaptive {
+        min_delay: Duration,
+        max_delay: Duration,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JitterBufferConfig {
+    pub mode: PlayoutMode,
+    pub capacity: usize,
+}
+
+impl Default for JitterBufferConfig {
+    fn default() -> Self {
+        Self {
+            mode: PlayoutMode::Adaptive {
+                min_delay: Duration::ZERO,
+                max_delay: Duration::from_millis(100),
+            },
+            capacity: 16,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct JitterBufferStats {
+    pub frames_played: u64,
+    pub frames_late: u64,
+    pub frames_dropped: u64,
+    pub frames_buffered: usize,
+    pub target_delay: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayoutFrame {
+    pub frame: ReassembledFrame,
+    pub dropped_before: u32,
+}
+
+#[derive(Debug, Clone)]
+struct BufferedFrame {
+    frame: ReassembledFrame,
+    playout_nanos: i128,
+}
+
+#[derive(Debug, Clone)]
+pub struct JitterBuffer {
+    config: JitterBufferConfig,
+    epoch: Instant,
+    frames: VecDeque<BufferedFrame>,
+    transits: VecDeque<i128>,
+    jitter_nanos: f64,
+    last_played_timestamp: Option<u64>,
+    dropped_since_played: u32,
+    stats: JitterBufferStats,
+}
+
+impl JitterBuffer {
+    pub fn new(config: JitterBufferConfig, epoch: Instant) -> Self {
+        Self {
+            config,
+            epoch,
+            frames: VecDeque::with_capacity(config.capacity),
+            transits: VecDeque::with_capacity(TRANSIT_WINDOW),
+            jitter_nanos: 0.0,
+            last_played_timestamp: None,
+            dropped_since_played: 0,
+            stats: JitterBufferStats::default(),
+        }
+    }
+
+    pub fn push(&mut self, frame: ReassembledFrame, now: Instant) {
+        if self
+            .last_played_timestamp
+            .is_some_and(|played| frame.timestamp_nanos <= played)
+        {
+            self.record_dropped(1);
+            return;
+        }
+
+        self.observe_transit(frame.timestamp_nanos, now);
+        let playout_nanos = self.playout_nanos(frame.timestamp_nanos);
+        if playout_nanos < self.nanos_since_epoch(now) {
+            self.stats.frames_late += 1;
+        }
+
+        let position = self
+            .frames
+            .iter()
+            .position(|buffered| buffered.frame.timestamp_nanos > frame.timestamp_nanos)
+            .unwrap_or(self.frames.len());
+        self.frames.insert(
+            position,
+            BufferedFrame {
+                frame,
+                playout_nanos,
+            },
+        );
+
+        while self.frames.len() > self.config.capacity.max(1) {
+            self.frames.pop_front();
+            self.record_dropped(1);
+        }
+    }
+
+    pub fn pop_ready(&mut self, now: Instant) -> Option<PlayoutFrame> {
+        let ready = match self.config.mode {
+            PlayoutMode::LatestFrame => {
+                let skipped = self.frames.len().saturating_sub(1);
+                self.frames.drain(..skipped);
+                self.record_dropped(skipped);
+                self.frames.pop_front()
+            }
+            PlayoutMode::Adaptive { .. } => {
+                let now_nanos = self.nanos_since_epoch(now);
+                if self
+                    .frames
+                    .front()
+                    .is_some_and(|buffered| buffered.playout_nanos <= now_nanos)
+                {
+                    self.frames.pop_front()
+                } else {
+                    None
+                }
+            }
+        }?;
+
+        self.stats.frames_played += 1;
+        self.last_played_timestamp = Some(ready.frame.timestamp_nanos);
+        Some(PlayoutFrame {
+            frame: ready.frame,
+            dropped_before: std::mem::take(&mut self.dropped_since_played),
+        })
+    }
+
+    fn record_dropped(&mut self, frames: usize) {
+        self.stats.frames_dropped += frames as u64;
+        self.dropped_since_played = self
+            .dropped_since_played
+            .saturating_add(u32::try_from(frames).unwrap_or(u32::MAX));
+    }
+
+    pub fn next_playout(&self) -> Option<Instant> {
+        let buffered = self.frames.front()?;
+        let nanos = u64::try_from(buffered.playout_nanos.max(0)).unwrap_or(u64::MAX);
+        Some(self.epoch + Duration::from_nanos(nanos))
+    }
+
+    pub fn target_delay(&self) -> Duration {
+        match self.config.mode {
+            PlayoutMode::LatestFrame => Duration::ZERO,
+            PlayoutMode::Adaptive {
+                min_delay,
+                max_delay,
+            } => {
+                let estimate = Duration::from_nanos((self.jitter_nanos * JITTER_MULTIPLIER) as u64);
+                estimate.clamp(min_delay, max_delay.max(min_delay))
+            }
+        }
+    }
+
+    pub fn stats(&self) -> JitterBufferStats {
+        JitterBufferStats {
+            frames_buffered: self.frames.len(),
+            target_delay: self.target_delay(),
+            ..self.stats
+        }
+    }
+
+    fn observe_transit(&mut self, timestamp_nanos: u64, now: Instant) {
+        let transit = self.nanos_since_epoch(now) - i128::from(timestamp_nanos);
+        if let Some(&last_transit) = self.transits.back() {
+            let difference = (transit - last_transit).unsigned_abs() as f64;
+            self.jitter_nanos += (difference - self.jitter_nanos) / JITTER_GAIN;
+        }
+        if self.transits.len() == TRANSIT_WINDOW {
+            self.transits.pop_front();
+        }
+        self.transits.push_back(transit);
+    }
+
+    fn playout_nanos(&self, timestamp_nanos: u64) -> i128 {
+        let base_transit = self.transits.iter().copied().min().unwrap_or(0);
+        i128::from(timestamp_nanos) + base_transit + self.target_delay().as_nanos() as i128
+    }
+
+    fn nanos_since_epoch(&self, now: Instant) -> i128 {
+        now.saturating_duration_since(self.epoch).as_nanos() as i128
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{JitterBuffer, JitterBufferConfig, PlayoutMode};
+    use crate::core::reassembler::ReassembledFrame;
+    use std::time::{Duration, Instant};
+
+    const FRAME_INTERVAL_NANOS: u64 = 16_000_000;
+
+    fn frame(frame_identifier: u32) -> ReassembledFrame {
+        ReassembledFrame {
+            frame_identifier,
+            timestamp_nanos: 1_000_000_000 + u64::from(frame_identifier) * FRAME_INTERVAL_NANOS,
+            payload: vec![frame_identifier as u8],
+        }
+    }
+
+    fn adaptive(max_delay_millis: u64) -> JitterBufferConfig {
+        JitterBufferConfig {
+            mode: PlayoutMode::Adaptive {
+                min_delay: Duration::ZERO,
+                max_delay: Duration::from_millis(max_delay_millis),
+            },
+            capacity: 16,
+        }
+    }
+
+    fn at(epoch: Instant, nanos: u64) -> Instant {
+        epoch + Duration::from_nanos(nanos)
+    }
+
+    #[test]
+    fn steady_arrivals_play_immediately() {
+        let epoch = Instant::now();
+        let mut buffer = JitterBuffer::new(adaptive(100), epoch);
+
+        for frame_identifier in 0..20 {
+            let now = at(epoch, u64::from(frame_identifier) * FRAME_INTERVAL_NANOS);
+            buffer.push(frame(frame_identifier), now);
+            let played = buffer.pop_ready(now).expect("frame is due");
+            assert_eq!(played.frame.frame_identifier, frame_identifier);
+            assert_eq!(played.dropped_before, 0);
+        }
+
+        let stats = buffer.stats();
+        assert_eq!(stats.frames_played, 20);
+        assert_eq!(stats.frames_late, 0);
+        assert_eq!(stats.target_delay, Duration::ZERO);
+    }
+
+    #[test]
+    fn jittery_arrivals_grow_target_delay_and_smooth_playout() {
+        let epoch = Instant::now();
+        let mut buffer = JitterBuffer::new(adaptive(100), epoch);
+
+        for frame_identifier in 0..200_u32 {
+            let delay = if frame_identifier % 2 == 0 {
+                0
+            } else {
+                8_000_000
+            };
+            let arrival = u64::from(frame_identifier) * FRAME_INTERVAL_NANOS + delay;
+            buffer.push(frame(frame_identifier), at(epoch, arrival));
+        }
+
+        let target = buffer.stats().target_delay;
+        assert!(target > Duration::from_millis(20), "target {target:?}");
+        assert!(target <= Duration::from_millis(100));
+        assert!(buffer.next_playout().is_some());
+    }
+
+    #[test]
+    fn holds_frames_until_playout_time() {
+        let epoch = Instant::now();
+        let config = JitterBufferConfig {
+            mode: PlayoutMode::Adaptive {
+                min_delay: Duration::from_millis(30),
+                max_delay: Duration::from_millis(100),
+            },
+            capacity: 16,
+        };
+        let mut buffer = JitterBuffer::new(config, epoch);
+
+        buffer.push(frame(0), at(epoch, 0));
+        assert!(buffer.pop_ready(at(epoch, 29_000_000)).is_none());
+        assert_eq!(buffer.next_playout(), Some(at(epoch, 30_000_000)));
+        assert_eq!(
+            buffer
+                .pop_ready(at(epoch, 30_000_000))
+                .map(|played| played.frame.frame_identifier),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn reorders_by_timestamp_and_drops_frames_older_than_played() {
+        let epoch = Instant::now();
+        let mut buffer = JitterBuffer::new(adaptive(100), epoch);
+
+        buffer.push(frame(2), at(epoch, 2 * FRAME_INTERVAL_NANOS));
+        buffer.push(frame(1), at(epoch, 2 * FRAME_INTERVAL_NANOS));
+        let later = at(epoch, 10 * FRAME_INTERVAL_NANOS);
+        assert_eq!(buffer.pop_ready(later).unwrap().frame.frame_identifier, 1);
+        assert_eq!(buffer.pop_ready(later).unwrap().frame.frame_identifier, 2);
+
+        buffer.push(frame(0), later);
+        assert!(buffer.pop_ready(later).is_none());
+        assert_eq!(buffer.stats().frames_dropped, 1);
+
+        buffer.push(frame(3), later);
+        let played = buffer.pop_ready(later).unwrap();
+        assert_eq!(played.frame.frame_identifier, 3);
+        assert_eq!(played.dropped_before, 1);
+    }
+
+    #[test]
+    fn latest_frame_mode_skips_to_newest() {
+        let epoch = Instant::now();
+        let config = JitterBufferConfig {
+            mode: PlayoutMode::LatestFrame,
+            capacity: 16,
+        };
+        let mut buffer = JitterBuffer::new(config, epoch);
+
+        for frame_identifier in 0..3 {
+            buffer.push(frame(frame_identifier), epoch);
+        }
+
+        let played = buffer.pop_ready(epoch).unwrap();
+        assert_eq!(played.frame.frame_identifier, 2);
+        assert_eq!(played.dropped_before, 2);
+        assert!(buffer.pop_ready(epoch).is_none());
+        let stats = buffer.stats();
+        assert_eq!(stats.frames_dropped, 2);
+        assert_eq!(stats.frames_played, 1);
+    }
+
+    #[test]
+    fn overflow_drops_oldest() {
+        let epoch = Instant::now();
+        let config = JitterBufferConfig {
+            mode: PlayoutMode::Adaptive {
+                min_delay: Duration::from_secs(1),
+                max_delay: Duration::from_secs(1),
+            },
+            capacity: 2,
+        };
+        let mut buffer = JitterBuffer::new(config, epoch);
+
+        for frame_identifier in 0..3 {
+            buffer.push(frame(frame_identifier), epoch);
+        }
+
+        let stats = buffer.stats();
+        assert_eq!(stats.frames_buffered, 2);
+        assert_eq!(stats.frames_dropped, 1);
+
+        let later = epoch + Duration::from_secs(2);
+        let played = buffer.pop_ready(later).unwrap();
+        assert_eq!(played.frame.frame_identifier, 1);
+        assert_eq!(played.dropped_before, 1);
+        assert_eq!(buffer.pop_ready(later).unwrap().dropped_before, 0);
+    }
+}
@@ -0,0 +1,91 @@
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone)]
+pub struct KeyframeRequestThrottle {
+    min_interval: Duration,
+    pending: bool,
+    last_keyframe: Option<Instant>,
+}
+
+impl KeyframeRequestThrottle {
+    pub fn new(min_interval: Duration) -> Self {
+        Self {
+            min_interval,
+            pending: false,
+            last_keyframe: None,
+        }
+    }
+
+    pub fn request(&mut self) {
+        self.pending = true;
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.pending
+    }
+
+    pub fn poll(&mut self, now: Instant) -> bool {
+        if !self.pending {
+            return false;
+        }
+        if let Some(last_keyframe) = self.last_keyframe {
+            if now.saturating_duration_since(last_keyframe) < self.min_interval {
+                return false;
+            }
+        }
+        self.pending = false;
+        self.last_keyframe = Some(now);
+        true
+    }
+
+    pub fn on_keyframe(&mut self, now: Instant) {
+        self.pending = false;
+        self.last_keyframe = Some(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KeyframeRequestThrottle;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn first_request_is_granted_immediately() {
+        let mut throttle = KeyframeRequestThrottle::new(Duration::from_millis(500));
+        let now = Instant::now();
+
+        assert!(!throttle.poll(now));
+        throttle.request();
+        assert!(throttle.poll(now));
+        assert!(!throttle.poll(now));
+    }
+
+    #[test]
+    fn requests_within_interval_are_deferred_and_coalesced() {
+        let mut throttle = KeyframeRequestThrottle::new(Duration::from_millis(500));
+        let start = Instant::now();
+        throttle.request();
+        assert!(throttle.poll(start));
+
+        for step in 1..10 {
+            throttle.request();
+            assert!(!throttle.poll(start + Duration::from_millis(step * 40)));
+        }
+        assert!(throttle.is_pending());
+
+        assert!(throttle.poll(start + Duration::from_millis(500)));
+        assert!(!throttle.poll(start + Duration::from_millis(1500)));
+    }
+
+    #[test]
+    fn natural_keyframe_satisfies_pending_request() {
+        let mut throttle = KeyframeRequestThrottle::new(Duration::from_millis(500));
+        let start = Instant::now();
+        throttle.request();
+        throttle.on_keyframe(start);
+
+        assert!(!throttle.is_pending());
+        throttle.request();
+        assert!(!throttle.poll(start + Duration::from_millis(100)));
+    }
+}
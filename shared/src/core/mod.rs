@@ -1,6 +1,19 @@
+pub mod congestion;
+pub mod control;
+pub mod decode_order;
+pub mod envelope;
+pub mod fec;
+pub mod healthcheck;
+pub mod jitter_buffer;
+pub mod keyframe;
+pub mod nack;
 pub mod packet;
 pub mod packet_codec;
 pub mod packetizer;
-pub mod healthcheck;
+pub mod path_mtu;
 pub mod reassembler;
+pub mod reception;
+pub mod reed_solomon;
+pub mod retransmit;
+pub mod rtp;
 pub mod sequence;
@@ -0,0 +1,396 @@
+use crate::core::packet::VideoPacketHeader;
+use crate::core::packetizer::emission_order;
+use crate::core::sequence::SequenceNumber;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+const MAX_GAP_PACKETS: i32 = 512;
+const FINISHED_FRAMES_CAPACITY: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NackConfig {
+    pub reorder_delay: Duration,
+    pub retry_interval: Duration,
+    pub max_attempts: u8,
+    pub max_tracked_frames: usize,
+}
+
+impl Default for NackConfig {
+    fn default() -> Self {
+        Self {
+            reorder_delay: Duration::from_millis(3),
+            retry_interval: Duration::from_millis(20),
+            max_attempts: 3,
+            max_tracked_frames: 8,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Attempts {
+    due_from: Instant,
+    sent: u8,
+    last_sent: Option<Instant>,
+}
+
+impl Attempts {
+    fn new(due_from: Instant) -> Self {
+        Self {
+            due_from,
+            sent: 0,
+            last_sent: None,
+        }
+    }
+
+    fn is_due(&self, config: &NackConfig, now: Instant) -> bool {
+        if self.sent >= config.max_attempts {
+            return false;
+        }
+        match self.last_sent {
+            Some(last_sent) => now.saturating_duration_since(last_sent) >= config.retry_interval,
+            None => now.saturating_duration_since(self.due_from) >= config.reorder_delay,
+        }
+    }
+
+    fn record(&mut self, now: Instant) {
+        self.sent += 1;
+        self.last_sent = Some(now);
+    }
+}
+
+#[derive(Debug, Clone)]
+struct TrackedFrame {
+    frame_identifier: u32,
+    base_sequence: SequenceNumber,
+    offsets: Vec<u16>,
+    received: Vec<bool>,
+    holes_visible: bool,
+    attempts: Attempts,
+}
+
+impl TrackedFrame {
+    fn missing(&self, highest: SequenceNumber) -> impl Iterator<Item = SequenceNumber> + '_ {
+        self.received
+            .iter()
+            .enumerate()
+            .filter(|(_, received)| !**received)
+            .map(|(chunk_index, _)| {
+                self.base_sequence
+                    .wrapping_add(u32::from(self.offsets[chunk_index]))
+            })
+            .filter(move |&sequence_number| sequence_number.distance_to(highest) > 0)
+    }
+
+    fn update_holes(&mut self, highest: SequenceNumber, now: Instant) {
+        let holes_visible = self.missing(highest).next().is_some();
+        if holes_visible && !self.holes_visible {
+            self.attempts.due_from = now;
+        }
+        self.holes_visible = holes_visible;
+    }
+}
+
+#[derive(Debug, Clone)]
+struct SequenceGap {
+    sequence_numbers: Vec<SequenceNumber>,
+    attempts: Attempts,
+}
+
+#[derive(Debug, Clone)]
+pub struct NackTracker {
+    config: NackConfig,
+    frames: VecDeque<TrackedFrame>,
+    finished: VecDeque<u32>,
+    gaps: Vec<SequenceGap>,
+    next_expected: Option<SequenceNumber>,
+    highest_received: Option<SequenceNumber>,
+}
+
+impl NackTracker {
+    pub fn new(config: NackConfig) -> Self {
+        Self {
+            config,
+            frames: VecDeque::new(),
+            finished: VecDeque::with_capacity(FINISHED_FRAMES_CAPACITY),
+            gaps: Vec::new(),
+            next_expected: None,
+            highest_received: None,
+        }
+    }
+
+    pub fn on_packet(&mut self, header: &VideoPacketHeader, now: Instant) {
+        let sequence_number = header.sequence_number;
+        self.gaps.retain_mut(|gap| {
+            gap.sequence_numbers
+                .retain(|missing| *missing != sequence_number);
+            !gap.sequence_numbers.is_empty()
+        });
+
+        let highest = match self.highest_received {
+            Some(highest) if highest.distance_to(sequence_number) <= 0 => highest,
+            _ => sequence_number,
+        };
+        self.highest_received = Some(highest);
+
+        if !self.finished.contains(&header.frame_identifier) {
+            self.track_chunk(header, now);
+        }
+        for frame in self.frames.iter_mut() {
+            frame.update_holes(highest, now);
+        }
+    }
+
+    fn track_chunk(&mut self, header: &VideoPacketHeader, now: Instant) {
+        let position = self
+            .frames
+            .iter()
+            .position(|frame| frame.frame_identifier == header.frame_identifier);
+        let frame = match position {
+            Some(position) => &mut self.frames[position],
+            None => {
+                let frame = self.start_frame(header, now);
+                self.frames.push_back(frame);
+                while self.frames.len() > self.config.max_tracked_frames {
+                    self.frames.pop_front();
+                }
+                self.frames.back_mut().expect("frame was just pushed")
+            }
+        };
+
+        if let Some(received) = frame.received.get_mut(header.chunk_index as usize) {
+            *received = true;
+        }
+    }
+
+    pub fn on_frame_complete(&mut self, frame_identifier: u32) {
+        self.frames
+            .retain(|frame| frame.frame_identifier != frame_identifier);
+        if self.finished.len() == FINISHED_FRAMES_CAPACITY {
+            self.finished.pop_front();
+        }
+        self.finished.push_back(frame_identifier);
+    }
+
+    pub fn poll(&mut self, now: Instant) -> Vec<SequenceNumber> {
+        let mut sequence_numbers = Vec::new();
+        let Some(highest) = self.highest_received else {
+            return sequence_numbers;
+        };
+
+        for frame in self.frames.iter_mut() {
+            if !frame.holes_visible || !frame.attempts.is_due(&self.config, now) {
+                continue;
+            }
+            let before = sequence_numbers.len();
+            sequence_numbers.extend(frame.missing(highest));
+            if sequence_numbers.len() > before {
+                frame.attempts.record(now);
+            }
+        }
+
+        for gap in self.gaps.iter_mut() {
+            if gap.attempts.is_due(&self.config, now) {
+                sequence_numbers.extend_from_slice(&gap.sequence_numbers);
+                gap.attempts.record(now);
+            }
+        }
+        let max_attempts = self.config.max_attempts;
+        self.gaps.retain(|gap| gap.attempts.sent < max_attempts);
+
+        sequence_numbers
+    }
+
+    fn start_frame(&mut self, header: &VideoPacketHeader, now: Instant) -> TrackedFrame {
+        let order = emission_order(header.chunks_total, header.fec);
+        let mut offsets = vec![0_u16; header.chunks_total as usize];
+        let mut header_offset = 0;
+        for (offset, &chunk_index) in order.iter().enumerate() {
+            if chunk_index < header.chunks_total {
+                offsets[chunk_index as usize] = offset as u16;
+            }
+            if chunk_index == header.chunk_index {
+                header_offset = offset as u32;
+            }
+        }
+
+        let base_sequence =
+            SequenceNumber::new(header.sequence_number.value().wrapping_sub(header_offset));
+        let frame_end = base_sequence.wrapping_add(order.len() as u32);
+
+        match self.next_expected {
+            Some(expected) => {
+                let gap = expected.distance_to(base_sequence);
+                if gap > 0 && gap <= MAX_GAP_PACKETS {
+                    self.gaps.push(SequenceGap {
+                        sequence_numbers: (0..gap as u32)
+                            .map(|step| expected.wrapping_add(step))
+                            .collect(),
+                        attempts: Attempts::new(now),
+                    });
+                }
+                if frame_end > expected {
+                    self.next_expected = Some(frame_end);
+                }
+            }
+            None => self.next_expected = Some(frame_end),
+        }
+
+        TrackedFrame {
+            frame_identifier: header.frame_identifier,
+            base_sequence,
+            offsets,
+            received: vec![false; header.chunks_total as usize],
+            holes_visible: false,
+            attempts: Attempts::new(now),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NackConfig, NackTracker};
+    use crate::core::fec::FecScheme;
+    use crate::core::packet::VideoPacket;
+    use crate::core::packetizer::{Packetization, Packetizer, PacketizerConfig};
+    use crate::core::sequence::SequenceNumber;
+    use std::time::{Duration, Instant};
+
+    fn frames(fec: FecScheme, count: u32, initial_sequence: u32) -> Vec<Vec<VideoPacket>> {
+        let mut packetizer = Packetizer::new(
+            PacketizerConfig {
+                max_payload_bytes: 6,
+                fec,
+                packetization: Packetization::Fixed,
+            },
+            SequenceNumber::new(initial_sequence),
+        );
+        (0..count)
+            .map(|frame_identifier| {
+                packetizer
+                    .packetize(frame_identifier, 0, &[0xAB; 20])
+                    .expect("packetize")
+            })
+            .collect()
+    }
+
+    fn millis(start: Instant, value: u64) -> Instant {
+        start + Duration::from_millis(value)
+    }
+
+    #[test]
+    fn nacks_gap_after_reorder_delay() {
+        let frames = frames(FecScheme::None, 1, 100);
+        let mut tracker = NackTracker::new(NackConfig::default());
+        let start = Instant::now();
+
+        for packet in frames[0]
+            .iter()
+            .filter(|packet| packet.header.chunk_index != 1)
+        {
+            tracker.on_packet(&packet.header, start);
+        }
+
+        assert!(tracker.poll(millis(start, 1)).is_empty());
+        assert_eq!(
+            tracker.poll(millis(start, 3)),
+            vec![SequenceNumber::new(101)]
+        );
+        assert!(tracker.poll(millis(start, 4)).is_empty());
+    }
+
+    #[test]
+    fn nacks_lost_tail_once_a_later_packet_arrives() {
+        let frames = frames(FecScheme::XorParity { group_size: 2 }, 2, 0);
+        let mut tracker = NackTracker::new(NackConfig::default());
+        let start = Instant::now();
+
+        let last_data = frames[0]
+            .iter()
+            .filter(|packet| !packet.header.is_parity())
+            .map(|packet| packet.header.chunk_index)
+            .max()
+            .unwrap();
+        let lost = frames[0]
+            .iter()
+            .find(|packet| packet.header.chunk_index == last_data)
+            .unwrap()
+            .header
+            .sequence_number;
+        for packet in frames[0]
+            .iter()
+            .filter(|packet| packet.header.sequence_number.value() < lost.value())
+        {
+            tracker.on_packet(&packet.header, start);
+        }
+        assert!(tracker.poll(millis(start, 5)).is_empty());
+
+        tracker.on_packet(&frames[1][0].header, millis(start, 6));
+        assert!(tracker.poll(millis(start, 8)).is_empty());
+        assert_eq!(tracker.poll(millis(start, 9)), vec![lost]);
+    }
+
+    #[test]
+    fn paced_chunks_in_flight_are_not_nacked() {
+        let frames = frames(FecScheme::XorParity { group_size: 2 }, 2, 0);
+        let mut tracker = NackTracker::new(NackConfig::default());
+        let start = Instant::now();
+
+        let mut elapsed = 0;
+        for packet in frames.iter().flatten() {
+            tracker.on_packet(&packet.header, millis(start, elapsed));
+            assert!(tracker.poll(millis(start, elapsed + 4)).is_empty());
+            elapsed += 5;
+        }
+        assert!(tracker.poll(millis(start, elapsed + 50)).is_empty());
+    }
+
+    #[test]
+    fn retries_until_attempts_exhausted() {
+        let frames = frames(FecScheme::None, 1, 0);
+        let config = NackConfig::default();
+        let mut tracker = NackTracker::new(config);
+        let start = Instant::now();
+        tracker.on_packet(&frames[0][0].header, start);
+        tracker.on_packet(&frames[0].last().unwrap().header, start);
+
+        let mut nacks = 0;
+        for step in 0..20 {
+            if !tracker.poll(millis(start, 5 + step * 10)).is_empty() {
+                nacks += 1;
+            }
+        }
+        assert_eq!(nacks, config.max_attempts);
+    }
+
+    #[test]
+    fn completed_frames_stop_nacking() {
+        let frames = frames(FecScheme::None, 1, 0);
+        let mut tracker = NackTracker::new(NackConfig::default());
+        let start = Instant::now();
+
+        tracker.on_packet(&frames[0][0].header, start);
+        tracker.on_frame_complete(0);
+
+        assert!(tracker.poll(millis(start, 50)).is_empty());
+    }
+
+    #[test]
+    fn nacks_entirely_lost_frame_from_sequence_gap() {
+        let frames = frames(FecScheme::None, 3, u32::MAX - 6);
+        let mut tracker = NackTracker::new(NackConfig::default());
+        let start = Instant::now();
+
+        for frame_identifier in [0, 2] {
+            for packet in &frames[frame_identifier] {
+                tracker.on_packet(&packet.header, start);
+            }
+            tracker.on_frame_complete(frame_identifier as u32);
+        }
+
+        let expected: Vec<SequenceNumber> = frames[1]
+            .iter()
+            .map(|packet| packet.header.sequence_number)
+            .collect();
+        assert_eq!(tracker.poll(millis(start, 5)), expected);
+    }
+}
@@ -1,6 +1,7 @@
+use crate::core::fec::{FecScheme, FEC_DESCRIPTOR_LENGTH};
 use crate::core::sequence::SequenceNumber;
 
-pub const VIDEO_PACKET_HEADER_LENGTH: usize = 4 + 8 + 4 + 2 + 2;
+pub const VIDEO_PACKET_HEADER_LENGTH: usize = 4 + 8 + 4 + 2 + 2 + FEC_DESCRIPTOR_LENGTH;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct VideoPacketHeader {
@@ -9,6 +10,7 @@ pub struct VideoPacketHeader {
     pub frame_identifier: u32,
     pub chunk_index: u16,
     pub chunks_total: u16,
+    pub fec: FecScheme,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -20,6 +22,7 @@ pub struct VideoPacket {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum PacketDecodeError {
     BufferTooSmall,
+    InvalidFecScheme,
 }
 
 impl VideoPacketHeader {
@@ -33,6 +36,7 @@ impl VideoPacketHeader {
         buffer[12..16].copy_from_slice(&self.frame_identifier.to_be_bytes());
         buffer[16..18].copy_from_slice(&self.chunk_index.to_be_bytes());
         buffer[18..20].copy_from_slice(&self.chunks_total.to_be_bytes());
+        self.fec.encode(&mut buffer[20..24]);
 
         Ok(())
     }
@@ -47,6 +51,8 @@ impl VideoPacketHeader {
         let frame_identifier = u32::from_be_bytes(buffer[12..16].try_into().unwrap());
         let chunk_index = u16::from_be_bytes(buffer[16..18].try_into().unwrap());
         let chunks_total = u16::from_be_bytes(buffer[18..20].try_into().unwrap());
+        let fec =
+            FecScheme::decode(&buffer[20..24]).map_err(|_| PacketDecodeError::InvalidFecScheme)?;
 
         Ok(Self {
             sequence_number: SequenceNumber::new(sequence_number),
@@ -54,13 +60,19 @@ impl VideoPacketHeader {
             frame_identifier,
             chunk_index,
             chunks_total,
+            fec,
         })
     }
+
+    pub fn is_parity(&self) -> bool {
+        self.chunk_index >= self.chunks_total
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::{PacketDecodeError, VideoPacketHeader, VIDEO_PACKET_HEADER_LENGTH};
+    use crate::core::fec::FecScheme;
     use crate::core::sequence::SequenceNumber;
 
     #[test]
@@ -71,6 +83,7 @@ mod tests {
             frame_identifier: 7,
             chunk_index: 1,
             chunks_total: 3,
+            fec: FecScheme::XorParity { group_size: 2 },
         };
 
         let mut buffer = vec![0_u8; VIDEO_PACKET_HEADER_LENGTH];
@@ -88,6 +101,7 @@ mod tests {
             frame_identifier: 0,
             chunk_index: 0,
             chunks_total: 1,
+            fec: FecScheme::None,
         };
 
         let mut buffer = vec![0_u8; VIDEO_PACKET_HEADER_LENGTH - 1];
@@ -101,4 +115,12 @@ mod tests {
         let result = VideoPacketHeader::decode(&buffer);
         assert_eq!(result, Err(PacketDecodeError::BufferTooSmall));
     }
+
+    #[test]
+    fn decode_rejects_unknown_fec_scheme() {
+        let mut buffer = vec![0_u8; VIDEO_PACKET_HEADER_LENGTH];
+        buffer[20] = 0xEE;
+        let result = VideoPacketHeader::decode(&buffer);
+        assert_eq!(result, Err(PacketDecodeError::InvalidFecScheme));
+    }
 }
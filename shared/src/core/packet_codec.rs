@@ -1,28 +1,76 @@
-use crate::core::packet::{VideoPacket, VideoPacketHeader, VIDEO_PACKET_HEADER_LENGTH};
+use crate::core::envelope::{EnvelopeError, EnvelopeHeader, MessageKind, ENVELOPE_HEADER_LENGTH};
+use crate::core::packet::{
+    PacketDecodeError, VideoPacket, VideoPacketHeader, VIDEO_PACKET_HEADER_LENGTH,
+};
+
+pub const VIDEO_PACKET_OVERHEAD: usize = ENVELOPE_HEADER_LENGTH + VIDEO_PACKET_HEADER_LENGTH;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum PacketCodecError {
     BufferTooSmall,
+    InvalidFecScheme,
+    Envelope(EnvelopeError),
+    UnexpectedMessageKind(MessageKind),
+}
+
+impl From<EnvelopeError> for PacketCodecError {
+    fn from(error: EnvelopeError) -> Self {
+        match error {
+            EnvelopeError::BufferTooSmall => Self::BufferTooSmall,
+            other => Self::Envelope(other),
+        }
+    }
+}
+
+impl From<PacketDecodeError> for PacketCodecError {
+    fn from(error: PacketDecodeError) -> Self {
+        match error {
+            PacketDecodeError::BufferTooSmall => Self::BufferTooSmall,
+            PacketDecodeError::InvalidFecScheme => Self::InvalidFecScheme,
+        }
+    }
+}
+
+impl std::fmt::Display for PacketCodecError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PacketCodecError::BufferTooSmall => write!(formatter, "video packet too small"),
+            PacketCodecError::InvalidFecScheme => write!(formatter, "invalid fec scheme"),
+            PacketCodecError::Envelope(error) => write!(formatter, "{error}"),
+            PacketCodecError::UnexpectedMessageKind(kind) => {
+                write!(formatter, "expected video packet, got {kind:?}")
+            }
+        }
+    }
 }
 
+impl std::error::Error for PacketCodecError {}
+
 pub fn encode_packet(packet: &VideoPacket) -> Vec<u8> {
-    let mut buffer = vec![0_u8; VIDEO_PACKET_HEADER_LENGTH + packet.payload.len()];
+    let mut buffer = vec![0_u8; VIDEO_PACKET_OVERHEAD + packet.payload.len()];
+    EnvelopeHeader::new(MessageKind::Video)
+        .encode(&mut buffer[..ENVELOPE_HEADER_LENGTH])
+        .expect("envelope buffer is sized correctly");
     packet
         .header
-        .encode(&mut buffer[..VIDEO_PACKET_HEADER_LENGTH])
+        .encode(&mut buffer[ENVELOPE_HEADER_LENGTH..VIDEO_PACKET_OVERHEAD])
         .expect("header buffer is sized correctly");
-    buffer[VIDEO_PACKET_HEADER_LENGTH..].copy_from_slice(&packet.payload);
+    buffer[VIDEO_PACKET_OVERHEAD..].copy_from_slice(&packet.payload);
     buffer
 }
 
 pub fn decode_packet(buffer: &[u8]) -> Result<VideoPacket, PacketCodecError> {
-    if buffer.len() < VIDEO_PACKET_HEADER_LENGTH {
+    let envelope = EnvelopeHeader::decode(buffer)?;
+    if envelope.kind != MessageKind::Video {
+        return Err(PacketCodecError::UnexpectedMessageKind(envelope.kind));
+    }
+
+    if buffer.len() < VIDEO_PACKET_OVERHEAD {
         return Err(PacketCodecError::BufferTooSmall);
     }
 
-    let header = VideoPacketHeader::decode(&buffer[..VIDEO_PACKET_HEADER_LENGTH])
-        .map_err(|_| PacketCodecError::BufferTooSmall)?;
-    let payload = buffer[VIDEO_PACKET_HEADER_LENGTH..].to_vec();
+    let header = VideoPacketHeader::decode(&buffer[ENVELOPE_HEADER_LENGTH..VIDEO_PACKET_OVERHEAD])?;
+    let payload = buffer[VIDEO_PACKET_OVERHEAD..].to_vec();
 
     Ok(VideoPacket { header, payload })
 }
@@ -30,9 +78,12 @@ pub fn decode_packet(buffer: &[u8]) -> Result<VideoPacket, PacketCodecError> {
 #[cfg(test)]
 mod tests {
     use super::{decode_packet, encode_packet, PacketCodecError};
+    use crate::core::envelope::{EnvelopeError, MessageKind, PROTOCOL_VERSION};
+    use crate::core::healthcheck::{HealthcheckKind, HealthcheckPacket};
+    use crate::core::fec::FecScheme;
+    use crate::core::packet::VideoPacket;
     use crate::core::packet::VideoPacketHeader;
     use crate::core::sequence::SequenceNumber;
-    use crate::core::packet::VideoPacket;
 
     #[test]
     fn round_trip_packet_encode_decode() {
@@ -43,6 +94,7 @@ mod tests {
                 frame_identifier: 7,
                 chunk_index: 0,
                 chunks_total: 1,
+                fec: FecScheme::None,
             },
             payload: b"payload".to_vec(),
         };
@@ -59,4 +111,55 @@ mod tests {
         let result = decode_packet(&buffer);
         assert_eq!(result, Err(PacketCodecError::BufferTooSmall));
     }
+
+    #[test]
+    fn decode_rejects_healthcheck_packet() {
+        let healthcheck = HealthcheckPacket {
+            kind: HealthcheckKind::Ping,
+            timestamp_nanos: 5,
+        };
+
+        let result = decode_packet(&healthcheck.encode());
+        assert_eq!(
+            result,
+            Err(PacketCodecError::UnexpectedMessageKind(
+                MessageKind::Healthcheck
+            ))
+        );
+    }
+
+    #[test]
+    fn decode_rejects_other_protocol_version() {
+        let packet = VideoPacket {
+            header: VideoPacketHeader {
+                sequence_number: SequenceNumber::new(1),
+                timestamp_nanos: 0,
+                frame_identifier: 0,
+                chunk_index: 0,
+                chunks_total: 1,
+                fec: FecScheme::None,
+            },
+            payload: b"x".to_vec(),
+        };
+
+        let mut buffer = encode_packet(&packet);
+        buffer[4] = PROTOCOL_VERSION + 1;
+
+        let result = decode_packet(&buffer);
+        assert_eq!(
+            result,
+            Err(PacketCodecError::Envelope(
+                EnvelopeError::UnsupportedVersion(PROTOCOL_VERSION + 1)
+            ))
+        );
+    }
+
+    #[test]
+    fn decode_rejects_stray_datagram() {
+        let result = decode_packet(b"definitely not a video packet");
+        assert_eq!(
+            result,
+            Err(PacketCodecError::Envelope(EnvelopeError::InvalidMagic))
+        );
+    }
 }
@@ -1,15 +1,26 @@
+use crate::codec::h264::split_annex_b;
+use crate::core::fec::FecScheme;
 use crate::core::packet::{VideoPacket, VideoPacketHeader};
 use crate::core::sequence::SequenceNumber;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct PacketizerConfig {
     pub max_payload_bytes: usize,
+    pub fec: FecScheme,
+    pub packetization: Packetization,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Packetization {
+    Fixed,
+    NalUnits,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum PacketizerError {
     EmptyPayload,
     PayloadTooLarge,
+    InvalidFecScheme,
 }
 
 impl std::fmt::Display for PacketizerError {
@@ -19,6 +30,9 @@ impl std::fmt::Display for PacketizerError {
             PacketizerError::PayloadTooLarge => {
                 write!(formatter, "packetizer payload too large")
             }
+            PacketizerError::InvalidFecScheme => {
+                write!(formatter, "packetizer fec scheme is invalid")
+            }
         }
     }
 }
@@ -38,6 +52,10 @@ impl Packetizer {
         }
     }
 
+    pub fn set_max_payload_bytes(&mut self, max_payload_bytes: usize) {
+        self.config.max_payload_bytes = max_payload_bytes;
+    }
+
     pub fn packetize(
         &mut self,
         frame_identifier: u32,
@@ -48,45 +66,147 @@ impl Packetizer {
             return Err(PacketizerError::EmptyPayload);
         }
 
-        if self.config.max_payload_bytes == 0 {
+        let fec = self.config.fec;
+        fec.validate()
+            .map_err(|_| PacketizerError::InvalidFecScheme)?;
+
+        let chunk_bytes = self
+            .config
+            .max_payload_bytes
+            .saturating_sub(fec.overhead_bytes());
+        if chunk_bytes == 0 {
             return Err(PacketizerError::PayloadTooLarge);
         }
 
-        let chunks_total = ((payload.len() + self.config.max_payload_bytes - 1)
-            / self.config.max_payload_bytes) as u16;
+        let chunks = match self.config.packetization {
+            Packetization::Fixed => payload.chunks(chunk_bytes).collect(),
+            Packetization::NalUnits => nal_unit_chunks(payload, chunk_bytes)
+                .unwrap_or_else(|| payload.chunks(chunk_bytes).collect()),
+        };
+        let data_chunks = chunks.len();
+        if data_chunks >= u16::MAX as usize {
+            return Err(PacketizerError::PayloadTooLarge);
+        }
+        let chunks_total = data_chunks as u16;
+        let parity_chunks = fec.parity_chunks(chunks_total);
+        if data_chunks + parity_chunks as usize > u16::MAX as usize {
+            return Err(PacketizerError::PayloadTooLarge);
+        }
 
-        let mut packets = Vec::with_capacity(chunks_total as usize);
+        let parity_per_group = fec.parity_per_group();
+        let mut group_parity: Vec<Vec<u8>> = Vec::new();
 
-        for (chunk_index, chunk) in payload
-            .chunks(self.config.max_payload_bytes)
-            .enumerate()
-        {
-            if chunk_index >= u16::MAX as usize {
-                return Err(PacketizerError::PayloadTooLarge);
-            }
+        let mut packets = Vec::with_capacity(data_chunks + parity_chunks as usize);
+        for chunk_index in emission_order(chunks_total, fec) {
+            let payload = if chunk_index < chunks_total {
+                chunks[chunk_index as usize].to_vec()
+            } else {
+                let parity_index = chunk_index - chunks_total;
+                let offset = parity_index % parity_per_group;
+                if offset == 0 {
+                    let members: Vec<&[u8]> = fec
+                        .group_members(fec.group_of_parity_chunk(parity_index), chunks_total)
+                        .into_iter()
+                        .map(|member| chunks[member as usize])
+                        .collect();
+                    group_parity = fec.encode_parity(&members);
+                }
+                std::mem::take(&mut group_parity[offset as usize])
+            };
 
-            let header = VideoPacketHeader {
-                sequence_number: self.next_sequence_number,
-                timestamp_nanos,
+            packets.push(self.next_packet(
                 frame_identifier,
-                chunk_index: chunk_index as u16,
+                timestamp_nanos,
+                chunk_index,
                 chunks_total,
-            };
-            self.next_sequence_number = self.next_sequence_number.next();
-
-            packets.push(VideoPacket {
-                header,
-                payload: chunk.to_vec(),
-            });
+                payload,
+            ));
         }
 
         Ok(packets)
     }
+
+    fn next_packet(
+        &mut self,
+        frame_identifier: u32,
+        timestamp_nanos: u64,
+        chunk_index: u16,
+        chunks_total: u16,
+        payload: Vec<u8>,
+    ) -> VideoPacket {
+        let header = VideoPacketHeader {
+            sequence_number: self.next_sequence_number,
+            timestamp_nanos,
+            frame_identifier,
+            chunk_index,
+            chunks_total,
+            fec: self.config.fec,
+        };
+        self.next_sequence_number = self.next_sequence_number.next();
+        VideoPacket { header, payload }
+    }
+}
+
+fn nal_unit_chunks(payload: &[u8], chunk_bytes: usize) -> Option<Vec<&[u8]>> {
+    let units = split_annex_b(payload)
+        .ok()
+        .filter(|units| !units.is_empty())?;
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut end = 0;
+    for unit in units {
+        if end - start + unit.len() <= chunk_bytes {
+            end += unit.len();
+            continue;
+        }
+        if end > start {
+            chunks.push(&payload[start..end]);
+        }
+        start = end;
+        if unit.len() <= chunk_bytes {
+            end += unit.len();
+            continue;
+        }
+
+        let unit_end = end + unit.len();
+        while end < unit_end {
+            let piece_end = (end + chunk_bytes).min(unit_end);
+            chunks.push(&payload[end..piece_end]);
+            end = piece_end;
+        }
+        start = end;
+    }
+    if end > start {
+        chunks.push(&payload[start..end]);
+    }
+    Some(chunks)
+}
+
+pub fn emission_order(chunks_total: u16, fec: FecScheme) -> Vec<u16> {
+    let parity_per_group = fec.parity_per_group();
+    let mut groups_closing_at: Vec<Vec<u16>> = vec![Vec::new(); chunks_total as usize];
+    for group in 0..fec.group_count(chunks_total) {
+        if let Some(&last) = fec.group_members(group, chunks_total).last() {
+            groups_closing_at[last as usize].push(group);
+        }
+    }
+
+    let mut order =
+        Vec::with_capacity(chunks_total as usize + fec.parity_chunks(chunks_total) as usize);
+    for chunk_index in 0..chunks_total {
+        order.push(chunk_index);
+        for &group in &groups_closing_at[chunk_index as usize] {
+            let parity_base = chunks_total + group * parity_per_group;
+            order.extend(parity_base..parity_base + parity_per_group);
+        }
+    }
+    order
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Packetizer, PacketizerConfig, PacketizerError};
+    use super::{emission_order, Packetization, Packetizer, PacketizerConfig, PacketizerError};
+    use crate::core::fec::FecScheme;
     use crate::core::sequence::SequenceNumber;
 
     #[test]
@@ -94,6 +214,8 @@ mod tests {
         let mut packetizer = Packetizer::new(
             PacketizerConfig {
                 max_payload_bytes: 4,
+                fec: FecScheme::None,
+                packetization: Packetization::Fixed,
             },
             SequenceNumber::new(1),
         );
@@ -116,6 +238,8 @@ mod tests {
         let mut packetizer = Packetizer::new(
             PacketizerConfig {
                 max_payload_bytes: 4,
+                fec: FecScheme::None,
+                packetization: Packetization::Fixed,
             },
             SequenceNumber::new(1),
         );
@@ -129,6 +253,8 @@ mod tests {
         let mut packetizer = Packetizer::new(
             PacketizerConfig {
                 max_payload_bytes: 0,
+                fec: FecScheme::None,
+                packetization: Packetization::Fixed,
             },
             SequenceNumber::new(1),
         );
@@ -137,4 +263,127 @@ mod tests {
         let result = packetizer.packetize(1, 0, &payload);
         assert_eq!(result, Err(PacketizerError::PayloadTooLarge));
     }
+
+    #[test]
+    fn packetize_emits_parity_after_each_group() {
+        let mut packetizer = Packetizer::new(
+            PacketizerConfig {
+                max_payload_bytes: 6,
+                fec: FecScheme::XorParity { group_size: 2 },
+                packetization: Packetization::Fixed,
+            },
+            SequenceNumber::new(10),
+        );
+
+        let payload: Vec<u8> = (0..10).collect();
+        let packets = packetizer.packetize(1, 0, &payload).expect("packetize");
+
+        let indices: Vec<u16> = packets
+            .iter()
+            .map(|packet| packet.header.chunk_index)
+            .collect();
+        assert_eq!(indices, vec![0, 1, 3, 2, 4]);
+        assert!(packets.iter().all(|packet| packet.header.chunks_total == 3));
+        assert!(packets.iter().all(|packet| packet.payload.len() <= 6));
+        assert!(packets[2].header.is_parity());
+        assert_eq!(packets[4].header.sequence_number, SequenceNumber::new(14));
+    }
+
+    #[test]
+    fn packetize_rejects_zero_group_size() {
+        let mut packetizer = Packetizer::new(
+            PacketizerConfig {
+                max_payload_bytes: 6,
+                fec: FecScheme::XorParity { group_size: 0 },
+                packetization: Packetization::Fixed,
+            },
+            SequenceNumber::new(1),
+        );
+
+        let result = packetizer.packetize(1, 0, &[1, 2, 3]);
+        assert_eq!(result, Err(PacketizerError::InvalidFecScheme));
+    }
+
+    #[test]
+    fn emission_order_matches_packetize() {
+        let fec = FecScheme::ReedSolomon {
+            data_shards: 3,
+            parity_shards: 2,
+        };
+        let mut packetizer = Packetizer::new(
+            PacketizerConfig {
+                max_payload_bytes: 6,
+                fec,
+                packetization: Packetization::Fixed,
+            },
+            SequenceNumber::new(0),
+        );
+
+        let payload: Vec<u8> = (0..27).collect();
+        let packets = packetizer.packetize(1, 0, &payload).expect("packetize");
+        let indices: Vec<u16> = packets
+            .iter()
+            .map(|packet| packet.header.chunk_index)
+            .collect();
+
+        assert_eq!(indices, emission_order(7, fec));
+        assert_eq!(indices, vec![0, 1, 2, 3, 4, 9, 10, 5, 11, 12, 6, 7, 8]);
+    }
+
+    fn annex_b_unit(nal_type: u8, body_bytes: usize) -> Vec<u8> {
+        let mut unit = vec![0, 0, 0, 1, nal_type];
+        unit.extend((0..body_bytes).map(|index| (index % 200 + 16) as u8));
+        unit
+    }
+
+    #[test]
+    fn nal_aware_packetization_aligns_chunks_to_nal_units() {
+        let mut packetizer = Packetizer::new(
+            PacketizerConfig {
+                max_payload_bytes: 32,
+                fec: FecScheme::None,
+                packetization: Packetization::NalUnits,
+            },
+            SequenceNumber::new(0),
+        );
+
+        let sps = annex_b_unit(0x67, 6);
+        let pps = annex_b_unit(0x68, 3);
+        let large_slice = annex_b_unit(0x65, 55);
+        let small_slice = annex_b_unit(0x41, 10);
+        let trailing_slice = annex_b_unit(0x41, 20);
+        let payload = [&sps[..], &pps, &large_slice, &small_slice, &trailing_slice].concat();
+
+        let packets = packetizer.packetize(1, 0, &payload).expect("packetize");
+        let chunks: Vec<&[u8]> = packets
+            .iter()
+            .map(|packet| packet.payload.as_slice())
+            .collect();
+
+        assert_eq!(chunks[0], [&sps[..], &pps].concat());
+        assert_eq!(chunks[1], &large_slice[..32]);
+        assert_eq!(chunks[2], &large_slice[32..]);
+        assert_eq!(chunks[3], &small_slice[..]);
+        assert_eq!(chunks[4], &trailing_slice[..]);
+        assert_eq!(chunks.len(), 5);
+        assert_eq!(chunks.concat(), payload);
+    }
+
+    #[test]
+    fn nal_aware_packetization_falls_back_for_other_payloads() {
+        let mut packetizer = Packetizer::new(
+            PacketizerConfig {
+                max_payload_bytes: 4,
+                fec: FecScheme::None,
+                packetization: Packetization::NalUnits,
+            },
+            SequenceNumber::new(0),
+        );
+
+        let packets = packetizer
+            .packetize(1, 0, &[1, 2, 3, 4, 5, 6, 7])
+            .expect("packetize");
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[1].payload, vec![5, 6, 7]);
+    }
 }
@@ -0,0 +1,201 @@
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathMtuConfig {
+    pub min_datagram_bytes: usize,
+    pub max_datagram_bytes: usize,
+    pub probe_timeout: Duration,
+    pub max_attempts: u8,
+}
+
+impl Default for PathMtuConfig {
+    fn default() -> Self {
+        Self {
+            min_datagram_bytes: 1228,
+            max_datagram_bytes: 8972,
+            probe_timeout: Duration::from_millis(100),
+            max_attempts: 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProbeStep {
+    Send(usize),
+    Wait,
+    Complete(usize),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ProbeInFlight {
+    datagram_bytes: usize,
+    sent_at: Instant,
+    attempts: u8,
+}
+
+#[derive(Debug, Clone)]
+pub struct PathMtuProber {
+    config: PathMtuConfig,
+    largest_working: usize,
+    smallest_failing: usize,
+    in_flight: Option<ProbeInFlight>,
+}
+
+impl PathMtuProber {
+    pub fn new(config: PathMtuConfig) -> Self {
+        Self {
+            config,
+            largest_working: config.min_datagram_bytes,
+            smallest_failing: config.max_datagram_bytes.max(config.min_datagram_bytes) + 1,
+            in_flight: None,
+        }
+    }
+
+    pub fn poll(&mut self, now: Instant) -> ProbeStep {
+        loop {
+            if self.largest_working + 1 >= self.smallest_failing {
+                return ProbeStep::Complete(self.largest_working);
+            }
+
+            let Some(probe) = self.in_flight.as_mut() else {
+                let datagram_bytes =
+                    self.largest_working + (self.smallest_failing - self.largest_working) / 2;
+                self.in_flight = Some(ProbeInFlight {
+                    datagram_bytes,
+                    sent_at: now,
+                    attempts: 1,
+                });
+                return ProbeStep::Send(datagram_bytes);
+            };
+
+            if now.saturating_duration_since(probe.sent_at) < self.config.probe_timeout {
+                return ProbeStep::Wait;
+            }
+            if probe.attempts < self.config.max_attempts {
+                probe.sent_at = now;
+                probe.attempts += 1;
+                return ProbeStep::Send(probe.datagram_bytes);
+            }
+
+            let datagram_bytes = probe.datagram_bytes;
+            self.on_too_large(datagram_bytes);
+        }
+    }
+
+    pub fn on_ack(&mut self, datagram_bytes: usize) {
+        if datagram_bytes > self.largest_working && datagram_bytes < self.smallest_failing {
+            self.largest_working = datagram_bytes;
+        }
+        self.clear_in_flight(datagram_bytes);
+    }
+
+    pub fn on_too_large(&mut self, datagram_bytes: usize) {
+        if datagram_bytes > self.largest_working && datagram_bytes < self.smallest_failing {
+            self.smallest_failing = datagram_bytes;
+        }
+        self.clear_in_flight(datagram_bytes);
+    }
+
+    pub fn result(&self) -> Option<usize> {
+        (self.largest_working + 1 >= self.smallest_failing).then_some(self.largest_working)
+    }
+
+    fn clear_in_flight(&mut self, datagram_bytes: usize) {
+        if self
+            .in_flight
+            .is_some_and(|probe| probe.datagram_bytes == datagram_bytes)
+        {
+            self.in_flight = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PathMtuConfig, PathMtuProber, ProbeStep};
+    use std::time::{Duration, Instant};
+
+    fn config(min_datagram_bytes: usize, max_datagram_bytes: usize) -> PathMtuConfig {
+        PathMtuConfig {
+            min_datagram_bytes,
+            max_datagram_bytes,
+            ..PathMtuConfig::default()
+        }
+    }
+
+    fn discover(prober: &mut PathMtuProber, path_mtu: usize) -> (usize, usize) {
+        let mut now = Instant::now();
+        let mut probes = 0;
+        loop {
+            match prober.poll(now) {
+                ProbeStep::Send(datagram_bytes) => {
+                    probes += 1;
+                    if datagram_bytes <= path_mtu {
+                        prober.on_ack(datagram_bytes);
+                    }
+                }
+                ProbeStep::Wait => now += Duration::from_millis(10),
+                ProbeStep::Complete(datagram_bytes) => return (datagram_bytes, probes),
+            }
+        }
+    }
+
+    #[test]
+    fn finds_jumbo_frame_path() {
+        let mut prober = PathMtuProber::new(config(1228, 8972));
+        let (datagram_bytes, _) = discover(&mut prober, 8972);
+        assert_eq!(datagram_bytes, 8972);
+        assert_eq!(prober.result(), Some(8972));
+    }
+
+    #[test]
+    fn finds_exact_limit_with_retries_on_silence() {
+        let mut prober = PathMtuProber::new(config(1228, 8972));
+        let (datagram_bytes, probes) = discover(&mut prober, 1472);
+        assert_eq!(datagram_bytes, 1472);
+        assert!(probes < 60, "{probes} probes");
+    }
+
+    #[test]
+    fn retries_before_declaring_size_too_large() {
+        let mut prober = PathMtuProber::new(PathMtuConfig {
+            probe_timeout: Duration::from_millis(100),
+            max_attempts: 2,
+            ..config(1000, 2000)
+        });
+        let start = Instant::now();
+
+        assert_eq!(prober.poll(start), ProbeStep::Send(1500));
+        assert_eq!(
+            prober.poll(start + Duration::from_millis(50)),
+            ProbeStep::Wait
+        );
+        assert_eq!(
+            prober.poll(start + Duration::from_millis(100)),
+            ProbeStep::Send(1500)
+        );
+        assert_eq!(
+            prober.poll(start + Duration::from_millis(200)),
+            ProbeStep::Send(1250)
+        );
+    }
+
+    #[test]
+    fn local_message_too_large_skips_retries() {
+        let mut prober = PathMtuProber::new(config(1000, 2000));
+        let now = Instant::now();
+
+        assert_eq!(prober.poll(now), ProbeStep::Send(1500));
+        prober.on_too_large(1500);
+        assert_eq!(prober.poll(now), ProbeStep::Send(1250));
+        prober.on_ack(1250);
+        assert_eq!(prober.poll(now), ProbeStep::Send(1375));
+        assert_eq!(prober.result(), None);
+    }
+
+    #[test]
+    fn completes_immediately_without_room_to_grow() {
+        let mut prober = PathMtuProber::new(config(1500, 1200));
+        assert_eq!(prober.poll(Instant::now()), ProbeStep::Complete(1500));
+    }
+}
@@ -1,5 +1,10 @@
+use crate::core::fec::FecScheme;
 use crate::core::packet::VideoPacket;
-use std::collections::BTreeMap;
+use crate::core::sequence::serial_distance;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+const RECENTLY_FINISHED_CAPACITY: usize = 64;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ReassembledFrame {
@@ -14,64 +19,237 @@ pub enum ReassemblyError {
     InconsistentChunkCount,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvictionReason {
+    Deadline,
+    Capacity,
+    Superseded,
+}
+
+impl std::fmt::Display for EvictionReason {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EvictionReason::Deadline => write!(formatter, "deadline"),
+            EvictionReason::Capacity => write!(formatter, "capacity"),
+            EvictionReason::Superseded => write!(formatter, "superseded"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EvictedFrame {
+    pub frame_identifier: u32,
+    pub chunks_received: u16,
+    pub chunks_total: u16,
+    pub reason: EvictionReason,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartialFrame {
+    pub frame_identifier: u32,
+    pub timestamp_nanos: u64,
+    pub chunks: Vec<Option<Vec<u8>>>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReassemblerStats {
+    pub completed_frames: u64,
+    pub evicted_frames: u64,
+    pub recovered_chunks: u64,
+    pub duplicate_chunks: u64,
+    pub late_chunks_completed: u64,
+    pub late_chunks_evicted: u64,
+    pub invalid_chunk_indices: u64,
+    pub inconsistent_chunk_counts: u64,
+    pub bytes_buffered: usize,
+}
+
 #[derive(Debug, Clone)]
 struct FrameAssembly {
+    first_arrival: Instant,
     timestamp_nanos: u64,
     chunks_total: u16,
+    fec: FecScheme,
     received_count: u16,
+    recovered_count: u16,
     chunks: Vec<Option<Vec<u8>>>,
+    parity: Vec<Option<Vec<u8>>>,
+}
+
+impl FrameAssembly {
+    fn store(&mut self, chunk_index: u16, payload: Vec<u8>) -> bool {
+        if chunk_index < self.chunks_total {
+            let slot = &mut self.chunks[chunk_index as usize];
+            let is_new = slot.is_none();
+            if is_new {
+                *slot = Some(payload);
+                self.received_count += 1;
+            }
+            if let Some(group) = self.fec.group_of_data_chunk(chunk_index, self.chunks_total) {
+                self.recover_group(group);
+            }
+            is_new
+        } else {
+            let parity_index = chunk_index - self.chunks_total;
+            let slot = &mut self.parity[parity_index as usize];
+            let is_new = slot.is_none();
+            if is_new {
+                *slot = Some(payload);
+            }
+            self.recover_group(self.fec.group_of_parity_chunk(parity_index));
+            is_new
+        }
+    }
+
+    fn buffered_bytes(&self) -> usize {
+        self.chunks
+            .iter()
+            .chain(self.parity.iter())
+            .flatten()
+            .map(|chunk| chunk.len())
+            .sum()
+    }
+
+    fn recover_group(&mut self, group: u16) {
+        let members = self.fec.group_members(group, self.chunks_total);
+        if members
+            .iter()
+            .all(|&member| self.chunks[member as usize].is_some())
+        {
+            return;
+        }
+
+        let parity_per_group = self.fec.parity_per_group() as usize;
+        let parity_start = group as usize * parity_per_group;
+        let parity: Vec<Option<&[u8]>> = self.parity[parity_start..parity_start + parity_per_group]
+            .iter()
+            .map(|chunk| chunk.as_deref())
+            .collect();
+        let present: Vec<Option<&[u8]>> = members
+            .iter()
+            .map(|&member| self.chunks[member as usize].as_deref())
+            .collect();
+
+        if let Some(recovered) = self.fec.recover(&present, &parity) {
+            for (position, payload) in recovered {
+                let slot = &mut self.chunks[members[position] as usize];
+                if slot.is_none() {
+                    *slot = Some(payload);
+                    self.received_count += 1;
+                    self.recovered_count += 1;
+                }
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct FrameReassembler {
     max_in_flight_frames: usize,
-    frames: BTreeMap<u32, FrameAssembly>,
+    frame_deadline: Option<Duration>,
+    keep_partial_frames: bool,
+    frames: HashMap<u32, FrameAssembly>,
+    newest_frame: Option<u32>,
+    recently_finished: VecDeque<(u32, Option<EvictionReason>)>,
+    evicted: Vec<EvictedFrame>,
+    partial: Vec<PartialFrame>,
+    stats: ReassemblerStats,
 }
 
 impl FrameReassembler {
     pub fn new(max_in_flight_frames: usize) -> Self {
         Self {
             max_in_flight_frames,
-            frames: BTreeMap::new(),
+            frame_deadline: None,
+            keep_partial_frames: false,
+            frames: HashMap::new(),
+            newest_frame: None,
+            recently_finished: VecDeque::with_capacity(RECENTLY_FINISHED_CAPACITY),
+            evicted: Vec::new(),
+            partial: Vec::new(),
+            stats: ReassemblerStats::default(),
         }
     }
 
+    pub fn with_frame_deadline(mut self, frame_deadline: Duration) -> Self {
+        self.frame_deadline = Some(frame_deadline);
+        self
+    }
+
+    pub fn with_partial_frames(mut self) -> Self {
+        self.keep_partial_frames = true;
+        self
+    }
+
     pub fn push_packet(
         &mut self,
         packet: VideoPacket,
     ) -> Result<Option<ReassembledFrame>, ReassemblyError> {
-        if packet.header.chunk_index >= packet.header.chunks_total {
+        self.push_packet_at(packet, Instant::now())
+    }
+
+    pub fn push_packet_at(
+        &mut self,
+        packet: VideoPacket,
+        now: Instant,
+    ) -> Result<Option<ReassembledFrame>, ReassemblyError> {
+        self.expire(now);
+
+        let header = packet.header;
+        let parity_chunks = header.fec.parity_chunks(header.chunks_total);
+        if header.chunk_index as usize >= header.chunks_total as usize + parity_chunks as usize {
+            self.stats.invalid_chunk_indices += 1;
             return Err(ReassemblyError::InvalidChunkIndex);
         }
 
-        let frame_identifier = packet.header.frame_identifier;
+        let frame_identifier = header.frame_identifier;
+        if let Some((_, eviction)) = self
+            .recently_finished
+            .iter()
+            .find(|(finished, _)| *finished == frame_identifier)
+        {
+            match eviction {
+                Some(_) => self.stats.late_chunks_evicted += 1,
+                None if header.is_parity() => {}
+                None => self.stats.late_chunks_completed += 1,
+            }
+            return Ok(None);
+        }
 
-        let entry = self.frames.entry(frame_identifier).or_insert_with(|| {
-            let chunks_total = packet.header.chunks_total;
-            FrameAssembly {
-                timestamp_nanos: packet.header.timestamp_nanos,
-                chunks_total,
+        if self
+            .newest_frame
+            .is_none_or(|newest| serial_distance(newest, frame_identifier) > 0)
+        {
+            self.newest_frame = Some(frame_identifier);
+        }
+
+        let entry = self
+            .frames
+            .entry(frame_identifier)
+            .or_insert_with(|| FrameAssembly {
+                first_arrival: now,
+                timestamp_nanos: header.timestamp_nanos,
+                chunks_total: header.chunks_total,
+                fec: header.fec,
                 received_count: 0,
-                chunks: vec![None; chunks_total as usize],
-            }
-        });
+                recovered_count: 0,
+                chunks: vec![None; header.chunks_total as usize],
+                parity: vec![None; parity_chunks as usize],
+            });
 
-        if entry.chunks_total != packet.header.chunks_total {
+        if entry.chunks_total != header.chunks_total || entry.fec != header.fec {
+            self.stats.inconsistent_chunk_counts += 1;
             return Err(ReassemblyError::InconsistentChunkCount);
         }
 
-        let chunk_index = packet.header.chunk_index as usize;
-        if entry.chunks[chunk_index].is_none() {
-            entry.chunks[chunk_index] = Some(packet.payload);
-            entry.received_count += 1;
+        if !entry.store(header.chunk_index, packet.payload) {
+            self.stats.duplicate_chunks += 1;
         }
 
         if entry.received_count == entry.chunks_total {
             let mut payload = Vec::new();
-            for chunk in entry.chunks.iter() {
-                if let Some(bytes) = chunk {
-                    payload.extend_from_slice(bytes);
-                }
+            for bytes in entry.chunks.iter().flatten() {
+                payload.extend_from_slice(bytes);
             }
 
             let frame = ReassembledFrame {
@@ -79,7 +257,10 @@ impl FrameReassembler {
                 timestamp_nanos: entry.timestamp_nanos,
                 payload,
             };
+            self.stats.completed_frames += 1;
+            self.stats.recovered_chunks += u64::from(entry.recovered_count);
             self.frames.remove(&frame_identifier);
+            self.remember_finished(frame_identifier, None);
             return Ok(Some(frame));
         }
 
@@ -87,10 +268,102 @@ impl FrameReassembler {
         Ok(None)
     }
 
+    pub fn expire(&mut self, now: Instant) {
+        let Some(frame_deadline) = self.frame_deadline else {
+            return;
+        };
+
+        let mut expired: Vec<u32> = self
+            .frames
+            .iter()
+            .filter(|(_, frame)| {
+                now.saturating_duration_since(frame.first_arrival) >= frame_deadline
+            })
+            .map(|(&frame_identifier, _)| frame_identifier)
+            .collect();
+        expired.sort_by_key(|&frame_identifier| self.age_order(frame_identifier));
+        for frame_identifier in expired {
+            self.evict(frame_identifier, EvictionReason::Deadline);
+        }
+    }
+
+    pub fn take_evicted(&mut self) -> Vec<EvictedFrame> {
+        std::mem::take(&mut self.evicted)
+    }
+
+    pub fn take_partial_frames(&mut self) -> Vec<PartialFrame> {
+        std::mem::take(&mut self.partial)
+    }
+
+    pub fn stats(&self) -> ReassemblerStats {
+        ReassemblerStats {
+            bytes_buffered: self
+                .frames
+                .values()
+                .map(FrameAssembly::buffered_bytes)
+                .sum(),
+            ..self.stats
+        }
+    }
+
+    pub fn supersede_older_than(&mut self, frame_identifier: u32) {
+        let mut older: Vec<u32> = self
+            .frames
+            .keys()
+            .copied()
+            .filter(|&other| serial_distance(frame_identifier, other) < 0)
+            .collect();
+        older.sort_by_key(|&other| serial_distance(frame_identifier, other));
+        for other in older {
+            self.evict(other, EvictionReason::Superseded);
+        }
+    }
+
+    fn age_order(&self, frame_identifier: u32) -> i32 {
+        serial_distance(
+            self.newest_frame.unwrap_or(frame_identifier),
+            frame_identifier,
+        )
+    }
+
+    fn evict(&mut self, frame_identifier: u32, reason: EvictionReason) {
+        if let Some(frame) = self.frames.remove(&frame_identifier) {
+            self.stats.evicted_frames += 1;
+            self.stats.recovered_chunks += u64::from(frame.recovered_count);
+            self.remember_finished(frame_identifier, Some(reason));
+            self.evicted.push(EvictedFrame {
+                frame_identifier,
+                chunks_received: frame.received_count,
+                chunks_total: frame.chunks_total,
+                reason,
+            });
+            if self.keep_partial_frames && frame.received_count > 0 {
+                self.partial.push(PartialFrame {
+                    frame_identifier,
+                    timestamp_nanos: frame.timestamp_nanos,
+                    chunks: frame.chunks,
+                });
+            }
+        }
+    }
+
+    fn remember_finished(&mut self, frame_identifier: u32, eviction: Option<EvictionReason>) {
+        if self.recently_finished.len() == RECENTLY_FINISHED_CAPACITY {
+            self.recently_finished.pop_front();
+        }
+        self.recently_finished
+            .push_back((frame_identifier, eviction));
+    }
+
     fn evict_if_needed(&mut self) {
         while self.frames.len() > self.max_in_flight_frames {
-            if let Some(oldest_key) = self.frames.keys().next().cloned() {
-                self.frames.remove(&oldest_key);
+            if let Some(oldest_key) = self
+                .frames
+                .keys()
+                .copied()
+                .min_by_key(|&frame_identifier| self.age_order(frame_identifier))
+            {
+                self.evict(oldest_key, EvictionReason::Capacity);
             } else {
                 break;
             }
@@ -100,9 +373,15 @@ impl FrameReassembler {
 
 #[cfg(test)]
 mod tests {
-    use super::{FrameReassembler, ReassemblyError};
+    use super::{
+        EvictedFrame, EvictionReason, FrameReassembler, PartialFrame, ReassemblerStats,
+        ReassemblyError,
+    };
+    use crate::core::fec::FecScheme;
     use crate::core::packet::{VideoPacket, VideoPacketHeader};
+    use crate::core::packetizer::{Packetization, Packetizer, PacketizerConfig};
     use crate::core::sequence::SequenceNumber;
+    use std::time::{Duration, Instant};
 
     fn packet(
         frame_identifier: u32,
@@ -117,6 +396,7 @@ mod tests {
                 frame_identifier,
                 chunk_index,
                 chunks_total,
+                fec: FecScheme::None,
             },
             payload: payload.to_vec(),
         }
@@ -186,4 +466,445 @@ mod tests {
         let result = reassembler.push_packet(second);
         assert_eq!(result, Err(ReassemblyError::InconsistentChunkCount));
     }
+
+    #[test]
+    fn stats_count_each_outcome() {
+        let mut reassembler = FrameReassembler::new(1);
+
+        let _ = reassembler.push_packet(packet(1, 0, 2, b"hello "));
+        let _ = reassembler.push_packet(packet(1, 0, 2, b"hello "));
+        let _ = reassembler.push_packet(packet(1, 5, 2, b"bad"));
+        let _ = reassembler.push_packet(packet(1, 1, 3, b"bad"));
+        assert_eq!(reassembler.stats().bytes_buffered, 6);
+
+        let _ = reassembler.push_packet(packet(1, 1, 2, b"world"));
+        let _ = reassembler.push_packet(packet(1, 1, 2, b"world"));
+        let _ = reassembler.push_packet(packet(2, 0, 2, b"lost"));
+        let _ = reassembler.push_packet(packet(3, 0, 2, b"lost"));
+        let _ = reassembler.push_packet(packet(2, 1, 2, b"late"));
+
+        assert_eq!(
+            reassembler.stats(),
+            ReassemblerStats {
+                completed_frames: 1,
+                evicted_frames: 1,
+                recovered_chunks: 0,
+                duplicate_chunks: 1,
+                late_chunks_completed: 1,
+                late_chunks_evicted: 1,
+                invalid_chunk_indices: 1,
+                inconsistent_chunk_counts: 1,
+                bytes_buffered: 4,
+            }
+        );
+    }
+
+    #[test]
+    fn stats_count_recovered_chunks() {
+        let payload: Vec<u8> = (0..37).collect();
+        let packets = packetize_with_parity(3, &payload);
+        let mut reassembler = FrameReassembler::new(4);
+
+        for packet in packets {
+            if !matches!(packet.header.chunk_index, 1 | 4) {
+                let _ = reassembler.push_packet(packet);
+            }
+        }
+
+        let stats = reassembler.stats();
+        assert_eq!(stats.completed_frames, 1);
+        assert_eq!(stats.recovered_chunks, 2);
+        assert_eq!(stats.bytes_buffered, 0);
+    }
+
+    fn packetize_with_parity(group_size: u8, payload: &[u8]) -> Vec<VideoPacket> {
+        let mut packetizer = Packetizer::new(
+            PacketizerConfig {
+                max_payload_bytes: 6,
+                fec: FecScheme::XorParity { group_size },
+                packetization: Packetization::Fixed,
+            },
+            SequenceNumber::new(0),
+        );
+        packetizer.packetize(3, 42, payload).expect("packetize")
+    }
+
+    #[test]
+    fn recovers_one_lost_chunk_per_group() {
+        let payload: Vec<u8> = (0..37).collect();
+        let packets = packetize_with_parity(3, &payload);
+        let mut reassembler = FrameReassembler::new(4);
+
+        let mut completed = None;
+        for packet in packets {
+            let dropped = matches!(packet.header.chunk_index, 1 | 4 | 9);
+            if dropped {
+                continue;
+            }
+            if let Some(frame) = reassembler.push_packet(packet).unwrap() {
+                completed = Some(frame);
+            }
+        }
+
+        let frame = completed.expect("frame should be recovered");
+        assert_eq!(frame.payload, payload);
+        assert_eq!(frame.timestamp_nanos, 42);
+    }
+
+    #[test]
+    fn recovers_when_parity_arrives_before_data() {
+        let payload: Vec<u8> = (0..20).collect();
+        let mut packets = packetize_with_parity(4, &payload);
+        packets.reverse();
+        packets.retain(|packet| packet.header.chunk_index != 0);
+
+        let mut reassembler = FrameReassembler::new(4);
+        let mut completed = None;
+        for packet in packets {
+            if let Some(frame) = reassembler.push_packet(packet).unwrap() {
+                completed = Some(frame);
+            }
+        }
+
+        assert_eq!(completed.expect("frame").payload, payload);
+    }
+
+    #[test]
+    fn cannot_recover_two_losses_in_one_group() {
+        let payload: Vec<u8> = (0..16).collect();
+        let packets = packetize_with_parity(4, &payload);
+        let mut reassembler = FrameReassembler::new(4);
+
+        for packet in packets {
+            if matches!(packet.header.chunk_index, 0 | 1) {
+                continue;
+            }
+            assert!(reassembler.push_packet(packet).unwrap().is_none());
+        }
+    }
+
+    #[test]
+    fn rejects_parity_index_beyond_groups() {
+        let mut reassembler = FrameReassembler::new(4);
+        let mut bad_packet = packet(7, 4, 2, b"oops");
+        bad_packet.header.fec = FecScheme::XorParity { group_size: 2 };
+
+        let result = reassembler.push_packet(bad_packet);
+        assert_eq!(result, Err(ReassemblyError::InvalidChunkIndex));
+    }
+
+    #[test]
+    fn ignores_late_parity_for_completed_frame() {
+        let payload: Vec<u8> = (0..8).collect();
+        let packets = packetize_with_parity(2, &payload);
+        let mut reassembler = FrameReassembler::new(4);
+
+        let mut completed = 0;
+        for packet in packets {
+            if reassembler.push_packet(packet).unwrap().is_some() {
+                completed += 1;
+            }
+        }
+
+        assert_eq!(completed, 1);
+        assert!(reassembler.frames.is_empty());
+        assert_eq!(reassembler.stats().late_chunks_completed, 0);
+    }
+
+    #[test]
+    fn evicts_oldest_frame_across_identifier_wrap() {
+        let mut reassembler = FrameReassembler::new(3);
+        let identifiers: Vec<u32> = (0..6)
+            .map(|step| (u32::MAX - 2).wrapping_add(step))
+            .collect();
+
+        for &frame_identifier in &identifiers {
+            let result = reassembler.push_packet(packet(frame_identifier, 0, 2, b"head"));
+            assert_eq!(result, Ok(None));
+        }
+
+        let evicted: Vec<u32> = reassembler
+            .take_evicted()
+            .iter()
+            .map(|frame| frame.frame_identifier)
+            .collect();
+        assert_eq!(evicted, identifiers[..3].to_vec());
+        for &frame_identifier in &identifiers[3..] {
+            let frame = reassembler
+                .push_packet(packet(frame_identifier, 1, 2, b"tail"))
+                .unwrap()
+                .expect("frame completes");
+            assert_eq!(frame.frame_identifier, frame_identifier);
+        }
+    }
+
+    #[test]
+    fn evicts_oldest_frame_across_half_range() {
+        let mut reassembler = FrameReassembler::new(4);
+        for frame_identifier in [2, 3, 1 << 31, (1 << 31) + 2, (1 << 31) + 1] {
+            let result = reassembler.push_packet(packet(frame_identifier, 0, 2, b"head"));
+            assert_eq!(result, Ok(None));
+        }
+
+        let evicted: Vec<u32> = reassembler
+            .take_evicted()
+            .iter()
+            .map(|frame| frame.frame_identifier)
+            .collect();
+        assert_eq!(evicted, vec![2]);
+
+        reassembler.supersede_older_than((1 << 31) + 2);
+        let superseded: Vec<u32> = reassembler
+            .take_evicted()
+            .iter()
+            .map(|frame| frame.frame_identifier)
+            .collect();
+        assert_eq!(superseded, vec![3, 1 << 31, (1 << 31) + 1]);
+    }
+
+    #[test]
+    fn streams_across_sequence_and_frame_wrap() {
+        let mut packetizer = Packetizer::new(
+            PacketizerConfig {
+                max_payload_bytes: 4,
+                fec: FecScheme::XorParity { group_size: 2 },
+                packetization: Packetization::Fixed,
+            },
+            SequenceNumber::new(u32::MAX - 20),
+        );
+        let mut reassembler = FrameReassembler::new(2);
+
+        let mut frame_identifier = u32::MAX - 5;
+        let mut completed = Vec::new();
+        for _ in 0..12 {
+            let payload = frame_identifier.to_be_bytes().repeat(3);
+            let packets = packetizer
+                .packetize(frame_identifier, 0, &payload)
+                .expect("packetize");
+            for packet in packets.into_iter().skip(1) {
+                if let Some(frame) = reassembler.push_packet(packet).unwrap() {
+                    assert_eq!(frame.payload, payload);
+                    completed.push(frame.frame_identifier);
+                }
+            }
+            frame_identifier = frame_identifier.wrapping_add(1);
+        }
+
+        let expected: Vec<u32> = (0..12)
+            .map(|step| (u32::MAX - 5).wrapping_add(step))
+            .collect();
+        assert_eq!(completed, expected);
+        assert!(reassembler.take_evicted().is_empty());
+    }
+
+    #[test]
+    fn reports_and_ignores_evicted_frames() {
+        let mut reassembler = FrameReassembler::new(2);
+        for frame_identifier in 0..3 {
+            let result = reassembler.push_packet(packet(frame_identifier, 0, 2, b"head"));
+            assert_eq!(result, Ok(None));
+        }
+
+        assert_eq!(
+            reassembler.take_evicted(),
+            vec![EvictedFrame {
+                frame_identifier: 0,
+                chunks_received: 1,
+                chunks_total: 2,
+                reason: EvictionReason::Capacity,
+            }]
+        );
+        assert!(reassembler.take_evicted().is_empty());
+
+        let late = reassembler.push_packet(packet(0, 1, 2, b"tail"));
+        assert_eq!(late, Ok(None));
+        assert!(!reassembler.frames.contains_key(&0));
+    }
+
+    #[test]
+    fn evicts_frames_past_deadline() {
+        let mut reassembler =
+            FrameReassembler::new(8).with_frame_deadline(Duration::from_millis(50));
+        let start = Instant::now();
+
+        let result = reassembler.push_packet_at(packet(1, 0, 3, b"a"), start);
+        assert_eq!(result, Ok(None));
+        let result =
+            reassembler.push_packet_at(packet(2, 0, 2, b"b"), start + Duration::from_millis(30));
+        assert_eq!(result, Ok(None));
+
+        reassembler.expire(start + Duration::from_millis(49));
+        assert!(reassembler.take_evicted().is_empty());
+
+        reassembler.expire(start + Duration::from_millis(60));
+        assert_eq!(
+            reassembler.take_evicted(),
+            vec![EvictedFrame {
+                frame_identifier: 1,
+                chunks_received: 1,
+                chunks_total: 3,
+                reason: EvictionReason::Deadline,
+            }]
+        );
+
+        let frame = reassembler
+            .push_packet_at(packet(2, 1, 2, b"c"), start + Duration::from_millis(70))
+            .unwrap();
+        assert!(frame.is_some());
+    }
+
+    #[test]
+    fn supersedes_older_incomplete_frames_on_request() {
+        let mut reassembler = FrameReassembler::new(8);
+        assert_eq!(
+            reassembler.push_packet(packet(u32::MAX, 0, 2, b"a")),
+            Ok(None)
+        );
+        assert_eq!(reassembler.push_packet(packet(1, 0, 2, b"b")), Ok(None));
+
+        let frame = reassembler.push_packet(packet(0, 0, 1, b"c")).unwrap();
+        assert!(frame.is_some());
+        assert!(reassembler.take_evicted().is_empty());
+
+        reassembler.supersede_older_than(0);
+        let evicted = reassembler.take_evicted();
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].frame_identifier, u32::MAX);
+        assert_eq!(evicted[0].reason, EvictionReason::Superseded);
+        assert!(reassembler.frames.contains_key(&1));
+    }
+
+    struct SeededLoss {
+        state: u64,
+    }
+
+    impl SeededLoss {
+        fn next(&mut self) -> u64 {
+            self.state ^= self.state << 13;
+            self.state ^= self.state >> 7;
+            self.state ^= self.state << 17;
+            self.state
+        }
+
+        fn drops(&mut self, percent: u64) -> bool {
+            self.next() % 100 < percent
+        }
+    }
+
+    fn packetize_reed_solomon(frame_identifier: u32, payload: &[u8]) -> Vec<VideoPacket> {
+        let mut packetizer = Packetizer::new(
+            PacketizerConfig {
+                max_payload_bytes: 18,
+                fec: FecScheme::ReedSolomon {
+                    data_shards: 8,
+                    parity_shards: 3,
+                },
+                packetization: Packetization::Fixed,
+            },
+            SequenceNumber::new(0),
+        );
+        packetizer
+            .packetize(frame_identifier, 0, payload)
+            .expect("packetize")
+    }
+
+    #[test]
+    fn reed_solomon_recovers_burst_spread_by_interleaving() {
+        let payload: Vec<u8> = (0..512).map(|byte| (byte % 251) as u8).collect();
+        let packets = packetize_reed_solomon(1, &payload);
+        let mut reassembler = FrameReassembler::new(4);
+
+        let mut completed = None;
+        for packet in packets {
+            if (10..19).contains(&packet.header.chunk_index) {
+                continue;
+            }
+            if let Some(frame) = reassembler.push_packet(packet).unwrap() {
+                completed = Some(frame);
+            }
+        }
+
+        assert_eq!(
+            completed.expect("burst should be recovered").payload,
+            payload
+        );
+    }
+
+    #[test]
+    fn reed_solomon_recovers_seeded_loss_within_budget() {
+        let scheme = FecScheme::ReedSolomon {
+            data_shards: 8,
+            parity_shards: 3,
+        };
+        let mut loss = SeededLoss { state: 0x5EED_F00D };
+        let mut reassembler = FrameReassembler::new(4);
+        let mut recoverable_frames = 0;
+
+        for frame_identifier in 0..200_u32 {
+            let payload: Vec<u8> = (0..(300 + frame_identifier as usize))
+                .map(|byte| (byte as u32 ^ frame_identifier) as u8)
+                .collect();
+            let packets = packetize_reed_solomon(frame_identifier, &payload);
+            let chunks_total = packets[0].header.chunks_total;
+
+            let mut lost_per_group = vec![0_u16; scheme.group_count(chunks_total) as usize];
+            let mut delivered = Vec::new();
+            for packet in packets {
+                if loss.drops(12) {
+                    let index = packet.header.chunk_index;
+                    let group = if index < chunks_total {
+                        scheme.group_of_data_chunk(index, chunks_total).unwrap()
+                    } else {
+                        scheme.group_of_parity_chunk(index - chunks_total)
+                    };
+                    lost_per_group[group as usize] += 1;
+                } else {
+                    delivered.push(packet);
+                }
+            }
+
+            let mut completed = None;
+            for packet in delivered {
+                if let Some(frame) = reassembler.push_packet(packet).unwrap() {
+                    completed = Some(frame);
+                }
+            }
+
+            let recoverable = lost_per_group.iter().all(|&lost| lost <= 3);
+            if recoverable {
+                recoverable_frames += 1;
+                assert_eq!(
+                    completed.expect("frame within parity budget").payload,
+                    payload,
+                    "frame {frame_identifier}"
+                );
+            }
+        }
+
+        assert!(recoverable_frames > 150);
+    }
+
+    #[test]
+    fn keeps_received_chunks_of_evicted_frames_on_request() {
+        let mut reassembler = FrameReassembler::new(1).with_partial_frames();
+
+        reassembler.push_packet(packet(1, 0, 3, b"ab")).unwrap();
+        reassembler.push_packet(packet(1, 2, 3, b"ef")).unwrap();
+        reassembler.push_packet(packet(2, 1, 2, b"zz")).unwrap();
+
+        assert_eq!(
+            reassembler.take_partial_frames(),
+            vec![PartialFrame {
+                frame_identifier: 1,
+                timestamp_nanos: 10,
+                chunks: vec![Some(b"ab".to_vec()), None, Some(b"ef".to_vec())],
+            }]
+        );
+        assert_eq!(reassembler.take_evicted().len(), 1);
+
+        let mut plain = FrameReassembler::new(1);
+        plain.push_packet(packet(1, 0, 3, b"ab")).unwrap();
+        plain.push_packet(packet(2, 1, 2, b"zz")).unwrap();
+        assert!(plain.take_partial_frames().is_empty());
+    }
 }
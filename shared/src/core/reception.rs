@@ -0,0 +1,321 @@
+use crate::core::packet::VideoPacketHeader;
+use crate::core::sequence::SequenceNumber;
+use std::time::{Duration, Instant};
+
+const MAX_DROPOUT: i32 = 3000;
+const MAX_MISORDER: i32 = 100;
+const JITTER_GAIN: f64 = 16.0;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ReceptionStats {
+    pub packets_received: u64,
+    pub packets_expected: u64,
+    pub cumulative_lost: i64,
+    pub reordered: u64,
+    pub duplicates: u64,
+    pub extended_highest_sequence: u64,
+    pub jitter: Duration,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IntervalLoss {
+    pub expected: u64,
+    pub received: u64,
+    pub lost: u64,
+    pub fraction_lost: u8,
+    pub mean_transit_nanos: Option<i64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReceptionTracker {
+    epoch: Instant,
+    base_sequence: u64,
+    highest_sequence: SequenceNumber,
+    cycles: u64,
+    bad_sequence: Option<SequenceNumber>,
+    received: u64,
+    reordered: u64,
+    duplicates: u64,
+    recent: Vec<SequenceNumber>,
+    last_transit: Option<i128>,
+    jitter_nanos: f64,
+    expected_prior: u64,
+    received_prior: u64,
+    interval_transit_nanos: i128,
+    interval_transit_count: u64,
+    initialized: bool,
+}
+
+impl ReceptionTracker {
+    pub fn new(epoch: Instant) -> Self {
+        Self {
+            epoch,
+            base_sequence: 0,
+            highest_sequence: SequenceNumber::new(0),
+            cycles: 0,
+            bad_sequence: None,
+            received: 0,
+            reordered: 0,
+            duplicates: 0,
+            recent: Vec::new(),
+            last_transit: None,
+            jitter_nanos: 0.0,
+            expected_prior: 0,
+            received_prior: 0,
+            interval_transit_nanos: 0,
+            interval_transit_count: 0,
+            initialized: false,
+        }
+    }
+
+    pub fn on_packet(&mut self, header: &VideoPacketHeader, arrival: Instant) {
+        let sequence_number = header.sequence_number;
+        if !self.initialized {
+            self.restart(sequence_number);
+        }
+
+        let delta = self.highest_sequence.distance_to(sequence_number);
+        if delta > 0 && delta < MAX_DROPOUT {
+            if sequence_number.value() < self.highest_sequence.value() {
+                self.cycles += 1 << 32;
+            }
+            self.highest_sequence = sequence_number;
+            self.bad_sequence = None;
+        } else if (-MAX_MISORDER..=0).contains(&delta) {
+            if self.recent.contains(&sequence_number) {
+                self.duplicates += 1;
+                return;
+            }
+            if delta < 0 {
+                self.reordered += 1;
+            }
+        } else if self.bad_sequence == Some(sequence_number) {
+            self.restart(sequence_number);
+        } else {
+            self.bad_sequence = Some(sequence_number.next());
+            return;
+        }
+
+        self.received += 1;
+        self.remember(sequence_number);
+        self.update_jitter(header.timestamp_nanos, arrival);
+    }
+
+    pub fn stats(&self) -> ReceptionStats {
+        let expected = self.expected();
+        ReceptionStats {
+            packets_received: self.received,
+            packets_expected: expected,
+            cumulative_lost: expected as i64 - self.received as i64,
+            reordered: self.reordered,
+            duplicates: self.duplicates,
+            extended_highest_sequence: self.extended_highest_sequence(),
+            jitter: Duration::from_nanos(self.jitter_nanos as u64),
+        }
+    }
+
+    pub fn take_interval(&mut self) -> IntervalLoss {
+        let expected_total = self.expected();
+        let expected = expected_total - self.expected_prior;
+        let received = self.received - self.received_prior;
+        self.expected_prior = expected_total;
+        self.received_prior = self.received;
+
+        let lost = expected.saturating_sub(received);
+        let fraction_lost = (lost << 8)
+            .checked_div(expected)
+            .map_or(0, |fraction| fraction.min(255) as u8);
+        let mean_transit_nanos = self
+            .interval_transit_nanos
+            .checked_div(i128::from(self.interval_transit_count))
+            .map(|mean| mean as i64);
+        self.interval_transit_nanos = 0;
+        self.interval_transit_count = 0;
+        IntervalLoss {
+            expected,
+            received,
+            lost,
+            fraction_lost,
+            mean_transit_nanos,
+        }
+    }
+
+    fn restart(&mut self, sequence_number: SequenceNumber) {
+        self.base_sequence = u64::from(sequence_number.value());
+        self.highest_sequence = sequence_number;
+        self.cycles = 0;
+        self.bad_sequence = None;
+        self.received = 0;
+        self.reordered = 0;
+        self.duplicates = 0;
+        self.recent.clear();
+        self.last_transit = None;
+        self.jitter_nanos = 0.0;
+        self.expected_prior = 0;
+        self.received_prior = 0;
+        self.interval_transit_nanos = 0;
+        self.interval_transit_count = 0;
+        self.initialized = true;
+    }
+
+    fn remember(&mut self, sequence_number: SequenceNumber) {
+        let highest = self.highest_sequence;
+        self.recent
+            .retain(|recent| recent.distance_to(highest) <= MAX_MISORDER);
+        self.recent.push(sequence_number);
+    }
+
+    fn update_jitter(&mut self, timestamp_nanos: u64, arrival: Instant) {
+        let arrival_nanos = arrival.saturating_duration_since(self.epoch).as_nanos() as i128;
+        let transit = arrival_nanos - i128::from(timestamp_nanos);
+        if let Some(last_transit) = self.last_transit {
+            let difference = (transit - last_transit).unsigned_abs() as f64;
+            self.jitter_nanos += (difference - self.jitter_nanos) / JITTER_GAIN;
+        }
+        self.last_transit = Some(transit);
+        self.interval_transit_nanos += transit;
+        self.interval_transit_count += 1;
+    }
+
+    fn extended_highest_sequence(&self) -> u64 {
+        self.cycles + u64::from(self.highest_sequence.value())
+    }
+
+    fn expected(&self) -> u64 {
+        if !self.initialized {
+            return 0;
+        }
+        self.extended_highest_sequence() - self.base_sequence + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IntervalLoss, ReceptionTracker};
+    use crate::core::fec::FecScheme;
+    use crate::core::packet::VideoPacketHeader;
+    use crate::core::sequence::SequenceNumber;
+    use std::time::{Duration, Instant};
+
+    fn header(sequence_number: u32, timestamp_nanos: u64) -> VideoPacketHeader {
+        VideoPacketHeader {
+            sequence_number: SequenceNumber::new(sequence_number),
+            timestamp_nanos,
+            frame_identifier: 0,
+            chunk_index: 0,
+            chunks_total: 1,
+            fec: FecScheme::None,
+        }
+    }
+
+    fn feed(tracker: &mut ReceptionTracker, epoch: Instant, sequence_numbers: &[u32]) {
+        for &sequence_number in sequence_numbers {
+            tracker.on_packet(&header(sequence_number, 0), epoch);
+        }
+    }
+
+    #[test]
+    fn counts_loss_across_wraparound() {
+        let epoch = Instant::now();
+        let mut tracker = ReceptionTracker::new(epoch);
+        feed(&mut tracker, epoch, &[u32::MAX - 2, u32::MAX - 1, 0, 1, 3]);
+
+        let stats = tracker.stats();
+        assert_eq!(stats.packets_expected, 7);
+        assert_eq!(stats.packets_received, 5);
+        assert_eq!(stats.cumulative_lost, 2);
+        assert_eq!(stats.extended_highest_sequence, (1 << 32) + 3);
+    }
+
+    #[test]
+    fn reordered_packet_is_not_lost() {
+        let epoch = Instant::now();
+        let mut tracker = ReceptionTracker::new(epoch);
+        feed(&mut tracker, epoch, &[10, 12, 11, 13, 13]);
+
+        let stats = tracker.stats();
+        assert_eq!(stats.cumulative_lost, 0);
+        assert_eq!(stats.reordered, 1);
+        assert_eq!(stats.duplicates, 1);
+    }
+
+    #[test]
+    fn interval_loss_uses_rtcp_fixed_point() {
+        let epoch = Instant::now();
+        let mut tracker = ReceptionTracker::new(epoch);
+        feed(&mut tracker, epoch, &[0, 1, 3, 4]);
+        assert_eq!(
+            tracker.take_interval(),
+            IntervalLoss {
+                expected: 5,
+                received: 4,
+                lost: 1,
+                fraction_lost: 51,
+                mean_transit_nanos: Some(0),
+            }
+        );
+
+        feed(&mut tracker, epoch, &[5, 6, 7, 8]);
+        assert_eq!(tracker.take_interval().fraction_lost, 0);
+        assert_eq!(tracker.stats().cumulative_lost, 1);
+        assert_eq!(tracker.take_interval().mean_transit_nanos, None);
+    }
+
+    #[test]
+    fn interval_reports_mean_transit() {
+        let epoch = Instant::now();
+        let mut tracker = ReceptionTracker::new(epoch);
+        for (step, delay) in [(0_u32, 2_000_000_u64), (1, 4_000_000)] {
+            let sent = u64::from(step) * 16_000_000;
+            tracker.on_packet(
+                &header(step, sent),
+                epoch + Duration::from_nanos(sent + delay),
+            );
+        }
+
+        assert_eq!(tracker.take_interval().mean_transit_nanos, Some(3_000_000));
+    }
+
+    #[test]
+    fn resynchronises_after_sender_restart() {
+        let epoch = Instant::now();
+        let mut tracker = ReceptionTracker::new(epoch);
+        feed(&mut tracker, epoch, &[100, 101, 90_000]);
+        assert_eq!(tracker.stats().packets_received, 2);
+
+        feed(&mut tracker, epoch, &[90_001, 90_002]);
+        let stats = tracker.stats();
+        assert_eq!(stats.packets_received, 2);
+        assert_eq!(stats.cumulative_lost, 0);
+    }
+
+    #[test]
+    fn constant_transit_has_no_jitter() {
+        let epoch = Instant::now();
+        let mut tracker = ReceptionTracker::new(epoch);
+        for step in 0..50_u32 {
+            let sent = u64::from(step) * 16_000_000;
+            let arrival = epoch + Duration::from_nanos(sent + 2_000_000);
+            tracker.on_packet(&header(step, sent), arrival);
+        }
+
+        assert_eq!(tracker.stats().jitter, Duration::ZERO);
+    }
+
+    #[test]
+    fn jitter_converges_to_mean_deviation() {
+        let epoch = Instant::now();
+        let mut tracker = ReceptionTracker::new(epoch);
+        for step in 0..2000_u32 {
+            let sent = u64::from(step) * 16_000_000;
+            let delay = if step % 2 == 0 { 1_000_000 } else { 3_000_000 };
+            tracker.on_packet(
+                &header(step, sent),
+                epoch + Duration::from_nanos(sent + delay),
+            );
+        }
+
+        let jitter = tracker.stats().jitter.as_secs_f64() * 1000.0;
+        assert!((jitter - 2.0).abs() < 0.01, "jitter {jitter} ms");
+    }
+}
@@ -0,0 +1,221 @@
+pub const LENGTH_PREFIX_BYTES: usize = 2;
+pub const MAX_TOTAL_SHARDS: usize = 255;
+
+const FIELD_POLYNOMIAL: u16 = 0x11d;
+
+struct GaloisTables {
+    exp: [u8; 512],
+    log: [u8; 256],
+}
+
+const TABLES: GaloisTables = build_tables();
+
+const fn build_tables() -> GaloisTables {
+    let mut exp = [0_u8; 512];
+    let mut log = [0_u8; 256];
+    let mut value: u16 = 1;
+    let mut power = 0;
+    while power < 255 {
+        exp[power] = value as u8;
+        log[value as usize] = power as u8;
+        value <<= 1;
+        if value & 0x100 != 0 {
+            value ^= FIELD_POLYNOMIAL;
+        }
+        power += 1;
+    }
+    while power < 512 {
+        exp[power] = exp[power - 255];
+        power += 1;
+    }
+    GaloisTables { exp, log }
+}
+
+fn mul(left: u8, right: u8) -> u8 {
+    if left == 0 || right == 0 {
+        return 0;
+    }
+    TABLES.exp[TABLES.log[left as usize] as usize + TABLES.log[right as usize] as usize]
+}
+
+fn inv(value: u8) -> u8 {
+    debug_assert!(value != 0);
+    TABLES.exp[255 - TABLES.log[value as usize] as usize]
+}
+
+fn cauchy_coefficient(parity_row: usize, data_column: usize, data_count: usize) -> u8 {
+    let x = (data_count + parity_row) as u8;
+    let y = data_column as u8;
+    inv(x ^ y)
+}
+
+fn multiply_accumulate(target: &mut [u8], source: &[u8], coefficient: u8) {
+    if coefficient == 0 {
+        return;
+    }
+    for (target_byte, source_byte) in target.iter_mut().zip(source) {
+        *target_byte ^= mul(coefficient, *source_byte);
+    }
+}
+
+fn augment(shard: &[u8], shard_length: usize) -> Vec<u8> {
+    let mut augmented = vec![0_u8; shard_length];
+    augmented[..LENGTH_PREFIX_BYTES].copy_from_slice(&(shard.len() as u16).to_be_bytes());
+    augmented[LENGTH_PREFIX_BYTES..LENGTH_PREFIX_BYTES + shard.len()].copy_from_slice(shard);
+    augmented
+}
+
+pub fn encode(data: &[&[u8]], parity_count: usize) -> Vec<Vec<u8>> {
+    debug_assert!(data.len() + parity_count <= MAX_TOTAL_SHARDS);
+
+    let longest = data.iter().map(|shard| shard.len()).max().unwrap_or(0);
+    let shard_length = LENGTH_PREFIX_BYTES + longest;
+    let augmented: Vec<Vec<u8>> = data
+        .iter()
+        .map(|shard| augment(shard, shard_length))
+        .collect();
+
+    (0..parity_count)
+        .map(|row| {
+            let mut parity = vec![0_u8; shard_length];
+            for (column, shard) in augmented.iter().enumerate() {
+                multiply_accumulate(
+                    &mut parity,
+                    shard,
+                    cauchy_coefficient(row, column, data.len()),
+                );
+            }
+            parity
+        })
+        .collect()
+}
+
+pub fn reconstruct(data: &[Option<&[u8]>], parity: &[Option<&[u8]>]) -> Option<Vec<Vec<u8>>> {
+    let data_count = data.len();
+    let shard_length = parity.iter().flatten().map(|shard| shard.len()).next()?;
+    if shard_length < LENGTH_PREFIX_BYTES
+        || parity
+            .iter()
+            .flatten()
+            .any(|shard| shard.len() != shard_length)
+        || data
+            .iter()
+            .flatten()
+            .any(|shard| shard.len() + LENGTH_PREFIX_BYTES > shard_length)
+    {
+        return None;
+    }
+
+    let mut rows: Vec<Vec<u8>> = Vec::with_capacity(data_count);
+    let mut values: Vec<Vec<u8>> = Vec::with_capacity(data_count);
+    for (column, shard) in data.iter().enumerate() {
+        if let Some(shard) = shard {
+            let mut row = vec![0_u8; data_count];
+            row[column] = 1;
+            rows.push(row);
+            values.push(augment(shard, shard_length));
+        }
+    }
+    for (parity_row, shard) in parity.iter().enumerate() {
+        if rows.len() == data_count {
+            break;
+        }
+        if let Some(shard) = shard {
+            let row = (0..data_count)
+                .map(|column| cauchy_coefficient(parity_row, column, data_count))
+                .collect();
+            rows.push(row);
+            values.push(shard.to_vec());
+        }
+    }
+    if rows.len() < data_count {
+        return None;
+    }
+
+    for pivot in 0..data_count {
+        let pivot_row = (pivot..data_count).find(|&row| rows[row][pivot] != 0)?;
+        rows.swap(pivot, pivot_row);
+        values.swap(pivot, pivot_row);
+
+        let scale = inv(rows[pivot][pivot]);
+        for entry in rows[pivot].iter_mut() {
+            *entry = mul(*entry, scale);
+        }
+        for byte in values[pivot].iter_mut() {
+            *byte = mul(*byte, scale);
+        }
+
+        for row in 0..data_count {
+            let factor = rows[row][pivot];
+            if row == pivot || factor == 0 {
+                continue;
+            }
+            let (pivot_coefficients, pivot_values) = (rows[pivot].clone(), values[pivot].clone());
+            multiply_accumulate(&mut rows[row], &pivot_coefficients, factor);
+            multiply_accumulate(&mut values[row], &pivot_values, factor);
+        }
+    }
+
+    values
+        .into_iter()
+        .map(|augmented| {
+            let length = u16::from_be_bytes([augmented[0], augmented[1]]) as usize;
+            let payload = augmented.get(LENGTH_PREFIX_BYTES..LENGTH_PREFIX_BYTES + length)?;
+            Some(payload.to_vec())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode, inv, mul, reconstruct};
+
+    #[test]
+    fn field_inverse_is_consistent() {
+        for value in 1..=255_u8 {
+            assert_eq!(mul(value, inv(value)), 1);
+        }
+    }
+
+    #[test]
+    fn reconstructs_any_erasure_pattern_within_parity_budget() {
+        let data: Vec<Vec<u8>> = (0..5_u8)
+            .map(|index| (0..(10 + index)).map(|byte| byte ^ index).collect())
+            .collect();
+        let data_refs: Vec<&[u8]> = data.iter().map(|shard| shard.as_slice()).collect();
+        let parity = encode(&data_refs, 3);
+
+        for mask in 0_u32..(1 << 8) {
+            if mask.count_ones() > 3 {
+                continue;
+            }
+            let lost = |index: usize| mask & (1 << index) != 0;
+            let present_data: Vec<Option<&[u8]>> = data_refs
+                .iter()
+                .enumerate()
+                .map(|(index, shard)| (!lost(index)).then_some(*shard))
+                .collect();
+            let present_parity: Vec<Option<&[u8]>> = parity
+                .iter()
+                .enumerate()
+                .map(|(index, shard)| (!lost(5 + index)).then_some(shard.as_slice()))
+                .collect();
+
+            if present_parity.iter().all(|shard| shard.is_none()) {
+                continue;
+            }
+            let recovered = reconstruct(&present_data, &present_parity).expect("reconstruct");
+            assert_eq!(recovered, data, "mask {mask:08b}");
+        }
+    }
+
+    #[test]
+    fn gives_up_beyond_parity_budget() {
+        let data: Vec<&[u8]> = vec![b"one", b"two", b"three"];
+        let parity = encode(&data, 1);
+        let present_data = [None, None, Some(data[2])];
+        let present_parity = [Some(parity[0].as_slice())];
+
+        assert!(reconstruct(&present_data, &present_parity).is_none());
+    }
+}
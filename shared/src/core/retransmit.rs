@@ -0,0 +1,140 @@
+use crate::core::sequence::SequenceNumber;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone)]
+struct StoredPacket {
+    sequence_number: SequenceNumber,
+    sent_at: Instant,
+    bytes: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RetransmissionBuffer {
+    capacity: usize,
+    max_age: Duration,
+    packets: VecDeque<StoredPacket>,
+    positions: HashMap<SequenceNumber, u64>,
+    front_position: u64,
+}
+
+impl RetransmissionBuffer {
+    pub fn new(capacity: usize, max_age: Duration) -> Self {
+        Self {
+            capacity,
+            max_age,
+            packets: VecDeque::with_capacity(capacity),
+            positions: HashMap::with_capacity(capacity),
+            front_position: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.packets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.packets.is_empty()
+    }
+
+    pub fn insert(&mut self, sequence_number: SequenceNumber, bytes: Vec<u8>, now: Instant) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.packets.len() == self.capacity {
+            self.pop_front();
+        }
+
+        let position = self.front_position + self.packets.len() as u64;
+        self.positions.insert(sequence_number, position);
+        self.packets.push_back(StoredPacket {
+            sequence_number,
+            sent_at: now,
+            bytes,
+        });
+    }
+
+    pub fn get(&mut self, sequence_number: SequenceNumber, now: Instant) -> Option<&[u8]> {
+        self.expire(now);
+        let position = *self.positions.get(&sequence_number)?;
+        let packet = &self.packets[(position - self.front_position) as usize];
+        Some(packet.bytes.as_slice())
+    }
+
+    fn expire(&mut self, now: Instant) {
+        while let Some(packet) = self.packets.front() {
+            if now.saturating_duration_since(packet.sent_at) <= self.max_age {
+                break;
+            }
+            self.pop_front();
+        }
+    }
+
+    fn pop_front(&mut self) {
+        if let Some(packet) = self.packets.pop_front() {
+            if self.positions.get(&packet.sequence_number) == Some(&self.front_position) {
+                self.positions.remove(&packet.sequence_number);
+            }
+            self.front_position += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RetransmissionBuffer;
+    use crate::core::sequence::SequenceNumber;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn returns_stored_packet() {
+        let mut buffer = RetransmissionBuffer::new(4, Duration::from_secs(1));
+        let now = Instant::now();
+        buffer.insert(SequenceNumber::new(5), vec![1, 2, 3], now);
+
+        assert_eq!(
+            buffer.get(SequenceNumber::new(5), now),
+            Some(&[1_u8, 2, 3][..])
+        );
+        assert_eq!(buffer.get(SequenceNumber::new(6), now), None);
+    }
+
+    #[test]
+    fn evicts_oldest_when_full() {
+        let mut buffer = RetransmissionBuffer::new(2, Duration::from_secs(1));
+        let now = Instant::now();
+        for value in 0..3 {
+            buffer.insert(SequenceNumber::new(value), vec![value as u8], now);
+        }
+
+        assert_eq!(buffer.len(), 2);
+        assert_eq!(buffer.get(SequenceNumber::new(0), now), None);
+        assert_eq!(buffer.get(SequenceNumber::new(2), now), Some(&[2_u8][..]));
+    }
+
+    #[test]
+    fn expires_old_packets() {
+        let mut buffer = RetransmissionBuffer::new(8, Duration::from_millis(100));
+        let now = Instant::now();
+        buffer.insert(SequenceNumber::new(1), vec![1], now);
+        buffer.insert(
+            SequenceNumber::new(2),
+            vec![2],
+            now + Duration::from_millis(80),
+        );
+
+        let later = now + Duration::from_millis(150);
+        assert_eq!(buffer.get(SequenceNumber::new(1), later), None);
+        assert_eq!(buffer.get(SequenceNumber::new(2), later), Some(&[2_u8][..]));
+    }
+
+    #[test]
+    fn disabled_buffer_stores_nothing() {
+        let mut buffer = RetransmissionBuffer::new(0, Duration::from_secs(1));
+        let now = Instant::now();
+        buffer.insert(SequenceNumber::new(1), vec![1], now);
+
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.get(SequenceNumber::new(1), now), None);
+    }
+}
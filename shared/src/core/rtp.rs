@@ -0,0 +1,440 @@
+use crate::codec::h264::{parse_annex_b, H264Error, ParameterSets};
+use std::net::SocketAddr;
+
+pub const RTP_VERSION: u8 = 2;
+pub const RTP_HEADER_LENGTH: usize = 12;
+pub const H264_CLOCK_RATE: u32 = 90_000;
+
+const STAP_A: u8 = 24;
+const FU_A: u8 = 28;
+const STAP_A_HEADER_LENGTH: usize = 1;
+const STAP_A_SIZE_LENGTH: usize = 2;
+const FU_A_HEADER_LENGTH: usize = 2;
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RtpHeader {
+    pub marker: bool,
+    pub payload_type: u8,
+    pub sequence_number: u16,
+    pub timestamp: u32,
+    pub ssrc: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RtpError {
+    BufferTooSmall,
+    UnsupportedVersion(u8),
+    MaxPayloadTooSmall,
+    Bitstream(H264Error),
+}
+
+impl From<H264Error> for RtpError {
+    fn from(error: H264Error) -> Self {
+        Self::Bitstream(error)
+    }
+}
+
+impl std::fmt::Display for RtpError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RtpError::BufferTooSmall => write!(formatter, "rtp buffer too small"),
+            RtpError::UnsupportedVersion(version) => {
+                write!(formatter, "unsupported rtp version {version}")
+            }
+            RtpError::MaxPayloadTooSmall => {
+                write!(formatter, "rtp max payload too small for fu-a")
+            }
+            RtpError::Bitstream(error) => write!(formatter, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for RtpError {}
+
+impl RtpHeader {
+    pub fn encode(self, buffer: &mut [u8]) -> Result<(), RtpError> {
+        if buffer.len() < RTP_HEADER_LENGTH {
+            return Err(RtpError::BufferTooSmall);
+        }
+
+        buffer[0] = RTP_VERSION << 6;
+        buffer[1] = (u8::from(self.marker) << 7) | (self.payload_type & 0x7F);
+        buffer[2..4].copy_from_slice(&self.sequence_number.to_be_bytes());
+        buffer[4..8].copy_from_slice(&self.timestamp.to_be_bytes());
+        buffer[8..12].copy_from_slice(&self.ssrc.to_be_bytes());
+
+        Ok(())
+    }
+
+    pub fn decode(buffer: &[u8]) -> Result<Self, RtpError> {
+        if buffer.len() < RTP_HEADER_LENGTH {
+            return Err(RtpError::BufferTooSmall);
+        }
+        let version = buffer[0] >> 6;
+        if version != RTP_VERSION {
+            return Err(RtpError::UnsupportedVersion(version));
+        }
+
+        Ok(Self {
+            marker: buffer[1] & 0x80 != 0,
+            payload_type: buffer[1] & 0x7F,
+            sequence_number: u16::from_be_bytes([buffer[2], buffer[3]]),
+            timestamp: u32::from_be_bytes(buffer[4..8].try_into().unwrap()),
+            ssrc: u32::from_be_bytes(buffer[8..12].try_into().unwrap()),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RtpH264Config {
+    pub payload_type: u8,
+    pub ssrc: u32,
+    pub max_payload_bytes: usize,
+}
+
+impl Default for RtpH264Config {
+    fn default() -> Self {
+        Self {
+            payload_type: 96,
+            ssrc: u32::from_be_bytes(*b"TBDP"),
+            max_payload_bytes: 1200,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RtpH264Packetizer {
+    config: RtpH264Config,
+    next_sequence_number: u16,
+}
+
+impl RtpH264Packetizer {
+    pub fn new(config: RtpH264Config, initial_sequence_number: u16) -> Self {
+        Self {
+            config,
+            next_sequence_number: initial_sequence_number,
+        }
+    }
+
+    pub fn set_max_payload_bytes(&mut self, max_payload_bytes: usize) {
+        self.config.max_payload_bytes = max_payload_bytes;
+    }
+
+    pub fn packetize(
+        &mut self,
+        timestamp: u32,
+        access_unit: &[u8],
+    ) -> Result<Vec<Vec<u8>>, RtpError> {
+        let max_payload_bytes = self.config.max_payload_bytes;
+        if max_payload_bytes <= FU_A_HEADER_LENGTH {
+            return Err(RtpError::MaxPayloadTooSmall);
+        }
+
+        let mut payloads = Vec::new();
+        let mut aggregate: Vec<&[u8]> = Vec::new();
+        for nal_unit in parse_annex_b(access_unit)? {
+            let data = nal_unit.data;
+            if data.len() > max_payload_bytes {
+                flush_aggregate(&mut aggregate, &mut payloads);
+                fragment(data, max_payload_bytes, &mut payloads);
+                continue;
+            }
+
+            let aggregated_bytes = STAP_A_HEADER_LENGTH
+                + aggregate
+                    .iter()
+                    .chain(std::iter::once(&data))
+                    .map(|unit| STAP_A_SIZE_LENGTH + unit.len())
+                    .sum::<usize>();
+            if !aggregate.is_empty() && aggregated_bytes > max_payload_bytes {
+                flush_aggregate(&mut aggregate, &mut payloads);
+            }
+            aggregate.push(data);
+        }
+        flush_aggregate(&mut aggregate, &mut payloads);
+
+        let last = payloads.len().saturating_sub(1);
+        let packets = payloads
+            .into_iter()
+            .enumerate()
+            .map(|(index, payload)| {
+                let header = RtpHeader {
+                    marker: index == last,
+                    payload_type: self.config.payload_type,
+                    sequence_number: self.next_sequence_number,
+                    timestamp,
+                    ssrc: self.config.ssrc,
+                };
+                self.next_sequence_number = self.next_sequence_number.wrapping_add(1);
+
+                let mut packet = vec![0_u8; RTP_HEADER_LENGTH + payload.len()];
+                header
+                    .encode(&mut packet[..RTP_HEADER_LENGTH])
+                    .expect("rtp header buffer is sized correctly");
+                packet[RTP_HEADER_LENGTH..].copy_from_slice(&payload);
+                packet
+            })
+            .collect();
+        Ok(packets)
+    }
+}
+
+pub fn rtp_timestamp(timestamp_nanos: u64) -> u32 {
+    (u128::from(timestamp_nanos) * u128::from(H264_CLOCK_RATE) / 1_000_000_000) as u32
+}
+
+pub fn h264_sdp(
+    destination: SocketAddr,
+    payload_type: u8,
+    parameter_sets: &ParameterSets<'_>,
+) -> String {
+    let address_type = if destination.is_ipv4() { "IP4" } else { "IP6" };
+    let address = destination.ip();
+
+    let mut fmtp = String::from("packetization-mode=1");
+    if let Some(sps) = parameter_sets.sps.first().filter(|sps| sps.len() >= 4) {
+        fmtp.push_str(&format!(
+            ";profile-level-id={:02x}{:02x}{:02x}",
+            sps[1], sps[2], sps[3]
+        ));
+    }
+    if parameter_sets.is_complete() {
+        let sets: Vec<String> = parameter_sets
+            .sps
+            .iter()
+            .chain(parameter_sets.pps.iter())
+            .map(|set| base64_encode(set))
+            .collect();
+        fmtp.push_str(&format!(";sprop-parameter-sets={}", sets.join(",")));
+    }
+
+    [
+        "v=0".to_string(),
+        format!("o=- 0 0 IN {address_type} {address}"),
+        "s=thunderbolt-display".to_string(),
+        format!("c=IN {address_type} {address}"),
+        "t=0 0".to_string(),
+        format!("m=video {} RTP/AVP {payload_type}", destination.port()),
+        format!("a=rtpmap:{payload_type} H264/{H264_CLOCK_RATE}"),
+        format!("a=fmtp:{payload_type} {fmtp}"),
+        String::new(),
+    ]
+    .join("\r\n")
+}
+
+fn flush_aggregate(aggregate: &mut Vec<&[u8]>, payloads: &mut Vec<Vec<u8>>) {
+    match aggregate.as_slice() {
+        [] => {}
+        [single] => payloads.push(single.to_vec()),
+        units => {
+            let nri = units.iter().map(|unit| unit[0] & 0x60).max().unwrap_or(0);
+            let forbidden = units.iter().fold(0, |bits, unit| bits | (unit[0] & 0x80));
+            let mut payload = vec![forbidden | nri | STAP_A];
+            for unit in units {
+                payload.extend_from_slice(&(unit.len() as u16).to_be_bytes());
+                payload.extend_from_slice(unit);
+            }
+            payloads.push(payload);
+        }
+    }
+    aggregate.clear();
+}
+
+fn fragment(nal_unit: &[u8], max_payload_bytes: usize, payloads: &mut Vec<Vec<u8>>) {
+    let header = nal_unit[0];
+    let indicator = (header & 0xE0) | FU_A;
+    let fragment_bytes = max_payload_bytes - FU_A_HEADER_LENGTH;
+    let fragments: Vec<&[u8]> = nal_unit[1..].chunks(fragment_bytes).collect();
+    let last = fragments.len() - 1;
+    for (index, fragment) in fragments.into_iter().enumerate() {
+        let start = if index == 0 { 0x80 } else { 0 };
+        let end = if index == last { 0x40 } else { 0 };
+        let mut payload = Vec::with_capacity(FU_A_HEADER_LENGTH + fragment.len());
+        payload.push(indicator);
+        payload.push(start | end | (header & 0x1F));
+        payload.extend_from_slice(fragment);
+        payloads.push(payload);
+    }
+}
+
+fn base64_encode(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for group in data.chunks(3) {
+        let bytes = [
+            group[0],
+            group.get(1).copied().unwrap_or(0),
+            group.get(2).copied().unwrap_or(0),
+        ];
+        let bits = (u32::from(bytes[0]) << 16) | (u32::from(bytes[1]) << 8) | u32::from(bytes[2]);
+        for index in 0..4 {
+            if index <= group.len() {
+                let sextet = (bits >> (18 - 6 * index)) & 0x3F;
+                encoded.push(char::from(BASE64_ALPHABET[sextet as usize]));
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        base64_encode, h264_sdp, rtp_timestamp, RtpError, RtpH264Config, RtpH264Packetizer,
+        RtpHeader, RTP_HEADER_LENGTH,
+    };
+    use crate::codec::h264::{parse_annex_b, ParameterSets};
+
+    const SPS: [u8; 8] = [0x67, 0x42, 0xC0, 0x1E, 0xDA, 0x05, 0x07, 0xE4];
+    const PPS: [u8; 4] = [0x68, 0xCE, 0x3C, 0x80];
+
+    fn packetizer(max_payload_bytes: usize) -> RtpH264Packetizer {
+        RtpH264Packetizer::new(
+            RtpH264Config {
+                payload_type: 96,
+                ssrc: 0x1122_3344,
+                max_payload_bytes,
+            },
+            0xFFFE,
+        )
+    }
+
+    fn annex_b(nal_units: &[&[u8]]) -> Vec<u8> {
+        nal_units
+            .iter()
+            .flat_map(|unit| [&[0, 0, 0, 1][..], unit].concat())
+            .collect()
+    }
+
+    #[test]
+    fn header_matches_rfc_3550_layout() {
+        let header = RtpHeader {
+            marker: true,
+            payload_type: 96,
+            sequence_number: 0x1234,
+            timestamp: 0xAABB_CCDD,
+            ssrc: 0x1122_3344,
+        };
+        let mut buffer = [0_u8; RTP_HEADER_LENGTH];
+        header.encode(&mut buffer).expect("encode");
+
+        assert_eq!(
+            buffer,
+            [0x80, 0xE0, 0x12, 0x34, 0xAA, 0xBB, 0xCC, 0xDD, 0x11, 0x22, 0x33, 0x44]
+        );
+        assert_eq!(RtpHeader::decode(&buffer), Ok(header));
+        assert_eq!(
+            RtpHeader::decode(&[0x40; RTP_HEADER_LENGTH]),
+            Err(RtpError::UnsupportedVersion(1))
+        );
+    }
+
+    #[test]
+    fn sends_lone_nal_unit_as_single_nal_packet() {
+        let slice = [0x41, 0x9A, 0x02, 0x03];
+        let packets = packetizer(1200)
+            .packetize(90_000, &annex_b(&[&slice]))
+            .expect("packetize");
+
+        assert_eq!(
+            packets,
+            vec![vec![
+                0x80, 0xE0, 0xFF, 0xFE, 0x00, 0x01, 0x5F, 0x90, 0x11, 0x22, 0x33, 0x44, 0x41, 0x9A,
+                0x02, 0x03,
+            ]]
+        );
+    }
+
+    #[test]
+    fn aggregates_small_nal_units_into_stap_a() {
+        let slice = [0x65, 0x88, 0x84];
+        let packets = packetizer(1200)
+            .packetize(0, &annex_b(&[&SPS, &PPS, &slice]))
+            .expect("packetize");
+
+        assert_eq!(packets.len(), 1);
+        assert_eq!(
+            &packets[0][RTP_HEADER_LENGTH..],
+            &[
+                0x78, 0x00, 0x08, 0x67, 0x42, 0xC0, 0x1E, 0xDA, 0x05, 0x07, 0xE4, 0x00, 0x04, 0x68,
+                0xCE, 0x3C, 0x80, 0x00, 0x03, 0x65, 0x88, 0x84,
+            ]
+        );
+        assert_eq!(packets[0][1] & 0x80, 0x80);
+    }
+
+    #[test]
+    fn fragments_large_nal_unit_into_fu_a() {
+        let slice = [0x65, 1, 2, 3, 4, 5, 6, 7];
+        let mut packetizer = packetizer(5);
+        let packets = packetizer
+            .packetize(0, &annex_b(&[&PPS, &slice]))
+            .expect("packetize");
+
+        let payloads: Vec<&[u8]> = packets
+            .iter()
+            .map(|packet| &packet[RTP_HEADER_LENGTH..])
+            .collect();
+        assert_eq!(
+            payloads,
+            vec![
+                &[0x68, 0xCE, 0x3C, 0x80][..],
+                &[0x7C, 0x85, 1, 2, 3][..],
+                &[0x7C, 0x05, 4, 5, 6][..],
+                &[0x7C, 0x45, 7][..],
+            ]
+        );
+        let markers: Vec<bool> = packets.iter().map(|packet| packet[1] & 0x80 != 0).collect();
+        assert_eq!(markers, vec![false, false, false, true]);
+        let sequence_numbers: Vec<u16> = packets
+            .iter()
+            .map(|packet| u16::from_be_bytes([packet[2], packet[3]]))
+            .collect();
+        assert_eq!(sequence_numbers, vec![0xFFFE, 0xFFFF, 0, 1]);
+    }
+
+    #[test]
+    fn rejects_payload_limit_without_room_for_fu_a() {
+        assert_eq!(
+            packetizer(2).packetize(0, &annex_b(&[&PPS])),
+            Err(RtpError::MaxPayloadTooSmall)
+        );
+    }
+
+    #[test]
+    fn converts_nanoseconds_to_90khz_clock() {
+        assert_eq!(rtp_timestamp(1_000_000_000), 90_000);
+        assert_eq!(rtp_timestamp(16_666_667), 1500);
+    }
+
+    #[test]
+    fn encodes_base64_with_padding() {
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+        assert_eq!(base64_encode(&PPS), "aM48gA==");
+    }
+
+    #[test]
+    fn generates_sdp_with_parameter_sets() {
+        let access_unit = annex_b(&[&SPS, &PPS]);
+        let nal_units = parse_annex_b(&access_unit).expect("parse");
+        let parameter_sets = ParameterSets::extract(&nal_units);
+
+        let sdp = h264_sdp("192.168.0.2:5004".parse().unwrap(), 96, &parameter_sets);
+        assert_eq!(
+            sdp,
+            "v=0\r\n\
+             o=- 0 0 IN IP4 192.168.0.2\r\n\
+             s=thunderbolt-display\r\n\
+             c=IN IP4 192.168.0.2\r\n\
+             t=0 0\r\n\
+             m=video 5004 RTP/AVP 96\r\n\
+             a=rtpmap:96 H264/90000\r\n\
+             a=fmtp:96 packetization-mode=1;profile-level-id=42c01e;sprop-parameter-sets=Z0LAHtoFB+Q=,aM48gA==\r\n"
+        );
+    }
+}
@@ -1,4 +1,8 @@
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+use std::cmp::Ordering;
+
+const HALF_RANGE: u32 = 1 << 31;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct SequenceNumber(u32);
 
 impl SequenceNumber {
@@ -13,11 +17,39 @@ impl SequenceNumber {
     pub fn next(self) -> Self {
         Self(self.0.wrapping_add(1))
     }
+
+    pub fn wrapping_add(self, offset: u32) -> Self {
+        Self(self.0.wrapping_add(offset))
+    }
+
+    pub fn distance_to(self, other: SequenceNumber) -> i32 {
+        serial_distance(self.0, other.0)
+    }
+}
+
+impl PartialOrd for SequenceNumber {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        serial_partial_cmp(self.0, other.0)
+    }
+}
+
+pub fn serial_distance(from: u32, to: u32) -> i32 {
+    to.wrapping_sub(from) as i32
+}
+
+pub fn serial_partial_cmp(left: u32, right: u32) -> Option<Ordering> {
+    match left.wrapping_sub(right) {
+        0 => Some(Ordering::Equal),
+        HALF_RANGE => None,
+        difference if difference < HALF_RANGE => Some(Ordering::Greater),
+        _ => Some(Ordering::Less),
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::SequenceNumber;
+    use super::{serial_distance, serial_partial_cmp, SequenceNumber};
+    use std::cmp::Ordering;
 
     #[test]
     fn next_wraps_on_overflow() {
@@ -25,4 +57,46 @@ mod tests {
         let next = start.next();
         assert_eq!(next.value(), 0);
     }
+
+    #[test]
+    fn ordering_survives_wraparound() {
+        let before = SequenceNumber::new(u32::MAX - 1);
+        let after = before.wrapping_add(3);
+
+        assert_eq!(after.value(), 1);
+        assert!(before < after);
+        assert!(after > before);
+        assert_eq!(before.distance_to(after), 3);
+        assert_eq!(after.distance_to(before), -3);
+    }
+
+    #[test]
+    fn half_range_apart_is_unordered() {
+        assert_eq!(serial_partial_cmp(0, 1 << 31), None);
+        assert_eq!(serial_partial_cmp(1 << 31, 0), None);
+        assert_eq!(serial_partial_cmp(0, (1 << 31) - 1), Some(Ordering::Less));
+        assert_eq!(
+            serial_partial_cmp(0, (1 << 31) + 1),
+            Some(Ordering::Greater)
+        );
+    }
+
+    #[test]
+    fn frame_identifiers_compare_across_wrap() {
+        assert_eq!(serial_distance(0, u32::MAX), -1);
+        assert_eq!(serial_distance(u32::MAX - 5, 5), 11);
+        assert_eq!(serial_distance(7, 7), 0);
+        assert_eq!(serial_distance(u32::MAX, 2), 3);
+    }
+
+    #[test]
+    fn sorts_identifiers_spanning_half_range_from_a_reference() {
+        let newest = (1 << 31) + 2;
+        let mut identifiers = vec![newest, 2, 1 << 31, 3, (1 << 31) + 1];
+        identifiers.sort_by_key(|&identifier| serial_distance(newest, identifier));
+        assert_eq!(
+            identifiers,
+            vec![2, 3, 1 << 31, (1 << 31) + 1, (1 << 31) + 2]
+        );
+    }
 }
@@ -0,0 +1,265 @@
+use crate::transport::{PacketReceiver, PacketSender, TransportError};
+use std::time::Duration;
+
+const WRITABLE_WAIT: Duration = Duration::from_millis(5);
+const MAX_SEND_STALLS: u32 = 40;
+
+#[derive(Debug, Clone)]
+pub struct ReceiveBatch {
+    storage: Vec<u8>,
+    capacity: usize,
+    slot_bytes: usize,
+    lengths: Vec<usize>,
+}
+
+impl ReceiveBatch {
+    pub fn new(capacity: usize, slot_bytes: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            storage: vec![0_u8; capacity * slot_bytes],
+            capacity,
+            slot_bytes,
+            lengths: Vec::with_capacity(capacity),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn slot_bytes(&self) -> usize {
+        self.slot_bytes
+    }
+
+    pub fn len(&self) -> usize {
+        self.lengths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lengths.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.lengths.clear();
+    }
+
+    pub fn get(&self, index: usize) -> Option<&[u8]> {
+        let length = *self.lengths.get(index)?;
+        let start = index * self.slot_bytes;
+        Some(&self.storage[start..start + length])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &[u8]> {
+        (0..self.len()).filter_map(|index| self.get(index))
+    }
+
+    pub fn spare_slots_mut(&mut self) -> impl Iterator<Item = &mut [u8]> {
+        let filled = self.lengths.len() * self.slot_bytes;
+        self.storage[filled..].chunks_exact_mut(self.slot_bytes.max(1))
+    }
+
+    pub fn push_received(&mut self, length: usize) {
+        debug_assert!(self.lengths.len() < self.capacity);
+        self.lengths.push(length.min(self.slot_bytes));
+    }
+}
+
+pub fn send_individually<S: PacketSender + ?Sized>(
+    sender: &mut S,
+    packets: &[&[u8]],
+) -> Result<usize, TransportError> {
+    for (sent, packet) in packets.iter().enumerate() {
+        match sender.send(packet) {
+            Ok(_) => {}
+            Err(error) if sent == 0 => return Err(error),
+            Err(_) => return Ok(sent),
+        }
+    }
+    Ok(packets.len())
+}
+
+pub fn send_all<S: PacketSender + ?Sized>(
+    sender: &mut S,
+    packets: &[&[u8]],
+) -> Result<usize, TransportError> {
+    let mut remaining = packets;
+    let mut dropped = 0;
+    let mut stalls = 0;
+    while !remaining.is_empty() {
+        match sender.send_batch(remaining) {
+            Ok(sent) if sent > 0 => {
+                remaining = &remaining[sent.min(remaining.len())..];
+                stalls = 0;
+                continue;
+            }
+            Ok(_) => {}
+            Err(error) if error.is_transient() => {}
+            Err(error) => return Err(error),
+        }
+        if stalls == MAX_SEND_STALLS {
+            remaining = &remaining[1..];
+            dropped += 1;
+            stalls = 0;
+        } else {
+            stalls += 1;
+            sender.wait_writable(WRITABLE_WAIT)?;
+        }
+    }
+    Ok(dropped)
+}
+
+pub fn receive_individually<R: PacketReceiver + ?Sized>(
+    receiver: &mut R,
+    batch: &mut ReceiveBatch,
+) -> Result<usize, TransportError> {
+    batch.clear();
+    let Some(slot) = batch.spare_slots_mut().next() else {
+        return Ok(0);
+    };
+    let received = receiver.receive(slot)?;
+    batch.push_received(received);
+    Ok(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{send_all, send_individually, ReceiveBatch, MAX_SEND_STALLS};
+    use crate::transport::{PacketReceiver, PacketSender, TransportError};
+    use std::collections::VecDeque;
+    use std::time::Duration;
+
+    struct FlakySender {
+        sent: Vec<Vec<u8>>,
+        fail_at: usize,
+    }
+
+    impl PacketSender for FlakySender {
+        fn send(&mut self, packet: &[u8]) -> Result<usize, TransportError> {
+            if self.sent.len() == self.fail_at {
+                return Err(TransportError::Io(std::io::ErrorKind::WouldBlock.into()));
+            }
+            self.sent.push(packet.to_vec());
+            Ok(packet.len())
+        }
+    }
+
+    #[test]
+    fn individual_sends_report_partial_progress() {
+        let packets: [&[u8]; 3] = [b"a", b"b", b"c"];
+        let mut sender = FlakySender {
+            sent: Vec::new(),
+            fail_at: 2,
+        };
+        assert_eq!(send_individually(&mut sender, &packets).expect("send"), 2);
+        assert!(send_individually(&mut sender, &packets[2..]).is_err());
+
+        sender.fail_at = usize::MAX;
+        assert_eq!(sender.send_batch(&packets).expect("send"), 3);
+        assert_eq!(sender.sent.len(), 5);
+    }
+
+    struct StallingSender {
+        sent: Vec<Vec<u8>>,
+        stalls_left: u32,
+        waits: u32,
+    }
+
+    impl PacketSender for StallingSender {
+        fn send(&mut self, packet: &[u8]) -> Result<usize, TransportError> {
+            if self.stalls_left > 0 {
+                self.stalls_left -= 1;
+                return Err(TransportError::Io(std::io::ErrorKind::WouldBlock.into()));
+            }
+            self.sent.push(packet.to_vec());
+            Ok(packet.len())
+        }
+
+        fn send_batch(&mut self, packets: &[&[u8]]) -> Result<usize, TransportError> {
+            if self.stalls_left > 0 && self.waits % 2 == 1 {
+                self.stalls_left -= 1;
+                return Ok(0);
+            }
+            send_individually(self, &packets[..packets.len().min(2)])
+        }
+
+        fn wait_writable(&mut self, _timeout: Duration) -> Result<(), TransportError> {
+            self.waits += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn send_all_retries_the_same_packets_until_the_sender_is_writable() {
+        let packets: [&[u8]; 5] = [b"a", b"b", b"c", b"d", b"e"];
+        let mut sender = StallingSender {
+            sent: Vec::new(),
+            stalls_left: 6,
+            waits: 0,
+        };
+
+        assert_eq!(send_all(&mut sender, &packets).expect("send"), 0);
+        assert_eq!(sender.sent, packets.map(<[u8]>::to_vec).to_vec());
+        assert_eq!(sender.waits, 6);
+    }
+
+    #[test]
+    fn send_all_counts_packets_dropped_after_repeated_stalls() {
+        let packets: [&[u8]; 2] = [b"a", b"b"];
+        let mut sender = StallingSender {
+            sent: Vec::new(),
+            stalls_left: u32::MAX,
+            waits: 0,
+        };
+
+        assert_eq!(send_all(&mut sender, &packets).expect("send"), 2);
+        assert!(sender.sent.is_empty());
+        assert_eq!(sender.waits, 2 * MAX_SEND_STALLS);
+    }
+
+    struct QueueReceiver {
+        datagrams: VecDeque<Vec<u8>>,
+    }
+
+    impl PacketReceiver for QueueReceiver {
+        fn receive(&mut self, buffer: &mut [u8]) -> Result<usize, TransportError> {
+            let datagram = self
+                .datagrams
+                .pop_front()
+                .ok_or(TransportError::Io(std::io::ErrorKind::WouldBlock.into()))?;
+            let length = datagram.len().min(buffer.len());
+            buffer[..length].copy_from_slice(&datagram[..length]);
+            Ok(length)
+        }
+    }
+
+    #[test]
+    fn default_batch_receive_takes_one_datagram_per_call() {
+        let mut receiver = QueueReceiver {
+            datagrams: VecDeque::from(vec![b"first".to_vec(), b"second".to_vec()]),
+        };
+        let mut batch = ReceiveBatch::new(8, 4);
+
+        assert_eq!(receiver.receive_batch(&mut batch).expect("receive"), 1);
+        assert_eq!(batch.get(0), Some(&b"firs"[..]));
+        assert_eq!(receiver.receive_batch(&mut batch).expect("receive"), 1);
+        assert_eq!(batch.iter().collect::<Vec<_>>(), vec![&b"seco"[..]]);
+        assert!(receiver.receive_batch(&mut batch).is_err());
+    }
+
+    #[test]
+    fn batch_slots_fill_in_order() {
+        let mut batch = ReceiveBatch::new(3, 2);
+        assert_eq!(batch.capacity(), 3);
+        for (index, slot) in batch.spare_slots_mut().enumerate().take(2) {
+            slot.fill(index as u8 + 1);
+        }
+        batch.push_received(2);
+        batch.push_received(1);
+
+        assert_eq!(batch.len(), 2);
+        assert_eq!(batch.get(0), Some(&[1, 1][..]));
+        assert_eq!(batch.get(1), Some(&[2][..]));
+        assert_eq!(batch.get(2), None);
+        assert_eq!(batch.spare_slots_mut().count(), 1);
+    }
+}
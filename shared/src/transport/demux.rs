@@ -0,0 +1,301 @@
+use crate::core::control::{ControlError, ControlMessage};
+use crate::core::envelope::{EnvelopeError, EnvelopeHeader, MessageKind};
+use crate::core::healthcheck::{HealthcheckError, HealthcheckPacket};
+use crate::core::packet::VideoPacket;
+use crate::core::packet_codec::{decode_packet, PacketCodecError};
+use crate::transport::batch::ReceiveBatch;
+use crate::transport::{PacketReceiver, PacketSender, TransportError};
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    Video(VideoPacket),
+    Healthcheck(HealthcheckPacket),
+    Control(ControlMessage),
+}
+
+#[derive(Debug)]
+pub enum DemuxError {
+    Transport(TransportError),
+    Envelope(EnvelopeError),
+    Video(PacketCodecError),
+    Healthcheck(HealthcheckError),
+    Control(ControlError),
+}
+
+impl From<TransportError> for DemuxError {
+    fn from(error: TransportError) -> Self {
+        Self::Transport(error)
+    }
+}
+
+impl std::fmt::Display for DemuxError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DemuxError::Transport(error) => write!(formatter, "{error}"),
+            DemuxError::Envelope(error) => write!(formatter, "{error}"),
+            DemuxError::Video(error) => write!(formatter, "invalid video packet: {error}"),
+            DemuxError::Healthcheck(error) => {
+                write!(formatter, "invalid healthcheck packet: {error:?}")
+            }
+            DemuxError::Control(error) => write!(formatter, "invalid control message: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for DemuxError {}
+
+impl DemuxError {
+    pub fn is_version_mismatch(&self) -> bool {
+        matches!(
+            self,
+            DemuxError::Envelope(EnvelopeError::UnsupportedVersion(_))
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DemuxStats {
+    pub video: u64,
+    pub healthcheck: u64,
+    pub control: u64,
+    pub rejected: u64,
+}
+
+pub fn demultiplex(datagram: &[u8]) -> Result<Message, DemuxError> {
+    let envelope = EnvelopeHeader::decode(datagram).map_err(DemuxError::Envelope)?;
+    match envelope.kind {
+        MessageKind::Video => decode_packet(datagram)
+            .map(Message::Video)
+            .map_err(DemuxError::Video),
+        MessageKind::Healthcheck => HealthcheckPacket::decode(datagram)
+            .map(Message::Healthcheck)
+            .map_err(DemuxError::Healthcheck),
+        MessageKind::Control => ControlMessage::decode(datagram)
+            .map(Message::Control)
+            .map_err(DemuxError::Control),
+    }
+}
+
+pub struct Demultiplexer<T> {
+    transport: T,
+    batch: ReceiveBatch,
+    next_in_batch: usize,
+    max_datagram_bytes: usize,
+    stats: DemuxStats,
+}
+
+impl<T> Demultiplexer<T> {
+    pub fn new(transport: T, max_datagram_bytes: usize) -> Self {
+        Self {
+            transport,
+            batch: ReceiveBatch::new(1, max_datagram_bytes),
+            next_in_batch: 0,
+            max_datagram_bytes,
+            stats: DemuxStats::default(),
+        }
+    }
+
+    pub fn with_receive_batch(mut self, datagrams: usize) -> Self {
+        self.batch = ReceiveBatch::new(datagrams, self.max_datagram_bytes);
+        self.next_in_batch = 0;
+        self
+    }
+
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    pub fn transport_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    pub fn stats(&self) -> DemuxStats {
+        self.stats
+    }
+
+    pub fn max_datagram_bytes(&self) -> usize {
+        self.max_datagram_bytes
+    }
+
+    pub fn set_max_datagram_bytes(&mut self, max_datagram_bytes: usize) {
+        self.max_datagram_bytes = max_datagram_bytes;
+    }
+}
+
+impl<T: PacketReceiver> Demultiplexer<T> {
+    pub fn receive(&mut self) -> Result<Message, DemuxError> {
+        if self.next_in_batch >= self.batch.len() {
+            if self.batch.slot_bytes() != self.max_datagram_bytes {
+                self.batch = ReceiveBatch::new(self.batch.capacity(), self.max_datagram_bytes);
+            }
+            self.next_in_batch = 0;
+            self.transport.receive_batch(&mut self.batch)?;
+        }
+        let datagram = self.batch.get(self.next_in_batch).unwrap_or_default();
+        self.next_in_batch += 1;
+        match demultiplex(datagram) {
+            Ok(message) => {
+                match message {
+                    Message::Video(_) => self.stats.video += 1,
+                    Message::Healthcheck(_) => self.stats.healthcheck += 1,
+                    Message::Control(_) => self.stats.control += 1,
+                }
+                Ok(message)
+            }
+            Err(error) => {
+                self.stats.rejected += 1;
+                Err(error)
+            }
+        }
+    }
+}
+
+impl<T: PacketSender> PacketSender for Demultiplexer<T> {
+    fn send(&mut self, packet: &[u8]) -> Result<usize, TransportError> {
+        self.transport.send(packet)
+    }
+
+    fn send_batch(&mut self, packets: &[&[u8]]) -> Result<usize, TransportError> {
+        self.transport.send_batch(packets)
+    }
+
+    fn wait_writable(&mut self, timeout: Duration) -> Result<(), TransportError> {
+        self.transport.wait_writable(timeout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{demultiplex, Demultiplexer, DemuxError, DemuxStats, Message};
+    use crate::core::control::ControlMessage;
+    use crate::core::fec::FecScheme;
+    use crate::core::healthcheck::{HealthcheckKind, HealthcheckPacket};
+    use crate::core::packet::{VideoPacket, VideoPacketHeader};
+    use crate::core::packet_codec::encode_packet;
+    use crate::core::sequence::SequenceNumber;
+    use crate::transport::{PacketReceiver, TransportError};
+    use std::collections::VecDeque;
+
+    struct ScriptedReceiver {
+        datagrams: VecDeque<Vec<u8>>,
+    }
+
+    impl PacketReceiver for ScriptedReceiver {
+        fn receive(&mut self, buffer: &mut [u8]) -> Result<usize, TransportError> {
+            let datagram = self
+                .datagrams
+                .pop_front()
+                .ok_or_else(|| TransportError::Io(std::io::ErrorKind::WouldBlock.into()))?;
+            buffer[..datagram.len()].copy_from_slice(&datagram);
+            Ok(datagram.len())
+        }
+    }
+
+    #[test]
+    fn routes_video_packets() {
+        let packet = VideoPacket {
+            header: VideoPacketHeader {
+                sequence_number: SequenceNumber::new(3),
+                timestamp_nanos: 10,
+                frame_identifier: 1,
+                chunk_index: 0,
+                chunks_total: 1,
+                fec: FecScheme::None,
+            },
+            payload: b"frame".to_vec(),
+        };
+
+        let message = demultiplex(&encode_packet(&packet)).unwrap();
+        assert_eq!(message, Message::Video(packet));
+    }
+
+    #[test]
+    fn routes_healthcheck_packets() {
+        let ping = HealthcheckPacket {
+            kind: HealthcheckKind::Ping,
+            timestamp_nanos: 99,
+        };
+
+        let message = demultiplex(&ping.encode()).unwrap();
+        assert_eq!(message, Message::Healthcheck(ping));
+    }
+
+    #[test]
+    fn routes_control_messages() {
+        let nack = ControlMessage::Nack {
+            sequence_numbers: vec![SequenceNumber::new(4)],
+        };
+
+        let message = demultiplex(&nack.encode()).unwrap();
+        assert_eq!(message, Message::Control(nack));
+    }
+
+    #[test]
+    fn flags_version_mismatch() {
+        let mut datagram = HealthcheckPacket {
+            kind: HealthcheckKind::Pong,
+            timestamp_nanos: 0,
+        }
+        .encode();
+        datagram[4] = 0xFF;
+
+        let error = demultiplex(&datagram).unwrap_err();
+        assert!(error.is_version_mismatch());
+    }
+
+    #[test]
+    fn rejects_foreign_datagrams() {
+        let error = demultiplex(b"GET / HTTP/1.1").unwrap_err();
+        assert!(matches!(error, DemuxError::Envelope(_)));
+        assert!(!error.is_version_mismatch());
+    }
+
+    #[test]
+    fn demultiplexer_counts_each_kind() {
+        let ping = HealthcheckPacket {
+            kind: HealthcheckKind::Ping,
+            timestamp_nanos: 1,
+        };
+        let video = VideoPacket {
+            header: VideoPacketHeader {
+                sequence_number: SequenceNumber::new(0),
+                timestamp_nanos: 1,
+                frame_identifier: 0,
+                chunk_index: 0,
+                chunks_total: 1,
+                fec: FecScheme::None,
+            },
+            payload: vec![7_u8; 16],
+        };
+        let receiver = ScriptedReceiver {
+            datagrams: VecDeque::from(vec![
+                encode_packet(&video),
+                ping.encode().to_vec(),
+                b"noise".to_vec(),
+            ]),
+        };
+
+        let mut demultiplexer = Demultiplexer::new(receiver, 2048);
+        assert_eq!(demultiplexer.receive().unwrap(), Message::Video(video));
+        assert_eq!(demultiplexer.receive().unwrap(), Message::Healthcheck(ping));
+        assert!(matches!(
+            demultiplexer.receive(),
+            Err(DemuxError::Envelope(_))
+        ));
+        assert!(matches!(
+            demultiplexer.receive(),
+            Err(DemuxError::Transport(_))
+        ));
+
+        assert_eq!(
+            demultiplexer.stats(),
+            DemuxStats {
+                video: 1,
+                healthcheck: 1,
+                control: 0,
+                rejected: 1,
+            }
+        );
+    }
+}
@@ -0,0 +1,601 @@
+use crate::transport::{PacketReceiver, PacketSender, TransportError};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+const NANOS_PER_SECOND: f64 = 1_000_000_000.0;
+const DEFAULT_SEED: u64 = 0x5EED_F00D;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LossModel {
+    None,
+    Random {
+        probability: f64,
+    },
+    GilbertElliott {
+        good_to_bad: f64,
+        bad_to_good: f64,
+        good_loss: f64,
+        bad_loss: f64,
+    },
+}
+
+impl LossModel {
+    pub fn bursty(loss_rate: f64, mean_burst_packets: f64) -> Self {
+        let loss_rate = loss_rate.clamp(0.0, 0.99);
+        let bad_to_good = 1.0 / mean_burst_packets.max(1.0);
+        LossModel::GilbertElliott {
+            good_to_bad: loss_rate * bad_to_good / (1.0 - loss_rate),
+            bad_to_good,
+            good_loss: 0.0,
+            bad_loss: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImpairmentConfig {
+    pub seed: u64,
+    pub loss: LossModel,
+    pub delay: Duration,
+    pub jitter: Duration,
+    pub reorder_probability: f64,
+    pub reorder_delay: Duration,
+    pub duplicate_probability: f64,
+    pub bandwidth_bits_per_second: Option<u64>,
+    pub queue_limit_bytes: usize,
+}
+
+impl Default for ImpairmentConfig {
+    fn default() -> Self {
+        Self {
+            seed: DEFAULT_SEED,
+            loss: LossModel::None,
+            delay: Duration::ZERO,
+            jitter: Duration::ZERO,
+            reorder_probability: 0.0,
+            reorder_delay: Duration::from_millis(5),
+            duplicate_probability: 0.0,
+            bandwidth_bits_per_second: None,
+            queue_limit_bytes: 256 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImpairmentStats {
+    pub submitted: u64,
+    pub delivered: u64,
+    pub lost: u64,
+    pub queue_dropped: u64,
+    pub duplicated: u64,
+    pub reordered: u64,
+}
+
+#[derive(Debug, Clone)]
+struct ScheduledPacket {
+    due: Instant,
+    bytes: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Impairment {
+    config: ImpairmentConfig,
+    random_state: u64,
+    bursting: bool,
+    link_free_at: Option<Instant>,
+    queue: VecDeque<ScheduledPacket>,
+    stats: ImpairmentStats,
+}
+
+impl Impairment {
+    pub fn new(config: ImpairmentConfig) -> Self {
+        Self {
+            config,
+            random_state: config.seed.max(1),
+            bursting: false,
+            link_free_at: None,
+            queue: VecDeque::new(),
+            stats: ImpairmentStats::default(),
+        }
+    }
+
+    pub fn submit(&mut self, packet: &[u8], now: Instant) {
+        self.stats.submitted += 1;
+        if self.loses_packet() {
+            self.stats.lost += 1;
+            return;
+        }
+
+        let copies = if self.chance(self.config.duplicate_probability) {
+            self.stats.duplicated += 1;
+            2
+        } else {
+            1
+        };
+        for _ in 0..copies {
+            let Some(departure) = self.serialize(packet.len(), now) else {
+                self.stats.queue_dropped += 1;
+                continue;
+            };
+            let mut due = departure + self.config.delay;
+            if !self.config.jitter.is_zero() {
+                let sample = self.next_f64() * 2.0 - 1.0;
+                let offset = self.config.jitter.mul_f64(sample.abs());
+                due = if sample >= 0.0 {
+                    due + offset
+                } else {
+                    due.checked_sub(offset).unwrap_or(departure).max(departure)
+                };
+            }
+            if self.chance(self.config.reorder_probability) {
+                self.stats.reordered += 1;
+                due += self.config.reorder_delay;
+            }
+            self.schedule(due, packet.to_vec());
+        }
+    }
+
+    pub fn pop_due(&mut self, now: Instant) -> Option<Vec<u8>> {
+        if self.queue.front()?.due > now {
+            return None;
+        }
+        self.stats.delivered += 1;
+        self.queue.pop_front().map(|scheduled| scheduled.bytes)
+    }
+
+    pub fn next_due(&self) -> Option<Instant> {
+        self.queue.front().map(|scheduled| scheduled.due)
+    }
+
+    pub fn queued_packets(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn stats(&self) -> ImpairmentStats {
+        self.stats
+    }
+
+    fn loses_packet(&mut self) -> bool {
+        match self.config.loss {
+            LossModel::None => false,
+            LossModel::Random { probability } => self.chance(probability),
+            LossModel::GilbertElliott {
+                good_to_bad,
+                bad_to_good,
+                good_loss,
+                bad_loss,
+            } => {
+                let transition = if self.bursting {
+                    bad_to_good
+                } else {
+                    good_to_bad
+                };
+                if self.chance(transition) {
+                    self.bursting = !self.bursting;
+                }
+                let loss = if self.bursting { bad_loss } else { good_loss };
+                self.chance(loss)
+            }
+        }
+    }
+
+    fn serialize(&mut self, bytes: usize, now: Instant) -> Option<Instant> {
+        let Some(bits_per_second) = self.config.bandwidth_bits_per_second else {
+            return Some(now);
+        };
+        let bytes_per_second = bits_per_second as f64 / 8.0;
+        let start = self.link_free_at.map_or(now, |free_at| free_at.max(now));
+        let backlog_bytes = start.duration_since(now).as_secs_f64() * bytes_per_second;
+        if backlog_bytes + bytes as f64 > self.config.queue_limit_bytes as f64 {
+            return None;
+        }
+        let transmission_nanos = (bytes as f64 * NANOS_PER_SECOND / bytes_per_second).ceil();
+        let departure = start + Duration::from_nanos(transmission_nanos as u64);
+        self.link_free_at = Some(departure);
+        Some(departure)
+    }
+
+    fn schedule(&mut self, due: Instant, bytes: Vec<u8>) {
+        let position = self.queue.partition_point(|scheduled| scheduled.due <= due);
+        self.queue.insert(position, ScheduledPacket { due, bytes });
+    }
+
+    fn chance(&mut self, probability: f64) -> bool {
+        probability > 0.0 && self.next_f64() < probability
+    }
+
+    fn next_f64(&mut self) -> f64 {
+        self.random_state ^= self.random_state << 13;
+        self.random_state ^= self.random_state >> 7;
+        self.random_state ^= self.random_state << 17;
+        (self.random_state >> 11) as f64 / (1_u64 << 53) as f64
+    }
+}
+
+pub struct ImpairedTransport<T> {
+    inner: T,
+    outgoing: Option<Impairment>,
+    incoming: Option<Impairment>,
+    receive_buffer: Vec<u8>,
+}
+
+impl<T> ImpairedTransport<T> {
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            outgoing: None,
+            incoming: None,
+            receive_buffer: Vec::new(),
+        }
+    }
+
+    pub fn with_outgoing(mut self, config: ImpairmentConfig) -> Self {
+        self.outgoing = Some(Impairment::new(config));
+        self
+    }
+
+    pub fn with_incoming(mut self, config: ImpairmentConfig) -> Self {
+        self.incoming = Some(Impairment::new(config));
+        self
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    pub fn outgoing_stats(&self) -> Option<ImpairmentStats> {
+        self.outgoing.as_ref().map(Impairment::stats)
+    }
+
+    pub fn incoming_stats(&self) -> Option<ImpairmentStats> {
+        self.incoming.as_ref().map(Impairment::stats)
+    }
+}
+
+impl<T: PacketSender> ImpairedTransport<T> {
+    pub fn flush(&mut self) -> Result<(), TransportError> {
+        let Some(outgoing) = self.outgoing.as_mut() else {
+            return Ok(());
+        };
+        let now = Instant::now();
+        while let Some(packet) = outgoing.pop_due(now) {
+            match self.inner.send(&packet) {
+                Ok(_) => {}
+                Err(error) if error.is_transient() => {}
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T: PacketSender> PacketSender for ImpairedTransport<T> {
+    fn send(&mut self, packet: &[u8]) -> Result<usize, TransportError> {
+        let Some(outgoing) = self.outgoing.as_mut() else {
+            return self.inner.send(packet);
+        };
+        outgoing.submit(packet, Instant::now());
+        self.flush()?;
+        Ok(packet.len())
+    }
+
+    fn wait_writable(&mut self, timeout: Duration) -> Result<(), TransportError> {
+        self.inner.wait_writable(timeout)
+    }
+}
+
+impl<T: PacketReceiver + PacketSender> PacketReceiver for ImpairedTransport<T> {
+    fn receive(&mut self, buffer: &mut [u8]) -> Result<usize, TransportError> {
+        self.flush()?;
+        let Some(incoming) = self.incoming.as_mut() else {
+            return self.inner.receive(buffer);
+        };
+        if self.receive_buffer.len() < buffer.len() {
+            self.receive_buffer.resize(buffer.len(), 0);
+        }
+        loop {
+            if let Some(packet) = incoming.pop_due(Instant::now()) {
+                let length = packet.len().min(buffer.len());
+                buffer[..length].copy_from_slice(&packet[..length]);
+                return Ok(length);
+            }
+            let received = self
+                .inner
+                .receive(&mut self.receive_buffer[..buffer.len()])?;
+            incoming.submit(&self.receive_buffer[..received], Instant::now());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ImpairedTransport, Impairment, ImpairmentConfig, LossModel};
+    use crate::core::fec::FecScheme;
+    use crate::core::nack::{NackConfig, NackTracker};
+    use crate::core::packet_codec::{decode_packet, encode_packet};
+    use crate::core::packetizer::{Packetization, Packetizer, PacketizerConfig};
+    use crate::core::reassembler::FrameReassembler;
+    use crate::core::retransmit::RetransmissionBuffer;
+    use crate::core::sequence::SequenceNumber;
+    use crate::transport::{PacketReceiver, PacketSender, TransportError};
+    use std::collections::VecDeque;
+    use std::time::{Duration, Instant};
+
+    fn losses(config: ImpairmentConfig, packets: usize) -> Vec<bool> {
+        let mut impairment = Impairment::new(config);
+        let now = Instant::now();
+        (0..packets)
+            .map(|_| {
+                impairment.submit(&[0], now);
+                impairment.pop_due(now).is_none()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn random_loss_is_seeded_and_close_to_the_configured_rate() {
+        let config = ImpairmentConfig {
+            loss: LossModel::Random { probability: 0.1 },
+            ..ImpairmentConfig::default()
+        };
+        let first = losses(config, 10_000);
+        assert_eq!(first, losses(config, 10_000));
+        let lost = first.iter().filter(|&&lost| lost).count();
+        assert!((900..1100).contains(&lost), "lost {lost}");
+
+        let reseeded = ImpairmentConfig { seed: 7, ..config };
+        assert_ne!(first, losses(reseeded, 10_000));
+    }
+
+    #[test]
+    fn gilbert_elliott_loss_arrives_in_bursts() {
+        let config = ImpairmentConfig {
+            loss: LossModel::bursty(0.05, 4.0),
+            ..ImpairmentConfig::default()
+        };
+        let pattern = losses(config, 20_000);
+        let lost = pattern.iter().filter(|&&lost| lost).count();
+        let bursts = pattern
+            .windows(2)
+            .filter(|pair| pair[1] && !pair[0])
+            .count();
+        let mean_burst = lost as f64 / bursts as f64;
+
+        assert!((800..1200).contains(&lost), "lost {lost}");
+        assert!((3.0..5.0).contains(&mean_burst), "mean burst {mean_burst}");
+    }
+
+    #[test]
+    fn delay_and_jitter_hold_packets_back() {
+        let mut impairment = Impairment::new(ImpairmentConfig {
+            delay: Duration::from_millis(20),
+            jitter: Duration::from_millis(5),
+            ..ImpairmentConfig::default()
+        });
+        let start = Instant::now();
+        for index in 0..100_u8 {
+            impairment.submit(&[index], start);
+        }
+
+        assert!(impairment
+            .pop_due(start + Duration::from_millis(14))
+            .is_none());
+        let mut delivered = 0;
+        while impairment
+            .pop_due(start + Duration::from_millis(25))
+            .is_some()
+        {
+            delivered += 1;
+        }
+        assert_eq!(delivered, 100);
+    }
+
+    #[test]
+    fn reordered_and_duplicated_packets_are_counted() {
+        let mut impairment = Impairment::new(ImpairmentConfig {
+            reorder_probability: 0.2,
+            duplicate_probability: 0.1,
+            ..ImpairmentConfig::default()
+        });
+        let start = Instant::now();
+        for index in 0..200_u8 {
+            impairment.submit(&[index], start);
+        }
+
+        let mut order = Vec::new();
+        while let Some(packet) = impairment.pop_due(start + Duration::from_millis(10)) {
+            order.push(packet[0]);
+        }
+        let stats = impairment.stats();
+        assert!(stats.reordered > 0 && stats.duplicated > 0);
+        assert_eq!(order.len() as u64, 200 + stats.duplicated);
+        assert!(order.windows(2).any(|pair| pair[1] < pair[0]));
+    }
+
+    #[test]
+    fn bandwidth_cap_spaces_packets_and_drops_past_the_queue_limit() {
+        let mut impairment = Impairment::new(ImpairmentConfig {
+            bandwidth_bits_per_second: Some(1_000_000),
+            queue_limit_bytes: 10_000,
+            ..ImpairmentConfig::default()
+        });
+        let start = Instant::now();
+        for _ in 0..10 {
+            impairment.submit(&[0; 1250], start);
+        }
+
+        assert_eq!(impairment.stats().queue_dropped, 2);
+        assert_eq!(
+            impairment.next_due(),
+            Some(start + Duration::from_millis(10))
+        );
+        assert!(impairment
+            .pop_due(start + Duration::from_millis(9))
+            .is_none());
+        let mut delivered = 0;
+        while impairment
+            .pop_due(start + Duration::from_millis(80))
+            .is_some()
+        {
+            delivered += 1;
+        }
+        assert_eq!(delivered, 8);
+    }
+
+    #[test]
+    fn nack_retransmission_recovers_random_loss() {
+        let config = PacketizerConfig {
+            max_payload_bytes: 100,
+            fec: FecScheme::None,
+            packetization: Packetization::Fixed,
+        };
+        let mut packetizer = Packetizer::new(config, SequenceNumber::new(0));
+        let mut forward = Impairment::new(ImpairmentConfig {
+            loss: LossModel::Random { probability: 0.1 },
+            delay: Duration::from_millis(5),
+            ..ImpairmentConfig::default()
+        });
+        let mut retransmissions = RetransmissionBuffer::new(1024, Duration::from_secs(1));
+        let mut nack = NackTracker::new(NackConfig::default());
+        let mut reassembler = FrameReassembler::new(8);
+        let mut completed = 0;
+
+        let start = Instant::now();
+        for step in 0..2_000_u32 {
+            let now = start + Duration::from_millis(u64::from(step));
+            if step % 16 == 0 && step < 1_600 {
+                let frame_identifier = step / 16;
+                let payload = vec![frame_identifier as u8; 950];
+                for packet in packetizer
+                    .packetize(frame_identifier, u64::from(step), &payload)
+                    .expect("packetize")
+                {
+                    let bytes = encode_packet(&packet);
+                    retransmissions.insert(packet.header.sequence_number, bytes.clone(), now);
+                    forward.submit(&bytes, now);
+                }
+            }
+            while let Some(bytes) = forward.pop_due(now) {
+                let packet = decode_packet(&bytes).expect("decode");
+                nack.on_packet(&packet.header, now);
+                if let Some(frame) = reassembler.push_packet_at(packet, now).expect("push") {
+                    nack.on_frame_complete(frame.frame_identifier);
+                    completed += 1;
+                }
+            }
+            for sequence_number in nack.poll(now) {
+                if let Some(bytes) = retransmissions.get(sequence_number, now) {
+                    let bytes = bytes.to_vec();
+                    forward.submit(&bytes, now);
+                }
+            }
+        }
+
+        assert!(forward.stats().lost > 100);
+        assert!(completed >= 98, "completed {completed} of 100 frames");
+    }
+
+    #[test]
+    fn reed_solomon_rides_out_bursty_loss() {
+        let run = |fec: FecScheme| {
+            let mut packetizer = Packetizer::new(
+                PacketizerConfig {
+                    max_payload_bytes: 100,
+                    fec,
+                    packetization: Packetization::Fixed,
+                },
+                SequenceNumber::new(0),
+            );
+            let mut link = Impairment::new(ImpairmentConfig {
+                loss: LossModel::bursty(0.03, 2.0),
+                ..ImpairmentConfig::default()
+            });
+            let mut reassembler = FrameReassembler::new(4);
+            let now = Instant::now();
+            let mut completed = 0;
+            for frame_identifier in 0..200_u32 {
+                let payload = vec![frame_identifier as u8; 1500];
+                for packet in packetizer
+                    .packetize(frame_identifier, 0, &payload)
+                    .expect("packetize")
+                {
+                    link.submit(&encode_packet(&packet), now);
+                }
+                while let Some(bytes) = link.pop_due(now) {
+                    let packet = decode_packet(&bytes).expect("decode");
+                    if let Some(frame) = reassembler.push_packet_at(packet, now).expect("push") {
+                        assert_eq!(frame.payload, payload);
+                        completed += 1;
+                    }
+                }
+            }
+            completed
+        };
+
+        let without_fec = run(FecScheme::None);
+        let with_fec = run(FecScheme::ReedSolomon {
+            data_shards: 8,
+            parity_shards: 4,
+        });
+        assert!(without_fec < 180, "without fec {without_fec}");
+        assert!(with_fec >= 195, "with fec {with_fec}");
+    }
+
+    #[derive(Default)]
+    struct MemoryLink {
+        sent: Vec<Vec<u8>>,
+        inbound: VecDeque<Vec<u8>>,
+    }
+
+    impl PacketSender for MemoryLink {
+        fn send(&mut self, packet: &[u8]) -> Result<usize, TransportError> {
+            self.sent.push(packet.to_vec());
+            Ok(packet.len())
+        }
+    }
+
+    impl PacketReceiver for MemoryLink {
+        fn receive(&mut self, buffer: &mut [u8]) -> Result<usize, TransportError> {
+            let packet = self
+                .inbound
+                .pop_front()
+                .ok_or(TransportError::Io(std::io::ErrorKind::WouldBlock.into()))?;
+            buffer[..packet.len()].copy_from_slice(&packet);
+            Ok(packet.len())
+        }
+    }
+
+    #[test]
+    fn impaired_transport_applies_each_direction_separately() {
+        let mut inner = MemoryLink::default();
+        inner.inbound.extend((0..100_u8).map(|index| vec![index]));
+        let mut transport = ImpairedTransport::new(inner)
+            .with_outgoing(ImpairmentConfig {
+                loss: LossModel::Random { probability: 0.5 },
+                ..ImpairmentConfig::default()
+            })
+            .with_incoming(ImpairmentConfig {
+                duplicate_probability: 1.0,
+                ..ImpairmentConfig::default()
+            });
+
+        for index in 0..100_u8 {
+            assert_eq!(transport.send(&[index]).expect("send"), 1);
+        }
+        let mut buffer = [0_u8; 16];
+        let mut received = Vec::new();
+        while let Ok(length) = transport.receive(&mut buffer) {
+            received.push(buffer[..length].to_vec());
+        }
+
+        let outgoing = transport.outgoing_stats().expect("outgoing");
+        assert_eq!(transport.inner().sent.len() as u64, 100 - outgoing.lost);
+        assert!((30..70).contains(&outgoing.lost));
+        assert_eq!(received.len(), 200);
+        assert_eq!(received[0], received[1]);
+    }
+}
@@ -0,0 +1,211 @@
+use crate::transport::{PacketReceiver, PacketSender, TransportError};
+use std::cell::Cell;
+use std::io::ErrorKind;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::time::Duration;
+
+#[derive(Debug)]
+pub struct MemoryTransport {
+    sender: Sender<Vec<u8>>,
+    receiver: Receiver<Vec<u8>>,
+    read_timeout: Cell<Option<Duration>>,
+    nonblocking: Cell<bool>,
+}
+
+impl MemoryTransport {
+    pub fn pair() -> (Self, Self) {
+        let (first_sender, second_receiver) = mpsc::channel();
+        let (second_sender, first_receiver) = mpsc::channel();
+        (
+            Self::new(first_sender, first_receiver),
+            Self::new(second_sender, second_receiver),
+        )
+    }
+
+    fn new(sender: Sender<Vec<u8>>, receiver: Receiver<Vec<u8>>) -> Self {
+        Self {
+            sender,
+            receiver,
+            read_timeout: Cell::new(None),
+            nonblocking: Cell::new(false),
+        }
+    }
+
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> Result<(), TransportError> {
+        if timeout == Some(Duration::ZERO) {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidInput,
+                "cannot set a 0 duration timeout",
+            )
+            .into());
+        }
+        self.read_timeout.set(timeout);
+        Ok(())
+    }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> Result<(), TransportError> {
+        self.nonblocking.set(nonblocking);
+        Ok(())
+    }
+}
+
+impl PacketSender for MemoryTransport {
+    fn send(&mut self, packet: &[u8]) -> Result<usize, TransportError> {
+        self.sender
+            .send(packet.to_vec())
+            .map_err(|_| TransportError::Io(ErrorKind::ConnectionRefused.into()))?;
+        Ok(packet.len())
+    }
+}
+
+impl PacketReceiver for MemoryTransport {
+    fn receive(&mut self, buffer: &mut [u8]) -> Result<usize, TransportError> {
+        let packet = if self.nonblocking.get() {
+            self.receiver.try_recv().map_err(|error| match error {
+                TryRecvError::Empty => ErrorKind::WouldBlock,
+                TryRecvError::Disconnected => ErrorKind::NotConnected,
+            })
+        } else {
+            match self.read_timeout.get() {
+                Some(timeout) => self
+                    .receiver
+                    .recv_timeout(timeout)
+                    .map_err(|error| match error {
+                        RecvTimeoutError::Timeout => ErrorKind::WouldBlock,
+                        RecvTimeoutError::Disconnected => ErrorKind::NotConnected,
+                    }),
+                None => self.receiver.recv().map_err(|_| ErrorKind::NotConnected),
+            }
+        }
+        .map_err(|kind| TransportError::Io(kind.into()))?;
+
+        let length = packet.len().min(buffer.len());
+        buffer[..length].copy_from_slice(&packet[..length]);
+        Ok(length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MemoryTransport;
+    use crate::core::control::ControlMessage;
+    use crate::core::fec::FecScheme;
+    use crate::core::packet_codec::encode_packet;
+    use crate::core::packetizer::{Packetization, Packetizer, PacketizerConfig};
+    use crate::core::reassembler::FrameReassembler;
+    use crate::core::sequence::SequenceNumber;
+    use crate::transport::demux::{Demultiplexer, Message};
+    use crate::transport::{PacketReceiver, PacketSender};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn delivers_datagrams_in_both_directions() {
+        let (mut host, mut client) = MemoryTransport::pair();
+        host.send(b"video").expect("send");
+        client.send(b"nack").expect("send");
+
+        let mut buffer = [0_u8; 16];
+        let received = client.receive(&mut buffer).expect("receive");
+        assert_eq!(&buffer[..received], b"video");
+        let received = host.receive(&mut buffer).expect("receive");
+        assert_eq!(&buffer[..received], b"nack");
+    }
+
+    #[test]
+    fn truncates_datagrams_larger_than_the_buffer() {
+        let (mut host, mut client) = MemoryTransport::pair();
+        host.send(&[7_u8; 32]).expect("send");
+
+        let mut buffer = [0_u8; 8];
+        assert_eq!(client.receive(&mut buffer).expect("receive"), 8);
+    }
+
+    #[test]
+    fn read_timeout_and_nonblocking_report_transient_errors() {
+        let (_host, mut client) = MemoryTransport::pair();
+        let mut buffer = [0_u8; 8];
+
+        client
+            .set_read_timeout(Some(Duration::from_millis(20)))
+            .expect("timeout");
+        let started = Instant::now();
+        let error = client.receive(&mut buffer).expect_err("nothing sent");
+        assert!(error.is_transient());
+        assert!(started.elapsed() >= Duration::from_millis(20));
+
+        client.set_nonblocking(true).expect("nonblocking");
+        let error = client.receive(&mut buffer).expect_err("nothing sent");
+        assert!(error.is_transient());
+
+        assert!(client.set_read_timeout(Some(Duration::ZERO)).is_err());
+    }
+
+    #[test]
+    fn dropped_peer_stops_the_receiver_but_not_the_sender() {
+        let (host, mut client) = MemoryTransport::pair();
+        drop(host);
+
+        let error = client.send(b"ping").expect_err("peer gone");
+        assert!(error.is_transient());
+        let mut buffer = [0_u8; 8];
+        let error = client.receive(&mut buffer).expect_err("peer gone");
+        assert!(!error.is_transient());
+    }
+
+    #[test]
+    fn host_and_client_pipelines_run_in_one_process() {
+        let (mut host, client) = MemoryTransport::pair();
+        client
+            .set_read_timeout(Some(Duration::from_millis(100)))
+            .expect("timeout");
+
+        let sender = std::thread::spawn(move || {
+            let mut packetizer = Packetizer::new(
+                PacketizerConfig {
+                    max_payload_bytes: 64,
+                    fec: FecScheme::XorParity { group_size: 4 },
+                    packetization: Packetization::Fixed,
+                },
+                SequenceNumber::new(0),
+            );
+            for frame_identifier in 0..10_u32 {
+                let payload = vec![frame_identifier as u8; 300];
+                for packet in packetizer
+                    .packetize(frame_identifier, 0, &payload)
+                    .expect("packetize")
+                {
+                    host.send(&encode_packet(&packet)).expect("send");
+                }
+            }
+            host.set_read_timeout(Some(Duration::from_secs(5)))
+                .expect("timeout");
+            let mut buffer = [0_u8; 64];
+            let received = host.receive(&mut buffer).expect("receive");
+            buffer[..received].to_vec()
+        });
+
+        let mut receiver = Demultiplexer::new(client, 2048);
+        let mut reassembler = FrameReassembler::new(16);
+        let mut frames = Vec::new();
+        while frames.len() < 10 {
+            if let Message::Video(packet) = receiver.receive().expect("receive") {
+                if let Some(frame) = reassembler.push_packet(packet).expect("push") {
+                    frames.push(frame);
+                }
+            }
+        }
+        receiver
+            .send(&ControlMessage::KeyframeRequest.encode())
+            .expect("send");
+
+        let feedback = sender.join().expect("host thread");
+        assert_eq!(
+            ControlMessage::decode(&feedback).expect("decode"),
+            ControlMessage::KeyframeRequest
+        );
+        for (frame_identifier, frame) in frames.iter().enumerate() {
+            assert_eq!(frame.frame_identifier, frame_identifier as u32);
+            assert_eq!(frame.payload, vec![frame_identifier as u8; 300]);
+        }
+    }
+}
@@ -1,5 +1,18 @@
+pub mod batch;
+pub mod demux;
+pub mod impairment;
+pub mod memory;
+pub mod pacer;
+pub mod pcap;
+#[cfg(feature = "quic")]
+pub mod quic;
+pub mod replay;
+pub mod tcp;
 pub mod udp;
 
+use batch::ReceiveBatch;
+use std::time::Duration;
+
 #[derive(Debug)]
 pub enum TransportError {
     Io(std::io::Error),
@@ -21,10 +34,43 @@ impl std::fmt::Display for TransportError {
 
 impl std::error::Error for TransportError {}
 
+impl TransportError {
+    pub fn is_transient(&self) -> bool {
+        match self {
+            TransportError::Io(error) => matches!(
+                error.kind(),
+                std::io::ErrorKind::WouldBlock
+                    | std::io::ErrorKind::TimedOut
+                    | std::io::ErrorKind::Interrupted
+                    | std::io::ErrorKind::ConnectionRefused
+            ),
+        }
+    }
+
+    pub fn is_message_too_large(&self) -> bool {
+        match self {
+            TransportError::Io(error) => error.raw_os_error() == Some(libc::EMSGSIZE),
+        }
+    }
+}
+
 pub trait PacketSender {
     fn send(&mut self, packet: &[u8]) -> Result<usize, TransportError>;
+
+    fn send_batch(&mut self, packets: &[&[u8]]) -> Result<usize, TransportError> {
+        batch::send_individually(self, packets)
+    }
+
+    fn wait_writable(&mut self, timeout: Duration) -> Result<(), TransportError> {
+        std::thread::sleep(timeout);
+        Ok(())
+    }
 }
 
 pub trait PacketReceiver {
     fn receive(&mut self, buffer: &mut [u8]) -> Result<usize, TransportError>;
+
+    fn receive_batch(&mut self, batch: &mut ReceiveBatch) -> Result<usize, TransportError> {
+        batch::receive_individually(self, batch)
+    }
 }
@@ -0,0 +1,162 @@
+use std::time::{Duration, Instant};
+
+const NANOS_PER_SECOND: f64 = 1_000_000_000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PacerConfig {
+    pub target_bitrate: u64,
+    pub burst_bytes: usize,
+    pub frame_interval: Duration,
+    pub spread_fraction: f64,
+}
+
+impl Default for PacerConfig {
+    fn default() -> Self {
+        Self {
+            target_bitrate: 3_000_000,
+            burst_bytes: 12_000,
+            frame_interval: Duration::from_millis(16),
+            spread_fraction: 0.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Pacer {
+    config: PacerConfig,
+    tokens: f64,
+    bytes_per_second: f64,
+    last_refill: Option<Instant>,
+}
+
+impl Pacer {
+    pub fn new(config: PacerConfig) -> Self {
+        Self {
+            config,
+            tokens: config.burst_bytes as f64,
+            bytes_per_second: target_bytes_per_second(&config),
+            last_refill: None,
+        }
+    }
+
+    pub fn set_target_bitrate(&mut self, target_bitrate: u64) {
+        self.config.target_bitrate = target_bitrate;
+    }
+
+    pub fn begin_frame(&mut self, frame_bytes: usize) {
+        let window = self.config.frame_interval.as_secs_f64() * self.config.spread_fraction;
+        let spread_rate = if window > 0.0 {
+            frame_bytes as f64 / window
+        } else {
+            f64::INFINITY
+        };
+        self.bytes_per_second = target_bytes_per_second(&self.config).max(spread_rate);
+    }
+
+    pub fn schedule(&mut self, packet_bytes: usize, now: Instant) -> Instant {
+        self.refill(now);
+        let deficit = packet_bytes as f64 - self.tokens;
+        self.tokens -= packet_bytes as f64;
+        if deficit <= 0.0 || !self.bytes_per_second.is_finite() {
+            return now;
+        }
+        let delay_nanos = (deficit * NANOS_PER_SECOND / self.bytes_per_second).ceil();
+        now + Duration::from_nanos(delay_nanos as u64)
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let burst = self.config.burst_bytes as f64;
+        if let Some(last_refill) = self.last_refill {
+            let elapsed = now.saturating_duration_since(last_refill).as_secs_f64();
+            let refill = if self.bytes_per_second.is_finite() {
+                elapsed * self.bytes_per_second
+            } else {
+                burst - self.tokens
+            };
+            self.tokens = (self.tokens + refill).min(burst);
+        }
+        self.last_refill = Some(self.last_refill.map_or(now, |last| last.max(now)));
+    }
+}
+
+fn target_bytes_per_second(config: &PacerConfig) -> f64 {
+    config.target_bitrate as f64 / 8.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Pacer, PacerConfig};
+    use std::time::{Duration, Instant};
+
+    const PACKET_BYTES: usize = 1000;
+
+    fn config() -> PacerConfig {
+        PacerConfig {
+            target_bitrate: 8_000_000,
+            burst_bytes: 2 * PACKET_BYTES,
+            frame_interval: Duration::from_millis(16),
+            spread_fraction: 0.5,
+        }
+    }
+
+    fn schedule_frame(pacer: &mut Pacer, packets: usize, now: Instant) -> Vec<Duration> {
+        pacer.begin_frame(packets * PACKET_BYTES);
+        (0..packets)
+            .map(|_| pacer.schedule(PACKET_BYTES, now) - now)
+            .collect()
+    }
+
+    #[test]
+    fn sends_burst_then_paces_at_target_bitrate() {
+        let mut pacer = Pacer::new(config());
+        let now = Instant::now();
+
+        let schedule = schedule_frame(&mut pacer, 4, now);
+        assert_eq!(
+            schedule,
+            vec![
+                Duration::ZERO,
+                Duration::ZERO,
+                Duration::from_millis(1),
+                Duration::from_millis(2),
+            ]
+        );
+    }
+
+    #[test]
+    fn spreads_large_frame_across_fraction_of_interval() {
+        let mut pacer = Pacer::new(config());
+        let now = Instant::now();
+
+        let schedule = schedule_frame(&mut pacer, 80, now);
+        assert!(schedule.windows(2).all(|pair| pair[0] <= pair[1]));
+        let last = *schedule.last().unwrap();
+        assert!(last <= Duration::from_millis(8), "last packet at {last:?}");
+        assert!(last >= Duration::from_millis(7), "last packet at {last:?}");
+    }
+
+    #[test]
+    fn refills_while_idle_up_to_burst() {
+        let mut pacer = Pacer::new(config());
+        let start = Instant::now();
+        let _ = schedule_frame(&mut pacer, 4, start);
+
+        let later = start + Duration::from_secs(1);
+        let schedule = schedule_frame(&mut pacer, 3, later);
+        assert_eq!(
+            schedule,
+            vec![Duration::ZERO, Duration::ZERO, Duration::from_millis(1)]
+        );
+    }
+
+    #[test]
+    fn pays_back_debt_before_next_frame() {
+        let mut pacer = Pacer::new(config());
+        let start = Instant::now();
+        let _ = schedule_frame(&mut pacer, 4, start);
+
+        let next_frame = start + Duration::from_millis(1);
+        let schedule = schedule_frame(&mut pacer, 1, next_frame);
+        assert_eq!(schedule, vec![Duration::from_millis(2)]);
+    }
+}
@@ -0,0 +1,680 @@
+use crate::transport::{PacketReceiver, PacketSender, TransportError};
+use std::io::{Read, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const MAGIC_MICROS: u32 = 0xA1B2_C3D4;
+const MAGIC_NANOS: u32 = 0xA1B2_3C4D;
+const SNAPSHOT_LENGTH: u32 = 262_144;
+const GLOBAL_HEADER_LENGTH: usize = 24;
+const RECORD_HEADER_LENGTH: usize = 16;
+const IPV4_HEADER_LENGTH: usize = 20;
+const IPV6_HEADER_LENGTH: usize = 40;
+const UDP_HEADER_LENGTH: usize = 8;
+const UDP_PROTOCOL: u8 = 17;
+const DEFAULT_TTL: u8 = 64;
+const CAPTURE_FLUSH_INTERVAL: Duration = Duration::from_millis(100);
+
+const LINKTYPE_NULL: u32 = 0;
+const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_RAW: u32 = 101;
+const LINKTYPE_LINUX_SLL: u32 = 113;
+const LINKTYPE_IPV4: u32 = 228;
+const LINKTYPE_IPV6: u32 = 229;
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_IPV6: u16 = 0x86DD;
+const ETHERTYPE_VLAN: u16 = 0x8100;
+
+#[derive(Debug)]
+pub enum PcapError {
+    Io(std::io::Error),
+    InvalidMagic(u32),
+    UnsupportedLinkType(u32),
+    TruncatedRecord,
+    OversizedRecord(usize),
+}
+
+impl From<std::io::Error> for PcapError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl std::fmt::Display for PcapError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PcapError::Io(error) => write!(formatter, "pcap io error: {error}"),
+            PcapError::InvalidMagic(magic) => {
+                write!(formatter, "not a pcap file (magic {magic:#010x})")
+            }
+            PcapError::UnsupportedLinkType(link_type) => {
+                write!(formatter, "unsupported pcap link type {link_type}")
+            }
+            PcapError::TruncatedRecord => write!(formatter, "pcap record truncated"),
+            PcapError::OversizedRecord(length) => {
+                write!(
+                    formatter,
+                    "pcap record of {length} bytes exceeds the snapshot length"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for PcapError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedDatagram {
+    pub timestamp: Duration,
+    pub source: SocketAddr,
+    pub destination: SocketAddr,
+    pub payload: Vec<u8>,
+}
+
+pub struct PcapWriter<W: Write> {
+    writer: W,
+    packet: Vec<u8>,
+}
+
+impl<W: Write> PcapWriter<W> {
+    pub fn new(mut writer: W) -> std::io::Result<Self> {
+        let mut header = Vec::with_capacity(GLOBAL_HEADER_LENGTH);
+        header.extend_from_slice(&MAGIC_NANOS.to_le_bytes());
+        header.extend_from_slice(&2_u16.to_le_bytes());
+        header.extend_from_slice(&4_u16.to_le_bytes());
+        header.extend_from_slice(&0_i32.to_le_bytes());
+        header.extend_from_slice(&0_u32.to_le_bytes());
+        header.extend_from_slice(&SNAPSHOT_LENGTH.to_le_bytes());
+        header.extend_from_slice(&LINKTYPE_RAW.to_le_bytes());
+        writer.write_all(&header)?;
+        Ok(Self {
+            writer,
+            packet: Vec::new(),
+        })
+    }
+
+    pub fn write_datagram(
+        &mut self,
+        timestamp: Duration,
+        source: SocketAddr,
+        destination: SocketAddr,
+        payload: &[u8],
+    ) -> std::io::Result<()> {
+        self.packet.clear();
+        encode_ip_udp(&mut self.packet, source, destination, payload);
+
+        let length = self.packet.len() as u32;
+        let mut record = [0_u8; RECORD_HEADER_LENGTH];
+        record[0..4].copy_from_slice(&(timestamp.as_secs() as u32).to_le_bytes());
+        record[4..8].copy_from_slice(&timestamp.subsec_nanos().to_le_bytes());
+        record[8..12].copy_from_slice(&length.to_le_bytes());
+        record[12..16].copy_from_slice(&length.to_le_bytes());
+        self.writer.write_all(&record)?;
+        self.writer.write_all(&self.packet)
+    }
+
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PcapReaderStats {
+    pub records: u64,
+    pub datagrams: u64,
+    pub skipped: u64,
+}
+
+pub struct PcapReader<R: Read> {
+    reader: R,
+    swapped: bool,
+    nanosecond_timestamps: bool,
+    link_type: u32,
+    max_record_length: usize,
+    stats: PcapReaderStats,
+}
+
+impl<R: Read> PcapReader<R> {
+    pub fn new(mut reader: R) -> Result<Self, PcapError> {
+        let mut header = [0_u8; GLOBAL_HEADER_LENGTH];
+        reader.read_exact(&mut header)?;
+        let magic = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+        let (swapped, nanosecond_timestamps) = match magic {
+            MAGIC_MICROS => (false, false),
+            MAGIC_NANOS => (false, true),
+            _ if magic.swap_bytes() == MAGIC_MICROS => (true, false),
+            _ if magic.swap_bytes() == MAGIC_NANOS => (true, true),
+            _ => return Err(PcapError::InvalidMagic(magic)),
+        };
+        let snapshot_length = read_u32(&header[16..20], swapped);
+        let link_type = read_u32(&header[20..24], swapped) & 0x0FFF_FFFF;
+        if !matches!(
+            link_type,
+            LINKTYPE_NULL
+                | LINKTYPE_ETHERNET
+                | LINKTYPE_RAW
+                | LINKTYPE_LINUX_SLL
+                | LINKTYPE_IPV4
+                | LINKTYPE_IPV6
+        ) {
+            return Err(PcapError::UnsupportedLinkType(link_type));
+        }
+
+        Ok(Self {
+            reader,
+            swapped,
+            nanosecond_timestamps,
+            link_type,
+            max_record_length: match snapshot_length {
+                0 => SNAPSHOT_LENGTH,
+                length => length.min(SNAPSHOT_LENGTH),
+            } as usize,
+            stats: PcapReaderStats::default(),
+        })
+    }
+
+    pub fn next_datagram(&mut self) -> Result<Option<CapturedDatagram>, PcapError> {
+        loop {
+            let mut record = [0_u8; RECORD_HEADER_LENGTH];
+            match self.reader.read_exact(&mut record) {
+                Ok(()) => {}
+                Err(error) if error.kind() == std::io::ErrorKind::UnexpectedEof => {
+                    return Ok(None);
+                }
+                Err(error) => return Err(error.into()),
+            }
+            let seconds = read_u32(&record[0..4], self.swapped);
+            let fraction = read_u32(&record[4..8], self.swapped);
+            let captured_length = read_u32(&record[8..12], self.swapped) as usize;
+            let original_length = read_u32(&record[12..16], self.swapped) as usize;
+            if captured_length > self.max_record_length {
+                return Err(PcapError::OversizedRecord(captured_length));
+            }
+
+            let mut frame = vec![0_u8; captured_length];
+            self.reader.read_exact(&mut frame).map_err(|error| {
+                if error.kind() == std::io::ErrorKind::UnexpectedEof {
+                    PcapError::TruncatedRecord
+                } else {
+                    PcapError::Io(error)
+                }
+            })?;
+            self.stats.records += 1;
+
+            let subsec_nanos = if self.nanosecond_timestamps {
+                fraction
+            } else {
+                fraction.saturating_mul(1000)
+            };
+            let timestamp =
+                Duration::from_secs(u64::from(seconds)) + Duration::from_nanos(subsec_nanos.into());
+
+            let datagram = (captured_length == original_length)
+                .then(|| self.decode_frame(&frame, timestamp))
+                .flatten();
+            match datagram {
+                Some(datagram) => {
+                    self.stats.datagrams += 1;
+                    return Ok(Some(datagram));
+                }
+                None => self.stats.skipped += 1,
+            }
+        }
+    }
+
+    pub fn stats(&self) -> PcapReaderStats {
+        self.stats
+    }
+
+    fn decode_frame(&self, frame: &[u8], timestamp: Duration) -> Option<CapturedDatagram> {
+        let packet = match self.link_type {
+            LINKTYPE_NULL => frame.get(4..)?,
+            LINKTYPE_ETHERNET => {
+                let mut offset = 12;
+                let mut ethertype = read_u16(frame, offset)?;
+                while ethertype == ETHERTYPE_VLAN {
+                    offset += 4;
+                    ethertype = read_u16(frame, offset)?;
+                }
+                if ethertype != ETHERTYPE_IPV4 && ethertype != ETHERTYPE_IPV6 {
+                    return None;
+                }
+                frame.get(offset + 2..)?
+            }
+            LINKTYPE_LINUX_SLL => frame.get(16..)?,
+            _ => frame,
+        };
+        decode_ip_udp(packet, timestamp)
+    }
+}
+
+pub struct CaptureTransport<T, W: Write> {
+    inner: T,
+    writer: PcapWriter<W>,
+    local_address: SocketAddr,
+    remote_address: SocketAddr,
+    last_flush: Instant,
+}
+
+impl<T, W: Write> CaptureTransport<T, W> {
+    pub fn new(
+        inner: T,
+        writer: PcapWriter<W>,
+        local_address: SocketAddr,
+        remote_address: SocketAddr,
+    ) -> Self {
+        Self {
+            inner,
+            writer,
+            local_address,
+            remote_address,
+            last_flush: Instant::now(),
+        }
+    }
+
+    pub fn into_parts(self) -> (T, PcapWriter<W>) {
+        (self.inner, self.writer)
+    }
+
+    fn flush_if_due(&mut self) -> std::io::Result<()> {
+        if self.last_flush.elapsed() >= CAPTURE_FLUSH_INTERVAL {
+            self.writer.flush()?;
+            self.last_flush = Instant::now();
+        }
+        Ok(())
+    }
+}
+
+impl<T: PacketReceiver, W: Write> PacketReceiver for CaptureTransport<T, W> {
+    fn receive(&mut self, buffer: &mut [u8]) -> Result<usize, TransportError> {
+        let result = self.inner.receive(buffer);
+        if let Ok(received) = result {
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default();
+            self.writer.write_datagram(
+                timestamp,
+                self.remote_address,
+                self.local_address,
+                &buffer[..received],
+            )?;
+        }
+        self.flush_if_due()?;
+        result
+    }
+}
+
+impl<T: PacketSender, W: Write> PacketSender for CaptureTransport<T, W> {
+    fn send(&mut self, packet: &[u8]) -> Result<usize, TransportError> {
+        self.inner.send(packet)
+    }
+
+    fn send_batch(&mut self, packets: &[&[u8]]) -> Result<usize, TransportError> {
+        self.inner.send_batch(packets)
+    }
+
+    fn wait_writable(&mut self, timeout: Duration) -> Result<(), TransportError> {
+        self.inner.wait_writable(timeout)
+    }
+}
+
+fn encode_ip_udp(
+    packet: &mut Vec<u8>,
+    source: SocketAddr,
+    destination: SocketAddr,
+    payload: &[u8],
+) {
+    let udp_length = (UDP_HEADER_LENGTH + payload.len()).min(usize::from(u16::MAX)) as u16;
+    let mut udp = [0_u8; UDP_HEADER_LENGTH];
+    udp[0..2].copy_from_slice(&source.port().to_be_bytes());
+    udp[2..4].copy_from_slice(&destination.port().to_be_bytes());
+    udp[4..6].copy_from_slice(&udp_length.to_be_bytes());
+
+    match (source.ip(), destination.ip()) {
+        (IpAddr::V4(source_ip), IpAddr::V4(destination_ip)) => {
+            let total_length =
+                (IPV4_HEADER_LENGTH + usize::from(udp_length)).min(usize::from(u16::MAX)) as u16;
+            let mut header = [0_u8; IPV4_HEADER_LENGTH];
+            header[0] = 0x45;
+            header[2..4].copy_from_slice(&total_length.to_be_bytes());
+            header[6] = 0x40;
+            header[8] = DEFAULT_TTL;
+            header[9] = UDP_PROTOCOL;
+            header[12..16].copy_from_slice(&source_ip.octets());
+            header[16..20].copy_from_slice(&destination_ip.octets());
+            let checksum = internet_checksum(&[&header]);
+            header[10..12].copy_from_slice(&checksum.to_be_bytes());
+
+            let pseudo_header = [
+                &source_ip.octets()[..],
+                &destination_ip.octets(),
+                &[0, UDP_PROTOCOL],
+                &udp_length.to_be_bytes(),
+            ]
+            .concat();
+            let checksum = udp_checksum(&pseudo_header, &udp, payload);
+            udp[6..8].copy_from_slice(&checksum.to_be_bytes());
+            packet.extend_from_slice(&header);
+        }
+        (source_ip, destination_ip) => {
+            let source_ip = ipv6_of(source_ip);
+            let destination_ip = ipv6_of(destination_ip);
+            let mut header = [0_u8; IPV6_HEADER_LENGTH];
+            header[0] = 0x60;
+            header[4..6].copy_from_slice(&udp_length.to_be_bytes());
+            header[6] = UDP_PROTOCOL;
+            header[7] = DEFAULT_TTL;
+            header[8..24].copy_from_slice(&source_ip.octets());
+            header[24..40].copy_from_slice(&destination_ip.octets());
+
+            let pseudo_header = [
+                &source_ip.octets()[..],
+                &destination_ip.octets(),
+                &u32::from(udp_length).to_be_bytes(),
+                &[0, 0, 0, UDP_PROTOCOL],
+            ]
+            .concat();
+            let checksum = udp_checksum(&pseudo_header, &udp, payload);
+            udp[6..8].copy_from_slice(&checksum.to_be_bytes());
+            packet.extend_from_slice(&header);
+        }
+    }
+    packet.extend_from_slice(&udp);
+    packet.extend_from_slice(payload);
+}
+
+fn decode_ip_udp(packet: &[u8], timestamp: Duration) -> Option<CapturedDatagram> {
+    let version = packet.first()? >> 4;
+    let (source_ip, destination_ip, udp) = match version {
+        4 => {
+            let header_length = usize::from(packet[0] & 0x0F) * 4;
+            let total_length = usize::from(read_u16(packet, 2)?);
+            let fragment = read_u16(packet, 6)? & 0x3FFF;
+            if header_length < IPV4_HEADER_LENGTH
+                || *packet.get(9)? != UDP_PROTOCOL
+                || fragment != 0
+            {
+                return None;
+            }
+            let source: [u8; 4] = packet.get(12..16)?.try_into().ok()?;
+            let destination: [u8; 4] = packet.get(16..20)?.try_into().ok()?;
+            (
+                IpAddr::V4(Ipv4Addr::from(source)),
+                IpAddr::V4(Ipv4Addr::from(destination)),
+                packet.get(header_length..total_length)?,
+            )
+        }
+        6 => {
+            if *packet.get(6)? != UDP_PROTOCOL {
+                return None;
+            }
+            let payload_length = usize::from(read_u16(packet, 4)?);
+            let source: [u8; 16] = packet.get(8..24)?.try_into().ok()?;
+            let destination: [u8; 16] = packet.get(24..40)?.try_into().ok()?;
+            (
+                IpAddr::V6(Ipv6Addr::from(source)),
+                IpAddr::V6(Ipv6Addr::from(destination)),
+                packet.get(IPV6_HEADER_LENGTH..IPV6_HEADER_LENGTH + payload_length)?,
+            )
+        }
+        _ => return None,
+    };
+
+    let udp_length = usize::from(read_u16(udp, 4)?);
+    let payload = udp.get(UDP_HEADER_LENGTH..udp_length)?;
+    Some(CapturedDatagram {
+        timestamp,
+        source: SocketAddr::new(source_ip, read_u16(udp, 0)?),
+        destination: SocketAddr::new(destination_ip, read_u16(udp, 2)?),
+        payload: payload.to_vec(),
+    })
+}
+
+fn ipv6_of(address: IpAddr) -> Ipv6Addr {
+    match address {
+        IpAddr::V4(address) => address.to_ipv6_mapped(),
+        IpAddr::V6(address) => address,
+    }
+}
+
+fn read_u32(bytes: &[u8], swapped: bool) -> u32 {
+    let value = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    if swapped {
+        value.swap_bytes()
+    } else {
+        value
+    }
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    let value = bytes.get(offset..offset + 2)?;
+    Some(u16::from_be_bytes([value[0], value[1]]))
+}
+
+fn udp_checksum(pseudo_header: &[u8], udp_header: &[u8], payload: &[u8]) -> u16 {
+    match internet_checksum(&[pseudo_header, udp_header, payload]) {
+        0 => 0xFFFF,
+        checksum => checksum,
+    }
+}
+
+fn internet_checksum(parts: &[&[u8]]) -> u16 {
+    let mut sum: u64 = 0;
+    let mut odd_byte: Option<u8> = None;
+    for part in parts {
+        for &byte in part.iter() {
+            match odd_byte.take() {
+                Some(high) => sum += u64::from(u16::from_be_bytes([high, byte])),
+                None => odd_byte = Some(byte),
+            }
+        }
+    }
+    if let Some(high) = odd_byte {
+        sum += u64::from(u16::from_be_bytes([high, 0]));
+    }
+    while sum > 0xFFFF {
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        internet_checksum, CaptureTransport, CapturedDatagram, PcapError, PcapReader, PcapWriter,
+        SNAPSHOT_LENGTH,
+    };
+    use crate::transport::{PacketReceiver, TransportError};
+    use std::collections::VecDeque;
+    use std::net::SocketAddr;
+    use std::time::Duration;
+
+    fn address(value: &str) -> SocketAddr {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn round_trips_ipv4_and_ipv6_datagrams() {
+        let mut writer = PcapWriter::new(Vec::new()).expect("header");
+        writer
+            .write_datagram(
+                Duration::new(1_700_000_000, 123_456_789),
+                address("192.168.0.1:5001"),
+                address("192.168.0.2:5000"),
+                b"video",
+            )
+            .expect("write");
+        writer
+            .write_datagram(
+                Duration::new(1_700_000_001, 5),
+                address("[fe80::1]:5001"),
+                address("[fe80::2]:5000"),
+                b"odd",
+            )
+            .expect("write");
+        let capture = writer.into_inner();
+
+        let mut reader = PcapReader::new(capture.as_slice()).expect("header");
+        assert_eq!(
+            reader.next_datagram().expect("read"),
+            Some(CapturedDatagram {
+                timestamp: Duration::new(1_700_000_000, 123_456_789),
+                source: address("192.168.0.1:5001"),
+                destination: address("192.168.0.2:5000"),
+                payload: b"video".to_vec(),
+            })
+        );
+        let second = reader.next_datagram().expect("read").expect("datagram");
+        assert_eq!(second.source, address("[fe80::1]:5001"));
+        assert_eq!(second.payload, b"odd");
+        assert_eq!(reader.next_datagram().expect("read"), None);
+        assert_eq!(reader.stats().datagrams, 2);
+    }
+
+    #[test]
+    fn writes_valid_ipv4_and_udp_checksums() {
+        let mut writer = PcapWriter::new(Vec::new()).expect("header");
+        writer
+            .write_datagram(
+                Duration::ZERO,
+                address("10.0.0.1:1234"),
+                address("10.0.0.2:5678"),
+                b"abc",
+            )
+            .expect("write");
+        let capture = writer.into_inner();
+        let packet = &capture[24 + 16..];
+
+        assert_eq!(internet_checksum(&[&packet[..20]]), 0);
+        let pseudo_header = [&packet[12..20], &[0, 17], &packet[24..26]].concat();
+        assert_eq!(internet_checksum(&[&pseudo_header, &packet[20..]]), 0);
+    }
+
+    #[test]
+    fn reads_microsecond_ethernet_captures_and_skips_other_traffic() {
+        let mut capture = Vec::new();
+        capture.extend_from_slice(&0xA1B2_C3D4_u32.to_be_bytes());
+        capture.extend_from_slice(&2_u16.to_be_bytes());
+        capture.extend_from_slice(&4_u16.to_be_bytes());
+        capture.extend_from_slice(&[0; 8]);
+        capture.extend_from_slice(&65535_u32.to_be_bytes());
+        capture.extend_from_slice(&1_u32.to_be_bytes());
+
+        let mut push_frame = |ethertype: u16, ip_packet: &[u8]| {
+            let mut frame = vec![0_u8; 12];
+            frame.extend_from_slice(&ethertype.to_be_bytes());
+            frame.extend_from_slice(ip_packet);
+            capture.extend_from_slice(&10_u32.to_be_bytes());
+            capture.extend_from_slice(&250_u32.to_be_bytes());
+            capture.extend_from_slice(&(frame.len() as u32).to_be_bytes());
+            capture.extend_from_slice(&(frame.len() as u32).to_be_bytes());
+            capture.extend_from_slice(&frame);
+        };
+        push_frame(0x0806, &[0; 28]);
+        let mut udp_packet = Vec::new();
+        super::encode_ip_udp(
+            &mut udp_packet,
+            address("169.254.1.1:5001"),
+            address("169.254.1.2:5000"),
+            b"chunk",
+        );
+        push_frame(0x0800, &udp_packet);
+
+        let mut reader = PcapReader::new(capture.as_slice()).expect("header");
+        let datagram = reader.next_datagram().expect("read").expect("datagram");
+        assert_eq!(datagram.timestamp, Duration::new(10, 250_000));
+        assert_eq!(datagram.destination, address("169.254.1.2:5000"));
+        assert_eq!(datagram.payload, b"chunk");
+        assert_eq!(reader.stats().skipped, 1);
+    }
+
+    #[test]
+    fn rejects_records_longer_than_the_snapshot_length() {
+        let cases = [
+            (65535, 65536),
+            (0, SNAPSHOT_LENGTH + 1),
+            (u32::MAX, SNAPSHOT_LENGTH + 1),
+            (u32::MAX, u32::MAX),
+        ];
+        for (snapshot_length, captured_length) in cases {
+            let mut capture = Vec::new();
+            capture.extend_from_slice(&0xA1B2_C3D4_u32.to_le_bytes());
+            capture.extend_from_slice(&2_u16.to_le_bytes());
+            capture.extend_from_slice(&4_u16.to_le_bytes());
+            capture.extend_from_slice(&[0; 8]);
+            capture.extend_from_slice(&snapshot_length.to_le_bytes());
+            capture.extend_from_slice(&101_u32.to_le_bytes());
+            capture.extend_from_slice(&[0; 8]);
+            capture.extend_from_slice(&captured_length.to_le_bytes());
+            capture.extend_from_slice(&captured_length.to_le_bytes());
+
+            let mut reader = PcapReader::new(capture.as_slice()).expect("header");
+            let result = reader.next_datagram();
+            assert!(
+                matches!(
+                    result,
+                    Err(PcapError::OversizedRecord(length)) if length == captured_length as usize
+                ),
+                "snapshot length {snapshot_length:#x}, captured length {captured_length}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_files_that_are_not_pcap() {
+        let result = PcapReader::new(&[0_u8; 24][..]);
+        assert!(matches!(result, Err(PcapError::InvalidMagic(0))));
+    }
+
+    struct ScriptedReceiver {
+        datagrams: VecDeque<Vec<u8>>,
+    }
+
+    impl PacketReceiver for ScriptedReceiver {
+        fn receive(&mut self, buffer: &mut [u8]) -> Result<usize, TransportError> {
+            match self.datagrams.pop_front() {
+                Some(datagram) => {
+                    buffer[..datagram.len()].copy_from_slice(&datagram);
+                    Ok(datagram.len())
+                }
+                None => Err(TransportError::Io(std::io::ErrorKind::WouldBlock.into())),
+            }
+        }
+    }
+
+    #[test]
+    fn capture_transport_records_received_datagrams() {
+        let inner = ScriptedReceiver {
+            datagrams: VecDeque::from(vec![b"first".to_vec(), b"second".to_vec()]),
+        };
+        let writer = PcapWriter::new(Vec::new()).expect("header");
+        let mut transport = CaptureTransport::new(
+            inner,
+            writer,
+            address("127.0.0.1:5000"),
+            address("127.0.0.1:5001"),
+        );
+
+        let mut buffer = [0_u8; 64];
+        assert_eq!(transport.receive(&mut buffer).expect("receive"), 5);
+        assert_eq!(transport.receive(&mut buffer).expect("receive"), 6);
+        assert!(transport.receive(&mut buffer).is_err());
+
+        let (_, writer) = transport.into_parts();
+        let capture = writer.into_inner();
+        let mut reader = PcapReader::new(capture.as_slice()).expect("header");
+        let first = reader.next_datagram().expect("read").expect("datagram");
+        assert_eq!(first.source, address("127.0.0.1:5001"));
+        assert_eq!(first.destination, address("127.0.0.1:5000"));
+        assert_eq!(first.payload, b"first");
+        let second = reader.next_datagram().expect("read").expect("datagram");
+        assert_eq!(second.payload, b"second");
+        assert!(second.timestamp >= first.timestamp);
+        assert_eq!(reader.next_datagram().expect("read"), None);
+    }
+}
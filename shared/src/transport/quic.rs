@@ -0,0 +1,600 @@
+use crate::core::envelope::{EnvelopeHeader, MessageKind};
+use crate::transport::{PacketReceiver, PacketSender, TransportError};
+use bytes::Bytes;
+use quinn::crypto::rustls::{NoInitialCipherSuite, QuicClientConfig, QuicServerConfig};
+use quinn::{
+    ClientConfig, Connection, Endpoint, SendDatagramError, ServerConfig, TransportConfig, VarInt,
+};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer, ServerName, UnixTime};
+use rustls::{CertificateError, DigitallySignedStruct, SignatureScheme};
+use std::cell::Cell;
+use std::io::ErrorKind;
+use std::net::SocketAddr;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+const ALPN_PROTOCOL: &[u8] = b"tbdp";
+const SERVER_NAME: &str = "localhost";
+const LENGTH_PREFIX_BYTES: usize = 2;
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(1);
+const CLOSE_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Debug)]
+pub enum QuicError {
+    Io(std::io::Error),
+    Tls(rustls::Error),
+    Certificate(rcgen::Error),
+    NoInitialCipherSuite,
+    Connect(quinn::ConnectError),
+    Connection(quinn::ConnectionError),
+}
+
+impl From<std::io::Error> for QuicError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<rustls::Error> for QuicError {
+    fn from(error: rustls::Error) -> Self {
+        Self::Tls(error)
+    }
+}
+
+impl From<rcgen::Error> for QuicError {
+    fn from(error: rcgen::Error) -> Self {
+        Self::Certificate(error)
+    }
+}
+
+impl From<NoInitialCipherSuite> for QuicError {
+    fn from(_: NoInitialCipherSuite) -> Self {
+        Self::NoInitialCipherSuite
+    }
+}
+
+impl From<quinn::ConnectError> for QuicError {
+    fn from(error: quinn::ConnectError) -> Self {
+        Self::Connect(error)
+    }
+}
+
+impl From<quinn::ConnectionError> for QuicError {
+    fn from(error: quinn::ConnectionError) -> Self {
+        Self::Connection(error)
+    }
+}
+
+impl std::fmt::Display for QuicError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QuicError::Io(error) => write!(formatter, "quic io error: {error}"),
+            QuicError::Tls(error) => write!(formatter, "quic tls error: {error}"),
+            QuicError::Certificate(error) => write!(formatter, "quic certificate error: {error}"),
+            QuicError::NoInitialCipherSuite => {
+                write!(
+                    formatter,
+                    "quic tls configuration has no initial cipher suite"
+                )
+            }
+            QuicError::Connect(error) => write!(formatter, "quic connect failed: {error}"),
+            QuicError::Connection(error) => write!(formatter, "quic connection failed: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for QuicError {}
+
+pub struct QuicIdentity {
+    certificate: CertificateDer<'static>,
+    private_key: PrivatePkcs8KeyDer<'static>,
+}
+
+impl QuicIdentity {
+    pub fn generate() -> Result<Self, QuicError> {
+        let certified = rcgen::generate_simple_self_signed(vec![SERVER_NAME.to_string()])?;
+        Ok(Self {
+            certificate: certified.cert.der().clone(),
+            private_key: PrivatePkcs8KeyDer::from(certified.key_pair.serialize_der()),
+        })
+    }
+
+    pub fn fingerprint(&self) -> [u8; 32] {
+        certificate_fingerprint(&self.certificate)
+    }
+}
+
+pub fn format_fingerprint(fingerprint: &[u8; 32]) -> String {
+    fingerprint
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+pub fn parse_fingerprint(value: &str) -> Option<[u8; 32]> {
+    if value.len() != 64 || !value.is_ascii() {
+        return None;
+    }
+    let mut fingerprint = [0_u8; 32];
+    for (index, byte) in fingerprint.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&value[index * 2..index * 2 + 2], 16).ok()?;
+    }
+    Some(fingerprint)
+}
+
+fn certificate_fingerprint(certificate: &CertificateDer<'_>) -> [u8; 32] {
+    let digest = ring::digest::digest(&ring::digest::SHA256, certificate.as_ref());
+    let mut fingerprint = [0_u8; 32];
+    fingerprint.copy_from_slice(digest.as_ref());
+    fingerprint
+}
+
+#[derive(Debug)]
+struct PinnedCertificate {
+    fingerprint: [u8; 32],
+    provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for PinnedCertificate {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        if certificate_fingerprint(end_entity) == self.fingerprint {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::InvalidCertificate(
+                CertificateError::ApplicationVerificationFailure,
+            ))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        certificate: &CertificateDer<'_>,
+        signature: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            certificate,
+            signature,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        certificate: &CertificateDer<'_>,
+        signature: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            certificate,
+            signature,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+pub struct QuicListener {
+    endpoint: Endpoint,
+    runtime: Runtime,
+}
+
+impl QuicListener {
+    pub fn bind(local_addr: SocketAddr, identity: &QuicIdentity) -> Result<Self, QuicError> {
+        let runtime = new_runtime()?;
+        let mut crypto = rustls::ServerConfig::builder_with_provider(Arc::new(
+            rustls::crypto::ring::default_provider(),
+        ))
+        .with_protocol_versions(&[&rustls::version::TLS13])?
+        .with_no_client_auth()
+        .with_single_cert(
+            vec![identity.certificate.clone()],
+            PrivateKeyDer::Pkcs8(identity.private_key.clone_key()),
+        )?;
+        crypto.alpn_protocols = vec![ALPN_PROTOCOL.to_vec()];
+        let mut server_config =
+            ServerConfig::with_crypto(Arc::new(QuicServerConfig::try_from(crypto)?));
+        server_config.transport_config(transport_config());
+
+        let endpoint = {
+            let _guard = runtime.enter();
+            Endpoint::server(server_config, local_addr)?
+        };
+        Ok(Self { endpoint, runtime })
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr, QuicError> {
+        Ok(self.endpoint.local_addr()?)
+    }
+
+    pub fn accept(self) -> Result<QuicTransport, QuicError> {
+        let connection = self.runtime.block_on(async {
+            let incoming = self
+                .endpoint
+                .accept()
+                .await
+                .ok_or_else(|| std::io::Error::from(ErrorKind::NotConnected))?;
+            Ok::<_, QuicError>(incoming.await?)
+        })?;
+        Ok(QuicTransport::start(
+            self.runtime,
+            self.endpoint,
+            connection,
+        ))
+    }
+}
+
+pub struct QuicTransport {
+    connection: Connection,
+    endpoint: Endpoint,
+    control: UnboundedSender<Vec<u8>>,
+    incoming: Receiver<Vec<u8>>,
+    read_timeout: Cell<Option<Duration>>,
+    nonblocking: Cell<bool>,
+    runtime: Runtime,
+}
+
+impl QuicTransport {
+    pub fn connect(
+        local_addr: SocketAddr,
+        remote_addr: SocketAddr,
+        fingerprint: [u8; 32],
+    ) -> Result<Self, QuicError> {
+        let runtime = new_runtime()?;
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let mut crypto = rustls::ClientConfig::builder_with_provider(provider.clone())
+            .with_protocol_versions(&[&rustls::version::TLS13])?
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(PinnedCertificate {
+                fingerprint,
+                provider,
+            }))
+            .with_no_client_auth();
+        crypto.alpn_protocols = vec![ALPN_PROTOCOL.to_vec()];
+        let mut client_config = ClientConfig::new(Arc::new(QuicClientConfig::try_from(crypto)?));
+        client_config.transport_config(transport_config());
+
+        let mut endpoint = {
+            let _guard = runtime.enter();
+            Endpoint::client(local_addr)?
+        };
+        endpoint.set_default_client_config(client_config);
+        let connection = runtime.block_on(async {
+            Ok::<_, QuicError>(endpoint.connect(remote_addr, SERVER_NAME)?.await?)
+        })?;
+        Ok(Self::start(runtime, endpoint, connection))
+    }
+
+    fn start(runtime: Runtime, endpoint: Endpoint, connection: Connection) -> Self {
+        let (incoming_sender, incoming) = mpsc::channel();
+        let (control, outgoing_control) = unbounded_channel();
+        runtime.spawn(read_datagrams(connection.clone(), incoming_sender.clone()));
+        runtime.spawn(read_control(connection.clone(), incoming_sender));
+        runtime.spawn(write_control(connection.clone(), outgoing_control));
+        Self {
+            connection,
+            endpoint,
+            control,
+            incoming,
+            read_timeout: Cell::new(None),
+            nonblocking: Cell::new(false),
+            runtime,
+        }
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr, TransportError> {
+        Ok(self.endpoint.local_addr()?)
+    }
+
+    pub fn remote_addr(&self) -> SocketAddr {
+        self.connection.remote_address()
+    }
+
+    pub fn max_datagram_bytes(&self) -> Option<usize> {
+        self.connection.max_datagram_size()
+    }
+
+    pub fn rtt(&self) -> Duration {
+        self.connection.rtt()
+    }
+
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> Result<(), TransportError> {
+        if timeout == Some(Duration::ZERO) {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidInput,
+                "cannot set a 0 duration timeout",
+            )
+            .into());
+        }
+        self.read_timeout.set(timeout);
+        Ok(())
+    }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> Result<(), TransportError> {
+        self.nonblocking.set(nonblocking);
+        Ok(())
+    }
+}
+
+impl Drop for QuicTransport {
+    fn drop(&mut self) {
+        self.connection.close(VarInt::from_u32(0), b"closed");
+        let endpoint = self.endpoint.clone();
+        self.runtime.block_on(async move {
+            let _ = tokio::time::timeout(CLOSE_TIMEOUT, endpoint.wait_idle()).await;
+        });
+    }
+}
+
+impl PacketSender for QuicTransport {
+    fn send(&mut self, packet: &[u8]) -> Result<usize, TransportError> {
+        if !travels_as_datagram(packet) {
+            if packet.len() > usize::from(u16::MAX) {
+                return Err(std::io::Error::from_raw_os_error(libc::EMSGSIZE).into());
+            }
+            self.control
+                .send(packet.to_vec())
+                .map_err(|_| TransportError::Io(ErrorKind::NotConnected.into()))?;
+            return Ok(packet.len());
+        }
+
+        self.connection
+            .send_datagram(Bytes::copy_from_slice(packet))
+            .map_err(|error| match error {
+                SendDatagramError::TooLarge => std::io::Error::from_raw_os_error(libc::EMSGSIZE),
+                SendDatagramError::UnsupportedByPeer | SendDatagramError::Disabled => {
+                    ErrorKind::Unsupported.into()
+                }
+                SendDatagramError::ConnectionLost(_) => ErrorKind::NotConnected.into(),
+            })?;
+        Ok(packet.len())
+    }
+}
+
+impl PacketReceiver for QuicTransport {
+    fn receive(&mut self, buffer: &mut [u8]) -> Result<usize, TransportError> {
+        let packet = if self.nonblocking.get() {
+            self.incoming.try_recv().map_err(|error| match error {
+                TryRecvError::Empty => ErrorKind::WouldBlock,
+                TryRecvError::Disconnected => ErrorKind::NotConnected,
+            })
+        } else {
+            match self.read_timeout.get() {
+                Some(timeout) => self
+                    .incoming
+                    .recv_timeout(timeout)
+                    .map_err(|error| match error {
+                        RecvTimeoutError::Timeout => ErrorKind::WouldBlock,
+                        RecvTimeoutError::Disconnected => ErrorKind::NotConnected,
+                    }),
+                None => self.incoming.recv().map_err(|_| ErrorKind::NotConnected),
+            }
+        }
+        .map_err(|kind| TransportError::Io(kind.into()))?;
+
+        let length = packet.len().min(buffer.len());
+        buffer[..length].copy_from_slice(&packet[..length]);
+        Ok(length)
+    }
+}
+
+fn new_runtime() -> Result<Runtime, QuicError> {
+    Ok(tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .enable_all()
+        .build()?)
+}
+
+fn transport_config() -> Arc<TransportConfig> {
+    let mut config = TransportConfig::default();
+    config.keep_alive_interval(Some(KEEP_ALIVE_INTERVAL));
+    Arc::new(config)
+}
+
+fn travels_as_datagram(packet: &[u8]) -> bool {
+    EnvelopeHeader::decode(packet).map_or(true, |envelope| envelope.kind != MessageKind::Control)
+}
+
+async fn read_datagrams(connection: Connection, incoming: Sender<Vec<u8>>) {
+    while let Ok(datagram) = connection.read_datagram().await {
+        if incoming.send(datagram.to_vec()).is_err() {
+            return;
+        }
+    }
+}
+
+async fn read_control(connection: Connection, incoming: Sender<Vec<u8>>) {
+    let Ok(mut stream) = connection.accept_uni().await else {
+        return;
+    };
+    let mut prefix = [0_u8; LENGTH_PREFIX_BYTES];
+    while stream.read_exact(&mut prefix).await.is_ok() {
+        let mut message = vec![0_u8; usize::from(u16::from_be_bytes(prefix))];
+        if stream.read_exact(&mut message).await.is_err() || incoming.send(message).is_err() {
+            return;
+        }
+    }
+}
+
+async fn write_control(connection: Connection, mut outgoing: UnboundedReceiver<Vec<u8>>) {
+    let Ok(mut stream) = connection.open_uni().await else {
+        return;
+    };
+    while let Some(message) = outgoing.recv().await {
+        let mut framed = Vec::with_capacity(LENGTH_PREFIX_BYTES + message.len());
+        framed.extend_from_slice(&(message.len() as u16).to_be_bytes());
+        framed.extend_from_slice(&message);
+        if stream.write_all(&framed).await.is_err() {
+            return;
+        }
+    }
+    let _ = stream.finish();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        format_fingerprint, parse_fingerprint, travels_as_datagram, PinnedCertificate,
+        QuicIdentity, QuicListener, QuicTransport,
+    };
+    use crate::core::control::ControlMessage;
+    use crate::core::fec::FecScheme;
+    use crate::core::healthcheck::{HealthcheckKind, HealthcheckPacket};
+    use crate::core::packet_codec::{encode_packet, VIDEO_PACKET_OVERHEAD};
+    use crate::core::packetizer::{Packetization, Packetizer, PacketizerConfig};
+    use crate::core::reassembler::FrameReassembler;
+    use crate::core::sequence::SequenceNumber;
+    use crate::transport::demux::{Demultiplexer, Message};
+    use crate::transport::{PacketReceiver, PacketSender};
+    use rustls::client::danger::ServerCertVerifier;
+    use rustls::pki_types::{ServerName, UnixTime};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn fingerprints_round_trip_through_hex() {
+        let identity = QuicIdentity::generate().expect("identity");
+        let formatted = format_fingerprint(&identity.fingerprint());
+        assert_eq!(formatted.len(), 64);
+        assert_eq!(parse_fingerprint(&formatted), Some(identity.fingerprint()));
+        assert_eq!(parse_fingerprint(&formatted[2..]), None);
+        assert_eq!(parse_fingerprint(&"zz".repeat(32)), None);
+    }
+
+    #[test]
+    fn pinned_verifier_accepts_only_the_expected_certificate() {
+        let identity = QuicIdentity::generate().expect("identity");
+        let other = QuicIdentity::generate().expect("identity");
+        let verify = |fingerprint| {
+            PinnedCertificate {
+                fingerprint,
+                provider: Arc::new(rustls::crypto::ring::default_provider()),
+            }
+            .verify_server_cert(
+                &identity.certificate,
+                &[],
+                &ServerName::try_from("localhost").expect("name"),
+                &[],
+                UnixTime::now(),
+            )
+            .is_ok()
+        };
+
+        assert!(verify(identity.fingerprint()));
+        assert!(!verify(other.fingerprint()));
+        assert!(!verify([0; 32]));
+    }
+
+    #[test]
+    fn only_control_messages_use_the_reliable_stream() {
+        let ping = HealthcheckPacket {
+            kind: HealthcheckKind::Ping,
+            timestamp_nanos: 1,
+        };
+        assert!(!travels_as_datagram(
+            &ControlMessage::KeyframeRequest.encode()
+        ));
+        assert!(travels_as_datagram(&ping.encode()));
+        assert!(travels_as_datagram(b"garbage"));
+    }
+
+    #[test]
+    #[ignore = "requires UDP socket access, enable explicitly when allowed"]
+    fn host_and_client_pipelines_run_over_loopback() {
+        let identity = QuicIdentity::generate().expect("identity");
+        let fingerprint = identity.fingerprint();
+        let listener = QuicListener::bind("127.0.0.1:0".parse().unwrap(), &identity).expect("bind");
+        let client_address = listener.local_addr().expect("address");
+
+        let host = std::thread::spawn(move || {
+            let mut host =
+                QuicTransport::connect("127.0.0.1:0".parse().unwrap(), client_address, fingerprint)
+                    .expect("connect");
+            let max_payload_bytes =
+                host.max_datagram_bytes().expect("datagrams") - VIDEO_PACKET_OVERHEAD;
+            let mut packetizer = Packetizer::new(
+                PacketizerConfig {
+                    max_payload_bytes,
+                    fec: FecScheme::XorParity { group_size: 4 },
+                    packetization: Packetization::Fixed,
+                },
+                SequenceNumber::new(0),
+            );
+            for frame_identifier in 0..10_u32 {
+                let payload = vec![frame_identifier as u8; 3000];
+                for packet in packetizer
+                    .packetize(frame_identifier, 0, &payload)
+                    .expect("packetize")
+                {
+                    host.send(&encode_packet(&packet)).expect("send");
+                }
+            }
+            host.set_read_timeout(Some(Duration::from_secs(5)))
+                .expect("timeout");
+            let mut buffer = [0_u8; 64];
+            let received = host.receive(&mut buffer).expect("receive");
+            buffer[..received].to_vec()
+        });
+
+        let client = listener.accept().expect("accept");
+        client
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .expect("timeout");
+        let mut receiver = Demultiplexer::new(client, 2048);
+        let mut reassembler = FrameReassembler::new(16);
+        let mut frames = Vec::new();
+        while frames.len() < 10 {
+            if let Message::Video(packet) = receiver.receive().expect("receive") {
+                if let Some(frame) = reassembler.push_packet(packet).expect("push") {
+                    frames.push(frame);
+                }
+            }
+        }
+        receiver
+            .send(&ControlMessage::KeyframeRequest.encode())
+            .expect("send");
+
+        let feedback = host.join().expect("host thread");
+        assert_eq!(
+            ControlMessage::decode(&feedback).expect("decode"),
+            ControlMessage::KeyframeRequest
+        );
+        for (frame_identifier, frame) in frames.iter().enumerate() {
+            assert_eq!(frame.frame_identifier, frame_identifier as u32);
+            assert_eq!(frame.payload, vec![frame_identifier as u8; 3000]);
+        }
+    }
+
+    #[test]
+    #[ignore = "requires UDP socket access, enable explicitly when allowed"]
+    fn connect_rejects_an_unexpected_certificate() {
+        let identity = QuicIdentity::generate().expect("identity");
+        let listener = QuicListener::bind("127.0.0.1:0".parse().unwrap(), &identity).expect("bind");
+        let client_address = listener.local_addr().expect("address");
+        let accepted = std::thread::spawn(move || listener.accept().is_ok());
+
+        let result =
+            QuicTransport::connect("127.0.0.1:0".parse().unwrap(), client_address, [0; 32]);
+        assert!(result.is_err());
+        assert!(!accepted.join().expect("listener thread"));
+    }
+}
@@ -0,0 +1,321 @@
+use crate::core::decode_order::{DecodeOrder, OrderedFrame};
+use crate::core::reassembler::{EvictedFrame, FrameReassembler, ReassemblyError};
+use crate::transport::demux::{demultiplex, DemuxError, Message};
+use crate::transport::pcap::CapturedDatagram;
+use std::time::{Duration, Instant};
+
+const FINISH_HORIZON: Duration = Duration::from_secs(3600);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplaySpeed {
+    Scaled(f64),
+    Unpaced,
+}
+
+impl Default for ReplaySpeed {
+    fn default() -> Self {
+        ReplaySpeed::Scaled(1.0)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ReplayPacer {
+    speed: ReplaySpeed,
+    first_capture: Option<Duration>,
+    started: Instant,
+}
+
+impl ReplayPacer {
+    pub fn new(speed: ReplaySpeed) -> Self {
+        Self {
+            speed,
+            first_capture: None,
+            started: Instant::now(),
+        }
+    }
+
+    pub fn wait_time(&mut self, capture_timestamp: Duration, now: Instant) -> Duration {
+        let ReplaySpeed::Scaled(factor) = self.speed else {
+            return Duration::ZERO;
+        };
+        let Some(first_capture) = self.first_capture else {
+            self.first_capture = Some(capture_timestamp);
+            self.started = now;
+            return Duration::ZERO;
+        };
+        let offset = capture_timestamp.saturating_sub(first_capture);
+        let due = self.started + offset.div_f64(factor);
+        due.saturating_duration_since(now)
+    }
+}
+
+#[derive(Debug)]
+pub enum ReplayEvent {
+    Frame(OrderedFrame),
+    Dropped(EvictedFrame),
+    Rejected(DemuxError),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReplayStats {
+    pub datagrams: u64,
+    pub video_packets: u64,
+    pub other_messages: u64,
+    pub rejected: u64,
+    pub frames_released: u64,
+    pub frames_dropped: u64,
+}
+
+#[derive(Debug)]
+pub struct Replay {
+    reassembler: FrameReassembler,
+    decode_order: DecodeOrder,
+    origin: Instant,
+    first_capture: Option<Duration>,
+    last_capture: Duration,
+    stats: ReplayStats,
+}
+
+impl Replay {
+    pub fn new(reassembler: FrameReassembler, decode_order: DecodeOrder) -> Self {
+        Self {
+            reassembler,
+            decode_order,
+            origin: Instant::now(),
+            first_capture: None,
+            last_capture: Duration::ZERO,
+            stats: ReplayStats::default(),
+        }
+    }
+
+    pub fn push(&mut self, datagram: &CapturedDatagram) -> Vec<ReplayEvent> {
+        self.stats.datagrams += 1;
+        let first_capture = *self.first_capture.get_or_insert(datagram.timestamp);
+        self.last_capture = self.last_capture.max(datagram.timestamp);
+        let now = self.origin + datagram.timestamp.saturating_sub(first_capture);
+
+        let mut events = Vec::new();
+        self.reassembler.expire(now);
+        match demultiplex(&datagram.payload) {
+            Ok(Message::Video(packet)) => {
+                self.stats.video_packets += 1;
+                match self.reassembler.push_packet_at(packet, now) {
+                    Ok(Some(frame)) => self.decode_order.push(frame, now),
+                    Ok(None) => {}
+                    Err(ReassemblyError::InvalidChunkIndex) => {}
+                    Err(ReassemblyError::InconsistentChunkCount) => {}
+                }
+            }
+            Ok(_) => self.stats.other_messages += 1,
+            Err(error) => {
+                self.stats.rejected += 1;
+                events.push(ReplayEvent::Rejected(error));
+            }
+        }
+        self.release(now, &mut events);
+        events
+    }
+
+    pub fn finish(&mut self) -> Vec<ReplayEvent> {
+        let first_capture = self.first_capture.unwrap_or(self.last_capture);
+        let now = self.origin + (self.last_capture - first_capture) + FINISH_HORIZON;
+        let mut events = Vec::new();
+        self.reassembler.expire(now);
+        self.release(now, &mut events);
+        events
+    }
+
+    pub fn stats(&self) -> ReplayStats {
+        self.stats
+    }
+
+    pub fn reassembler(&self) -> &FrameReassembler {
+        &self.reassembler
+    }
+
+    pub fn decode_order(&self) -> &DecodeOrder {
+        &self.decode_order
+    }
+
+    fn release(&mut self, now: Instant, events: &mut Vec<ReplayEvent>) {
+        self.reassembler.take_partial_frames();
+        for evicted in self.reassembler.take_evicted() {
+            self.stats.frames_dropped += 1;
+            self.decode_order.give_up(evicted.frame_identifier);
+            events.push(ReplayEvent::Dropped(evicted));
+        }
+        while let Some(ordered) = self.decode_order.pop(now) {
+            self.stats.frames_released += 1;
+            self.reassembler
+                .supersede_older_than(ordered.frame.frame_identifier);
+            events.push(ReplayEvent::Frame(ordered));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Replay, ReplayEvent, ReplayPacer, ReplaySpeed};
+    use crate::core::decode_order::{DecodeOrder, DecodeOrderConfig};
+    use crate::core::fec::FecScheme;
+    use crate::core::healthcheck::{HealthcheckKind, HealthcheckPacket};
+    use crate::core::packet::{VideoPacket, VideoPacketHeader};
+    use crate::core::packet_codec::encode_packet;
+    use crate::core::reassembler::FrameReassembler;
+    use crate::core::sequence::SequenceNumber;
+    use crate::transport::pcap::{CapturedDatagram, PcapReader, PcapWriter};
+    use std::net::SocketAddr;
+    use std::time::{Duration, Instant};
+
+    fn chunk(frame_identifier: u32, chunk_index: u16, chunk_count: u16, payload: &[u8]) -> Vec<u8> {
+        encode_packet(&VideoPacket {
+            header: VideoPacketHeader {
+                sequence_number: SequenceNumber::new(frame_identifier * 4 + u32::from(chunk_index)),
+                frame_identifier,
+                chunk_index,
+                chunks_total: chunk_count,
+                timestamp_nanos: u64::from(frame_identifier) * 16_000_000,
+                fec: FecScheme::None,
+            },
+            payload: payload.to_vec(),
+        })
+    }
+
+    fn datagram(millis: u64, payload: Vec<u8>) -> CapturedDatagram {
+        let host: SocketAddr = "192.168.0.1:5001".parse().unwrap();
+        let client: SocketAddr = "192.168.0.2:5000".parse().unwrap();
+        CapturedDatagram {
+            timestamp: Duration::from_secs(1_700_000_000) + Duration::from_millis(millis),
+            source: host,
+            destination: client,
+            payload,
+        }
+    }
+
+    fn replay_all(datagrams: &[CapturedDatagram]) -> Vec<String> {
+        let reassembler = FrameReassembler::new(8).with_frame_deadline(Duration::from_millis(100));
+        let mut replay = Replay::new(reassembler, DecodeOrder::new(DecodeOrderConfig::default()));
+        let mut events = Vec::new();
+        for datagram in datagrams {
+            events.extend(replay.push(datagram));
+        }
+        events.extend(replay.finish());
+        events
+            .into_iter()
+            .map(|event| match event {
+                ReplayEvent::Frame(ordered) => format!(
+                    "frame {} {:?} after {} missing",
+                    ordered.frame.frame_identifier, ordered.frame.payload, ordered.missing_before
+                ),
+                ReplayEvent::Dropped(evicted) => {
+                    format!("dropped {} ({})", evicted.frame_identifier, evicted.reason)
+                }
+                ReplayEvent::Rejected(error) => format!("rejected: {error}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn replays_frames_using_capture_timing() {
+        let ping = HealthcheckPacket {
+            kind: HealthcheckKind::Ping,
+            timestamp_nanos: 1,
+        };
+        let datagrams = vec![
+            datagram(0, chunk(1, 0, 2, &[1, 1])),
+            datagram(1, ping.encode().to_vec()),
+            datagram(2, chunk(1, 1, 2, &[1, 2])),
+            datagram(16, chunk(2, 0, 2, &[2, 1])),
+            datagram(300, chunk(3, 0, 1, &[3, 1])),
+            datagram(301, b"garbage".to_vec()),
+        ];
+
+        let events = replay_all(&datagrams);
+        assert_eq!(events.len(), 4);
+        assert_eq!(events[0], "frame 1 [1, 1, 1, 2] after 0 missing");
+        assert_eq!(events[1], "dropped 2 (deadline)");
+        assert_eq!(events[2], "frame 3 [3, 1] after 1 missing");
+        assert!(events[3].starts_with("rejected: "));
+    }
+
+    #[test]
+    fn replay_of_a_pcap_round_trip_is_deterministic() {
+        let host: SocketAddr = "192.168.0.1:5001".parse().unwrap();
+        let client: SocketAddr = "192.168.0.2:5000".parse().unwrap();
+        let mut writer = PcapWriter::new(Vec::new()).expect("header");
+        for frame_identifier in 0..20_u32 {
+            for chunk_index in 0..3_u16 {
+                if frame_identifier % 7 == 3 && chunk_index == 1 {
+                    continue;
+                }
+                let millis = u64::from(frame_identifier) * 16 + u64::from(chunk_index);
+                writer
+                    .write_datagram(
+                        Duration::from_millis(millis),
+                        host,
+                        client,
+                        &chunk(frame_identifier, chunk_index, 3, &[frame_identifier as u8]),
+                    )
+                    .expect("write");
+            }
+        }
+        let capture = writer.into_inner();
+
+        let read_all = || {
+            let mut reader = PcapReader::new(capture.as_slice()).expect("header");
+            let mut datagrams = Vec::new();
+            while let Some(datagram) = reader.next_datagram().expect("read") {
+                datagrams.push(datagram);
+            }
+            datagrams
+        };
+        let first = replay_all(&read_all());
+        let second = replay_all(&read_all());
+
+        assert_eq!(first, second);
+        assert_eq!(
+            first
+                .iter()
+                .filter(|event| event.starts_with("dropped"))
+                .count(),
+            3
+        );
+        assert_eq!(
+            first
+                .iter()
+                .filter(|event| event.starts_with("frame"))
+                .count(),
+            17
+        );
+    }
+
+    #[test]
+    fn pacer_scales_capture_offsets() {
+        let start = Instant::now();
+        let capture = Duration::from_secs(100);
+
+        let mut original = ReplayPacer::new(ReplaySpeed::default());
+        assert_eq!(original.wait_time(capture, start), Duration::ZERO);
+        assert_eq!(
+            original.wait_time(capture + Duration::from_millis(40), start),
+            Duration::from_millis(40)
+        );
+
+        let mut doubled = ReplayPacer::new(ReplaySpeed::Scaled(2.0));
+        doubled.wait_time(capture, start);
+        assert_eq!(
+            doubled.wait_time(
+                capture + Duration::from_millis(40),
+                start + Duration::from_millis(5)
+            ),
+            Duration::from_millis(15)
+        );
+
+        let mut unpaced = ReplayPacer::new(ReplaySpeed::Unpaced);
+        unpaced.wait_time(capture, start);
+        assert_eq!(
+            unpaced.wait_time(capture + Duration::from_secs(5), start),
+            Duration::ZERO
+        );
+    }
+}
@@ -0,0 +1,499 @@
+use crate::core::envelope::{EnvelopeHeader, MessageKind, ENVELOPE_HEADER_LENGTH};
+use crate::core::packet::VideoPacketHeader;
+use crate::core::packet_codec::VIDEO_PACKET_OVERHEAD;
+use crate::core::sequence::serial_distance;
+use crate::transport::batch::ReceiveBatch;
+use crate::transport::{PacketReceiver, PacketSender, TransportError};
+use std::collections::VecDeque;
+use std::io::{ErrorKind, IoSlice, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+
+const LENGTH_PREFIX_BYTES: usize = 2;
+const MAX_FRAMED_PACKET_BYTES: usize = u16::MAX as usize;
+const READ_CHUNK_BYTES: usize = 64 * 1024;
+const MAX_WRITE_SLICES: usize = 64;
+const STARTED_FRAMES_TRACKED: usize = 4;
+const CONTROL_HEADROOM_BYTES: usize = 16 * 1024;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TcpStats {
+    pub packets_sent: u64,
+    pub packets_dropped: u64,
+    pub frames_dropped: u64,
+}
+
+#[derive(Debug)]
+struct QueuedPacket {
+    frame_identifier: Option<u32>,
+    bytes: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct TcpTransport {
+    stream: TcpStream,
+    inbound: Vec<u8>,
+    inbound_start: usize,
+    outbound: VecDeque<QueuedPacket>,
+    written: usize,
+    queued_bytes: usize,
+    max_queued_bytes: usize,
+    newest_frame: Option<u32>,
+    started_frames: VecDeque<u32>,
+    truncated_frame: Option<u32>,
+    stats: TcpStats,
+}
+
+impl TcpTransport {
+    pub fn new(stream: TcpStream) -> Result<Self, TransportError> {
+        stream.set_nodelay(true)?;
+        Ok(Self {
+            stream,
+            inbound: Vec::new(),
+            inbound_start: 0,
+            outbound: VecDeque::new(),
+            written: 0,
+            queued_bytes: 0,
+            max_queued_bytes: 256 * 1024,
+            newest_frame: None,
+            started_frames: VecDeque::with_capacity(STARTED_FRAMES_TRACKED),
+            truncated_frame: None,
+            stats: TcpStats::default(),
+        })
+    }
+
+    pub fn connect(remote_addr: SocketAddr) -> Result<Self, TransportError> {
+        Self::new(TcpStream::connect(remote_addr)?)
+    }
+
+    pub fn with_max_queued_bytes(mut self, max_queued_bytes: usize) -> Self {
+        self.max_queued_bytes = max_queued_bytes;
+        self
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr, TransportError> {
+        Ok(self.stream.local_addr()?)
+    }
+
+    pub fn peer_addr(&self) -> Result<SocketAddr, TransportError> {
+        Ok(self.stream.peer_addr()?)
+    }
+
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> Result<(), TransportError> {
+        self.stream.set_read_timeout(timeout)?;
+        Ok(())
+    }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> Result<(), TransportError> {
+        self.stream.set_nonblocking(nonblocking)?;
+        Ok(())
+    }
+
+    pub fn queued_bytes(&self) -> usize {
+        self.queued_bytes
+    }
+
+    pub fn stats(&self) -> TcpStats {
+        self.stats
+    }
+
+    pub fn flush(&mut self) -> Result<(), TransportError> {
+        while let Some(front) = self.outbound.front() {
+            let mut slices = Vec::with_capacity(self.outbound.len().min(MAX_WRITE_SLICES));
+            slices.push(IoSlice::new(&front.bytes[self.written..]));
+            slices.extend(
+                self.outbound
+                    .iter()
+                    .skip(1)
+                    .take(MAX_WRITE_SLICES - 1)
+                    .map(|queued| IoSlice::new(&queued.bytes)),
+            );
+            match self.stream.write_vectored(&slices) {
+                Ok(0) => return Err(std::io::Error::from(ErrorKind::WriteZero).into()),
+                Ok(written) => self.consume_written(written),
+                Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                Err(error) if error.kind() == ErrorKind::WouldBlock => return Ok(()),
+                Err(error) => return Err(error.into()),
+            }
+        }
+        Ok(())
+    }
+
+    fn consume_written(&mut self, mut written: usize) {
+        while let Some(front) = self.outbound.front() {
+            if let Some(frame_identifier) = front.frame_identifier {
+                if written > 0 && !self.started_frames.contains(&frame_identifier) {
+                    if self.started_frames.len() == STARTED_FRAMES_TRACKED {
+                        self.started_frames.pop_front();
+                    }
+                    self.started_frames.push_back(frame_identifier);
+                }
+            }
+            let remaining = front.bytes.len() - self.written;
+            if written < remaining {
+                self.written += written;
+                return;
+            }
+            written -= remaining;
+            self.queued_bytes -= front.bytes.len();
+            self.written = 0;
+            self.outbound.pop_front();
+            self.stats.packets_sent += 1;
+        }
+    }
+
+    fn enqueue(&mut self, packet: &[u8]) -> Result<(), TransportError> {
+        if packet.len() > MAX_FRAMED_PACKET_BYTES {
+            return Err(std::io::Error::from_raw_os_error(libc::EMSGSIZE).into());
+        }
+
+        let frame_identifier = video_frame_identifier(packet);
+        if let Some(frame_identifier) = frame_identifier {
+            if self
+                .newest_frame
+                .is_none_or(|newest| serial_distance(newest, frame_identifier) > 0)
+            {
+                self.newest_frame = Some(frame_identifier);
+            }
+        }
+        if frame_identifier.is_some() && frame_identifier == self.truncated_frame {
+            self.stats.packets_dropped += 1;
+            return Ok(());
+        }
+
+        let framed_bytes = LENGTH_PREFIX_BYTES + packet.len();
+        let limit = match frame_identifier {
+            Some(_) => self.max_queued_bytes,
+            None => self.max_queued_bytes + CONTROL_HEADROOM_BYTES,
+        };
+        if self.queued_bytes + framed_bytes > limit {
+            let newest_frame = self.newest_frame;
+            self.drop_frames(|queued| is_stale_frame(Some(queued), newest_frame));
+            if is_stale_frame(frame_identifier, self.newest_frame) {
+                self.stats.packets_dropped += 1;
+                return Ok(());
+            }
+        }
+        if self.queued_bytes + framed_bytes > limit {
+            self.stats.packets_dropped += 1;
+            if let Some(frame_identifier) = frame_identifier {
+                self.truncated_frame = Some(frame_identifier);
+                if self.drop_frames(|queued| queued == frame_identifier) == 0 {
+                    self.stats.frames_dropped += 1;
+                }
+            }
+            return Ok(());
+        }
+
+        let mut bytes = Vec::with_capacity(framed_bytes);
+        bytes.extend_from_slice(&(packet.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(packet);
+        self.queued_bytes += bytes.len();
+        self.outbound.push_back(QueuedPacket {
+            frame_identifier,
+            bytes,
+        });
+        Ok(())
+    }
+
+    fn drop_frames(&mut self, mut should_drop: impl FnMut(u32) -> bool) -> usize {
+        let mut dropped_frames = Vec::new();
+        let mut kept = VecDeque::with_capacity(self.outbound.len());
+        for queued in self.outbound.drain(..) {
+            let droppable = queued.frame_identifier.is_some_and(|frame_identifier| {
+                !self.started_frames.contains(&frame_identifier) && should_drop(frame_identifier)
+            });
+            if !droppable {
+                kept.push_back(queued);
+                continue;
+            }
+            self.queued_bytes -= queued.bytes.len();
+            self.stats.packets_dropped += 1;
+            if let Some(frame_identifier) = queued.frame_identifier {
+                if !dropped_frames.contains(&frame_identifier) {
+                    dropped_frames.push(frame_identifier);
+                }
+            }
+        }
+        self.outbound = kept;
+        self.stats.frames_dropped += dropped_frames.len() as u64;
+        dropped_frames.len()
+    }
+
+    fn take_buffered(&mut self, buffer: &mut [u8]) -> Option<usize> {
+        let available = &self.inbound[self.inbound_start..];
+        if available.len() < LENGTH_PREFIX_BYTES {
+            return None;
+        }
+        let length = usize::from(u16::from_be_bytes([available[0], available[1]]));
+        let framed_bytes = LENGTH_PREFIX_BYTES + length;
+        if available.len() < framed_bytes {
+            return None;
+        }
+        let copied = length.min(buffer.len());
+        buffer[..copied]
+            .copy_from_slice(&available[LENGTH_PREFIX_BYTES..LENGTH_PREFIX_BYTES + copied]);
+        self.inbound_start += framed_bytes;
+        Some(copied)
+    }
+
+    fn read_more(&mut self) -> Result<(), TransportError> {
+        if self.inbound_start > 0 {
+            self.inbound.drain(..self.inbound_start);
+            self.inbound_start = 0;
+        }
+        let filled = self.inbound.len();
+        self.inbound.resize(filled + READ_CHUNK_BYTES, 0);
+        let result = self.stream.read(&mut self.inbound[filled..]);
+        self.inbound
+            .truncate(filled + *result.as_ref().unwrap_or(&0));
+        match result {
+            Ok(0) => Err(std::io::Error::from(ErrorKind::NotConnected).into()),
+            Ok(_) => Ok(()),
+            Err(error) => Err(error.into()),
+        }
+    }
+}
+
+fn is_stale_frame(frame_identifier: Option<u32>, newest_frame: Option<u32>) -> bool {
+    match (frame_identifier, newest_frame) {
+        (Some(frame_identifier), Some(newest)) => serial_distance(frame_identifier, newest) > 0,
+        _ => false,
+    }
+}
+
+fn video_frame_identifier(packet: &[u8]) -> Option<u32> {
+    let envelope = EnvelopeHeader::decode(packet).ok()?;
+    if envelope.kind != MessageKind::Video || packet.len() < VIDEO_PACKET_OVERHEAD {
+        return None;
+    }
+    let header =
+        VideoPacketHeader::decode(&packet[ENVELOPE_HEADER_LENGTH..VIDEO_PACKET_OVERHEAD]).ok()?;
+    Some(header.frame_identifier)
+}
+
+impl PacketSender for TcpTransport {
+    fn send(&mut self, packet: &[u8]) -> Result<usize, TransportError> {
+        self.enqueue(packet)?;
+        self.flush()?;
+        Ok(packet.len())
+    }
+
+    fn send_batch(&mut self, packets: &[&[u8]]) -> Result<usize, TransportError> {
+        for (queued, packet) in packets.iter().enumerate() {
+            if let Err(error) = self.enqueue(packet) {
+                if queued == 0 {
+                    return Err(error);
+                }
+                self.flush()?;
+                return Ok(queued);
+            }
+        }
+        self.flush()?;
+        Ok(packets.len())
+    }
+}
+
+impl PacketReceiver for TcpTransport {
+    fn receive(&mut self, buffer: &mut [u8]) -> Result<usize, TransportError> {
+        self.flush()?;
+        loop {
+            if let Some(received) = self.take_buffered(buffer) {
+                return Ok(received);
+            }
+            match self.read_more() {
+                Ok(()) => {}
+                Err(TransportError::Io(error)) if error.kind() == ErrorKind::Interrupted => {}
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    fn receive_batch(&mut self, batch: &mut ReceiveBatch) -> Result<usize, TransportError> {
+        batch.clear();
+        let Some(slot) = batch.spare_slots_mut().next() else {
+            return Ok(0);
+        };
+        let received = self.receive(slot)?;
+        batch.push_received(received);
+        while batch.len() < batch.capacity() {
+            let slot = batch
+                .spare_slots_mut()
+                .next()
+                .expect("batch has a spare slot");
+            let Some(received) = self.take_buffered(slot) else {
+                break;
+            };
+            batch.push_received(received);
+        }
+        Ok(batch.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{video_frame_identifier, TcpTransport};
+    use crate::core::control::ControlMessage;
+    use crate::core::fec::FecScheme;
+    use crate::core::packet::{VideoPacket, VideoPacketHeader};
+    use crate::core::packet_codec::encode_packet;
+    use crate::core::sequence::SequenceNumber;
+    use crate::transport::batch::ReceiveBatch;
+    use crate::transport::{PacketReceiver, PacketSender};
+    use std::net::TcpListener;
+    use std::time::Duration;
+
+    const CHUNKS_PER_FRAME: u16 = 4;
+
+    fn video(frame_identifier: u32, chunk_index: u16, payload_bytes: usize) -> Vec<u8> {
+        encode_packet(&VideoPacket {
+            header: VideoPacketHeader {
+                sequence_number: SequenceNumber::new(
+                    frame_identifier * u32::from(CHUNKS_PER_FRAME) + u32::from(chunk_index),
+                ),
+                frame_identifier,
+                chunk_index,
+                chunks_total: CHUNKS_PER_FRAME,
+                timestamp_nanos: 0,
+                fec: FecScheme::None,
+            },
+            payload: vec![frame_identifier as u8; payload_bytes],
+        })
+    }
+
+    fn connected_pair() -> (TcpTransport, TcpTransport) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let sender = TcpTransport::connect(listener.local_addr().expect("addr")).expect("connect");
+        let (stream, _) = listener.accept().expect("accept");
+        (sender, TcpTransport::new(stream).expect("receiver"))
+    }
+
+    #[test]
+    fn finds_frame_identifiers_of_video_packets_only() {
+        assert_eq!(video_frame_identifier(&video(42, 1, 10)), Some(42));
+        assert_eq!(
+            video_frame_identifier(&ControlMessage::KeyframeRequest.encode()),
+            None
+        );
+        assert_eq!(video_frame_identifier(b"garbage"), None);
+    }
+
+    #[test]
+    #[ignore = "requires TCP socket access, enable explicitly when allowed"]
+    fn length_prefix_keeps_packet_boundaries() {
+        let (mut sender, mut receiver) = connected_pair();
+        receiver
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .expect("timeout");
+        let packets: Vec<Vec<u8>> = vec![
+            b"one".to_vec(),
+            Vec::new(),
+            vec![9_u8; 60_000],
+            b"four".to_vec(),
+        ];
+        let slices: Vec<&[u8]> = packets.iter().map(Vec::as_slice).collect();
+        sender.send(slices[0]).expect("send");
+        assert_eq!(sender.send_batch(&slices[1..]).expect("send"), 3);
+        assert!(sender
+            .send(&vec![0_u8; 70_000])
+            .expect_err("too large")
+            .is_message_too_large());
+
+        let mut buffer = vec![0_u8; 65_536];
+        for packet in &packets[..2] {
+            let received = receiver.receive(&mut buffer).expect("receive");
+            assert_eq!(&buffer[..received], packet.as_slice());
+        }
+        let mut batch = ReceiveBatch::new(4, 65_536);
+        let mut received = Vec::new();
+        while received.len() < 2 {
+            receiver.receive_batch(&mut batch).expect("receive");
+            received.extend(batch.iter().map(<[u8]>::to_vec));
+        }
+        assert_eq!(received, packets[2..]);
+
+        drop(sender);
+        let error = receiver.receive(&mut buffer).expect_err("closed");
+        assert!(!error.is_transient());
+    }
+
+    #[test]
+    #[ignore = "requires TCP socket access, enable explicitly when allowed"]
+    fn drops_whole_stale_frames_when_the_socket_backs_up() {
+        let (sender, mut receiver) = connected_pair();
+        let mut sender = sender.with_max_queued_bytes(64 * 1024);
+        sender.set_nonblocking(true).expect("nonblocking");
+
+        let mut frames_sent = 0_u32;
+        while sender.stats().frames_dropped < 5 {
+            for chunk_index in 0..CHUNKS_PER_FRAME {
+                sender
+                    .send(&video(frames_sent, chunk_index, 8_000))
+                    .expect("send");
+            }
+            sender
+                .send(&ControlMessage::KeyframeRequest.encode())
+                .expect("send");
+            frames_sent += 1;
+            assert!(frames_sent < 100_000, "socket never backed up");
+        }
+
+        receiver
+            .set_read_timeout(Some(Duration::from_millis(200)))
+            .expect("timeout");
+        let mut chunks = vec![0_u16; frames_sent as usize];
+        let mut control_messages = 0;
+        let mut buffer = vec![0_u8; 65_536];
+        loop {
+            match receiver.receive(&mut buffer) {
+                Ok(received) => match video_frame_identifier(&buffer[..received]) {
+                    Some(frame_identifier) => chunks[frame_identifier as usize] += 1,
+                    None => control_messages += 1,
+                },
+                Err(error) if error.is_transient() => {
+                    if sender.queued_bytes() == 0 {
+                        break;
+                    }
+                    sender.flush().expect("flush");
+                }
+                Err(error) => panic!("receive failed: {error}"),
+            }
+        }
+
+        assert_eq!(control_messages, frames_sent);
+        assert!(chunks
+            .iter()
+            .all(|&count| count == 0 || count == CHUNKS_PER_FRAME));
+        let dropped = chunks.iter().filter(|&&count| count == 0).count() as u64;
+        assert_eq!(dropped, sender.stats().frames_dropped);
+        assert_eq!(chunks.last(), Some(&CHUNKS_PER_FRAME));
+    }
+
+    #[test]
+    #[ignore = "requires TCP socket access, enable explicitly when allowed"]
+    fn queue_stays_bounded_while_the_peer_stalls() {
+        let (sender, _receiver) = connected_pair();
+        let max_queued_bytes = 64 * 1024;
+        let mut sender = sender.with_max_queued_bytes(max_queued_bytes);
+        sender.set_nonblocking(true).expect("nonblocking");
+
+        for frame_identifier in 0..2_000_u32 {
+            for chunk_index in 0..CHUNKS_PER_FRAME {
+                sender
+                    .send(&video(frame_identifier, chunk_index, 6_000))
+                    .expect("send");
+                assert!(sender.queued_bytes() <= max_queued_bytes);
+            }
+        }
+        let stats = sender.stats();
+        assert!(stats.frames_dropped > 0, "socket never backed up");
+
+        for _ in 0..10_000 {
+            sender
+                .send(&ControlMessage::KeyframeRequest.encode())
+                .expect("send");
+            assert!(sender.queued_bytes() <= max_queued_bytes + super::CONTROL_HEADROOM_BYTES);
+        }
+        assert!(sender.stats().packets_dropped > stats.packets_dropped);
+    }
+}
@@ -1,16 +1,30 @@
+#[cfg(target_os = "linux")]
+use crate::transport::batch::ReceiveBatch;
 use crate::transport::{PacketReceiver, PacketSender, TransportError};
 use std::net::{SocketAddr, UdpSocket};
+use std::os::fd::AsRawFd;
 use std::time::Duration;
 
+#[cfg(target_os = "linux")]
+const MAX_MESSAGES_PER_CALL: usize = 1024;
+#[cfg(target_os = "linux")]
+const MAX_SEGMENTS_PER_DATAGRAM: usize = 64;
+#[cfg(target_os = "linux")]
+const MAX_SEGMENTED_DATAGRAM_BYTES: usize = 65_507;
+
 #[derive(Debug)]
 pub struct UdpTransport {
     socket: UdpSocket,
+    segmentation_offload: bool,
 }
 
 impl UdpTransport {
     pub fn bind(local_addr: SocketAddr) -> Result<Self, TransportError> {
         let socket = UdpSocket::bind(local_addr)?;
-        Ok(Self { socket })
+        Ok(Self {
+            socket,
+            segmentation_offload: false,
+        })
     }
 
     pub fn connect(self, remote_addr: SocketAddr) -> Result<Self, TransportError> {
@@ -27,6 +41,37 @@ impl UdpTransport {
         Ok(())
     }
 
+    pub fn set_nonblocking(&self, nonblocking: bool) -> Result<(), TransportError> {
+        self.socket.set_nonblocking(nonblocking)?;
+        Ok(())
+    }
+
+    pub fn set_dont_fragment(&self, dont_fragment: bool) -> Result<(), TransportError> {
+        let (level, name, value) = dont_fragment_option(self.socket.local_addr()?, dont_fragment)?;
+        let result = unsafe {
+            libc::setsockopt(
+                self.socket.as_raw_fd(),
+                level,
+                name,
+                &value as *const libc::c_int as *const libc::c_void,
+                std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+            )
+        };
+        if result != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(())
+    }
+
+    pub fn enable_segmentation_offload(&mut self) -> bool {
+        self.segmentation_offload = segmentation_offload_supported(&self.socket);
+        self.segmentation_offload
+    }
+
+    pub fn segmentation_offload(&self) -> bool {
+        self.segmentation_offload
+    }
+
     pub fn send_to(
         &mut self,
         packet: &[u8],
@@ -40,23 +85,297 @@ impl UdpTransport {
     }
 }
 
+#[cfg(target_os = "linux")]
+fn dont_fragment_option(
+    local_addr: SocketAddr,
+    dont_fragment: bool,
+) -> std::io::Result<(libc::c_int, libc::c_int, libc::c_int)> {
+    Ok(match local_addr {
+        SocketAddr::V4(_) => (
+            libc::IPPROTO_IP,
+            libc::IP_MTU_DISCOVER,
+            if dont_fragment {
+                libc::IP_PMTUDISC_DO
+            } else {
+                libc::IP_PMTUDISC_DONT
+            },
+        ),
+        SocketAddr::V6(_) => (
+            libc::IPPROTO_IPV6,
+            libc::IPV6_DONTFRAG,
+            libc::c_int::from(dont_fragment),
+        ),
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn dont_fragment_option(
+    local_addr: SocketAddr,
+    dont_fragment: bool,
+) -> std::io::Result<(libc::c_int, libc::c_int, libc::c_int)> {
+    match local_addr {
+        SocketAddr::V4(_) => Ok((
+            libc::IPPROTO_IP,
+            libc::IP_DONTFRAG,
+            libc::c_int::from(dont_fragment),
+        )),
+        SocketAddr::V6(_) => Err(std::io::ErrorKind::Unsupported.into()),
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn dont_fragment_option(
+    _local_addr: SocketAddr,
+    _dont_fragment: bool,
+) -> std::io::Result<(libc::c_int, libc::c_int, libc::c_int)> {
+    Err(std::io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(target_os = "linux")]
+fn segmentation_offload_supported(socket: &UdpSocket) -> bool {
+    let mut value: libc::c_int = 0;
+    let mut length = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+    let result = unsafe {
+        libc::getsockopt(
+            socket.as_raw_fd(),
+            libc::SOL_UDP,
+            libc::UDP_SEGMENT,
+            &mut value as *mut libc::c_int as *mut libc::c_void,
+            &mut length,
+        )
+    };
+    result == 0
+}
+
+#[cfg(not(target_os = "linux"))]
+fn segmentation_offload_supported(_socket: &UdpSocket) -> bool {
+    false
+}
+
+#[cfg(target_os = "linux")]
+fn segment_groups(packets: &[&[u8]], segmentation_offload: bool) -> Vec<std::ops::Range<usize>> {
+    let mut groups = Vec::new();
+    let mut start = 0;
+    while start < packets.len() {
+        let segment_bytes = packets[start].len();
+        let mut end = start + 1;
+        let mut total_bytes = segment_bytes;
+        if segmentation_offload && segment_bytes > 0 {
+            while end < packets.len()
+                && end - start < MAX_SEGMENTS_PER_DATAGRAM
+                && packets[end].len() <= segment_bytes
+                && !packets[end].is_empty()
+                && total_bytes + packets[end].len() <= MAX_SEGMENTED_DATAGRAM_BYTES
+            {
+                total_bytes += packets[end].len();
+                end += 1;
+                if packets[end - 1].len() < segment_bytes {
+                    break;
+                }
+            }
+        }
+        groups.push(start..end);
+        start = end;
+    }
+    groups
+}
+
+#[cfg(target_os = "linux")]
+fn send_messages(
+    socket: &UdpSocket,
+    packets: &[&[u8]],
+    segmentation_offload: bool,
+) -> std::io::Result<usize> {
+    let groups = segment_groups(packets, segmentation_offload);
+    let mut iovecs: Vec<libc::iovec> = packets
+        .iter()
+        .map(|packet| libc::iovec {
+            iov_base: packet.as_ptr() as *mut libc::c_void,
+            iov_len: packet.len(),
+        })
+        .collect();
+    let control_bytes =
+        unsafe { libc::CMSG_SPACE(std::mem::size_of::<u16>() as libc::c_uint) } as usize;
+    let mut control = vec![[0_u64; 4]; groups.len()];
+    debug_assert!(control_bytes <= std::mem::size_of::<[u64; 4]>());
+
+    let mut messages: Vec<libc::mmsghdr> = Vec::with_capacity(groups.len());
+    for (group, control) in groups.iter().zip(control.iter_mut()) {
+        let mut header: libc::msghdr = unsafe { std::mem::zeroed() };
+        header.msg_iov = iovecs[group.start..].as_mut_ptr();
+        header.msg_iovlen = group.len() as _;
+        if group.len() > 1 {
+            header.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+            header.msg_controllen = control_bytes as _;
+            unsafe {
+                let cmsg = libc::CMSG_FIRSTHDR(&header);
+                (*cmsg).cmsg_level = libc::SOL_UDP;
+                (*cmsg).cmsg_type = libc::UDP_SEGMENT;
+                (*cmsg).cmsg_len = libc::CMSG_LEN(std::mem::size_of::<u16>() as libc::c_uint) as _;
+                let segment_bytes = packets[group.start].len() as u16;
+                std::ptr::write_unaligned(libc::CMSG_DATA(cmsg) as *mut u16, segment_bytes);
+            }
+        }
+        messages.push(libc::mmsghdr {
+            msg_hdr: header,
+            msg_len: 0,
+        });
+    }
+
+    let mut messages_sent = 0;
+    while messages_sent < messages.len() {
+        let count = (messages.len() - messages_sent).min(MAX_MESSAGES_PER_CALL);
+        let result = unsafe {
+            libc::sendmmsg(
+                socket.as_raw_fd(),
+                messages[messages_sent..].as_mut_ptr(),
+                count as libc::c_uint,
+                0,
+            )
+        };
+        if result < 0 {
+            if messages_sent == 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            break;
+        }
+        messages_sent += result as usize;
+        if (result as usize) < count {
+            break;
+        }
+    }
+    Ok(groups[..messages_sent]
+        .iter()
+        .map(|group| group.len())
+        .sum())
+}
+
+#[cfg(target_os = "linux")]
+fn receive_messages(socket: &UdpSocket, batch: &mut ReceiveBatch) -> std::io::Result<usize> {
+    batch.clear();
+    let mut iovecs: Vec<libc::iovec> = batch
+        .spare_slots_mut()
+        .map(|slot| libc::iovec {
+            iov_base: slot.as_mut_ptr() as *mut libc::c_void,
+            iov_len: slot.len(),
+        })
+        .collect();
+    let mut messages: Vec<libc::mmsghdr> = iovecs
+        .iter_mut()
+        .map(|iovec| {
+            let mut header: libc::msghdr = unsafe { std::mem::zeroed() };
+            header.msg_iov = iovec;
+            header.msg_iovlen = 1;
+            libc::mmsghdr {
+                msg_hdr: header,
+                msg_len: 0,
+            }
+        })
+        .collect();
+
+    let result = unsafe {
+        libc::recvmmsg(
+            socket.as_raw_fd(),
+            messages.as_mut_ptr(),
+            messages.len() as libc::c_uint,
+            libc::MSG_WAITFORONE,
+            std::ptr::null_mut(),
+        )
+    };
+    if result < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    let received = result as usize;
+    for message in &messages[..received] {
+        batch.push_received(message.msg_len as usize);
+    }
+    Ok(received)
+}
+
 impl PacketSender for UdpTransport {
     fn send(&mut self, packet: &[u8]) -> Result<usize, TransportError> {
         Ok(self.socket.send(packet)?)
     }
+
+    #[cfg(target_os = "linux")]
+    fn send_batch(&mut self, packets: &[&[u8]]) -> Result<usize, TransportError> {
+        match send_messages(&self.socket, packets, self.segmentation_offload) {
+            Err(error) if self.segmentation_offload && error.raw_os_error() == Some(libc::EIO) => {
+                self.segmentation_offload = false;
+                Ok(send_messages(&self.socket, packets, false)?)
+            }
+            result => Ok(result?),
+        }
+    }
+
+    fn wait_writable(&mut self, timeout: Duration) -> Result<(), TransportError> {
+        let mut descriptor = libc::pollfd {
+            fd: self.socket.as_raw_fd(),
+            events: libc::POLLOUT,
+            revents: 0,
+        };
+        let timeout_millis = timeout.as_millis().clamp(1, libc::c_int::MAX as u128) as libc::c_int;
+        let result = unsafe { libc::poll(&mut descriptor, 1, timeout_millis) };
+        if result < 0 {
+            let error = std::io::Error::last_os_error();
+            if error.kind() != std::io::ErrorKind::Interrupted {
+                return Err(error.into());
+            }
+        }
+        Ok(())
+    }
 }
 
 impl PacketReceiver for UdpTransport {
     fn receive(&mut self, buffer: &mut [u8]) -> Result<usize, TransportError> {
         Ok(self.socket.recv(buffer)?)
     }
+
+    #[cfg(target_os = "linux")]
+    fn receive_batch(&mut self, batch: &mut ReceiveBatch) -> Result<usize, TransportError> {
+        Ok(receive_messages(&self.socket, batch)?)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::UdpTransport;
+    use crate::transport::batch::ReceiveBatch;
     use crate::transport::{PacketReceiver, PacketSender};
     use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+    use std::time::{Duration, Instant};
+
+    fn connected_pair() -> (UdpTransport, UdpTransport) {
+        let local = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0);
+        let sender = UdpTransport::bind(local).expect("bind sender");
+        let receiver = UdpTransport::bind(local).expect("bind receiver");
+        let sender_address = sender.local_addr().unwrap();
+        let receiver_address = receiver.local_addr().unwrap();
+        (
+            sender.connect(receiver_address).expect("connect sender"),
+            receiver.connect(sender_address).expect("connect receiver"),
+        )
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn segment_groups_follow_the_offload_rules() {
+        let full = [0_u8; 1000];
+        let short = [0_u8; 400];
+        let packets: Vec<&[u8]> = vec![&full, &full, &full, &short, &full, &short, &short];
+
+        assert_eq!(
+            super::segment_groups(&packets, true),
+            vec![0..4, 4..6, 6..7]
+        );
+        assert_eq!(super::segment_groups(&packets, false).len(), packets.len());
+
+        let many: Vec<&[u8]> = vec![&short; 100];
+        assert_eq!(super::segment_groups(&many, true), vec![0..64, 64..100]);
+        let large = [0_u8; 8000];
+        let jumbo: Vec<&[u8]> = vec![&large; 10];
+        assert_eq!(super::segment_groups(&jumbo, true), vec![0..8, 8..10]);
+    }
 
     #[test]
     #[ignore = "requires UDP socket access, enable explicitly when allowed"]
@@ -80,4 +399,114 @@ mod tests {
         let received = receiver.receive(&mut buffer).expect("receive");
         assert_eq!(&buffer[..received], payload);
     }
+
+    #[test]
+    #[ignore = "requires UDP socket access, enable explicitly when allowed"]
+    fn dont_fragment_jumbo_datagram_on_loopback() {
+        let local = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0);
+        let sender = UdpTransport::bind(local).expect("bind sender");
+        let receiver = UdpTransport::bind(local).expect("bind receiver");
+        sender.set_dont_fragment(true).expect("set dont fragment");
+
+        let receiver_address = receiver.local_addr().unwrap();
+        let mut sender = sender.connect(receiver_address).expect("connect sender");
+        let mut receiver = receiver;
+
+        let payload = vec![0x5A_u8; 8972];
+        sender.send(&payload).expect("send");
+
+        let mut buffer = vec![0_u8; 9000];
+        let received = receiver.receive(&mut buffer).expect("receive");
+        assert_eq!(received, payload.len());
+    }
+
+    #[test]
+    #[ignore = "requires UDP socket access, enable explicitly when allowed"]
+    fn batched_round_trip_keeps_datagram_boundaries() {
+        let (mut sender, mut receiver) = connected_pair();
+        receiver
+            .set_read_timeout(Some(Duration::from_millis(200)))
+            .expect("timeout");
+        sender.enable_segmentation_offload();
+
+        let packets: Vec<Vec<u8>> = (0..40_u8)
+            .map(|index| vec![index; if index == 39 { 300 } else { 1200 }])
+            .collect();
+        let slices: Vec<&[u8]> = packets.iter().map(Vec::as_slice).collect();
+        assert_eq!(sender.send_batch(&slices).expect("send"), packets.len());
+
+        let mut batch = ReceiveBatch::new(16, 2048);
+        let mut received = Vec::new();
+        while received.len() < packets.len() {
+            receiver.receive_batch(&mut batch).expect("receive");
+            received.extend(batch.iter().map(<[u8]>::to_vec));
+        }
+        assert_eq!(received, packets);
+
+        let error = receiver
+            .receive_batch(&mut batch)
+            .expect_err("nothing left");
+        assert!(error.is_transient());
+    }
+
+    #[test]
+    #[ignore = "measures loopback throughput; run with --release -- --ignored --nocapture"]
+    fn loopback_throughput_comparison() {
+        const PACKETS: usize = 200_000;
+        const PACKET_BYTES: usize = 1200;
+        const BATCH: usize = 64;
+
+        let payload = vec![0x5A_u8; PACKET_BYTES];
+        for (name, batched, segmentation_offload) in [
+            ("send/recv per packet", false, false),
+            ("sendmmsg/recvmmsg", true, false),
+            ("sendmmsg/recvmmsg + segmentation offload", true, true),
+        ] {
+            let (mut sender, mut receiver) = connected_pair();
+            receiver
+                .set_read_timeout(Some(Duration::from_millis(200)))
+                .expect("timeout");
+            let offload = segmentation_offload && sender.enable_segmentation_offload();
+
+            let drain = std::thread::spawn(move || {
+                let mut batch = ReceiveBatch::new(BATCH, 2048);
+                let mut received = 0;
+                loop {
+                    let result = if batched {
+                        receiver.receive_batch(&mut batch)
+                    } else {
+                        receiver.receive(&mut [0_u8; 2048]).map(|_| 1)
+                    };
+                    match result {
+                        Ok(count) => received += count,
+                        Err(_) => break received,
+                    }
+                }
+            });
+
+            let started = Instant::now();
+            let slices = vec![payload.as_slice(); BATCH];
+            let mut sent = 0;
+            while sent < PACKETS {
+                if batched {
+                    sent += sender.send_batch(&slices).unwrap_or(1);
+                } else {
+                    let _ = sender.send(&payload);
+                    sent += 1;
+                }
+            }
+            let elapsed = started.elapsed();
+            let received = drain.join().expect("drain thread");
+            println!(
+                "{name}{}: {:.0} packets/s sent, {:.0} Mbit/s, {received} of {sent} received",
+                if segmentation_offload && !offload {
+                    " (unsupported)"
+                } else {
+                    ""
+                },
+                sent as f64 / elapsed.as_secs_f64(),
+                (sent * PACKET_BYTES * 8) as f64 / elapsed.as_secs_f64() / 1e6
+            );
+        }
+    }
 }